@@ -0,0 +1,291 @@
+use ndc_sdk::models::{ComparisonTarget, ComparisonValue, Expression};
+use serde_json::Value;
+
+use super::filter::{
+    CONTAINS_OPERATOR, EQUAL_OPERATOR, GREATER_THAN_OPERATOR, GREATER_THAN_OR_EQUAL_OPERATOR,
+    IN_OPERATOR, LESS_THAN_OPERATOR, LESS_THAN_OR_EQUAL_OPERATOR, NOT_EQUAL_OPERATOR,
+};
+
+/// The columns of the messages collection that can be compared to a string in an activity query
+const STRING_COLUMNS: [&str; 5] = ["msg_id", "from_email", "to_email", "subject", "status"];
+
+/// The columns of the messages collection that the list endpoint does not return
+const UNRETURNED_COLUMNS: [&str; 2] = ["categories", "unique_args"];
+
+/// A query's `where` clause split into the part SendGrid evaluates and the rest
+#[derive(Clone, Debug, Default)]
+pub struct TranslatedPredicate {
+    /// The Email Activity query that filters the messages, if any of the predicate translated
+    pub query: Option<String>,
+    /// The conjuncts of the predicate that could not be translated, which must be evaluated
+    /// against the returned messages
+    pub residual: Option<Expression>,
+}
+
+/// Translates as much of a predicate on the messages collection as possible into SendGrid's
+/// Email Activity query language. Each conjunct of the predicate is either translated exactly or
+/// left in the residual predicate, so the two together select exactly the rows the predicate does.
+pub fn translate_predicate(predicate: &Expression) -> TranslatedPredicate {
+    let conjuncts = match predicate {
+        Expression::And { expressions } => expressions.iter().collect::<Vec<_>>(),
+        expression => vec![expression],
+    };
+
+    let mut queries = vec![];
+    let mut residual = vec![];
+    for conjunct in conjuncts {
+        match translate_expression(conjunct) {
+            Some(query) => queries.push(query),
+            None => residual.push(conjunct.clone()),
+        }
+    }
+
+    TranslatedPredicate {
+        query: (!queries.is_empty()).then(|| queries.join(" AND ")),
+        residual: match residual.len() {
+            0 => None,
+            1 => residual.pop(),
+            _ => Some(Expression::And {
+                expressions: residual,
+            }),
+        },
+    }
+}
+
+fn translate_expression(expression: &Expression) -> Option<String> {
+    match expression {
+        Expression::And { expressions } => translate_all(expressions, " AND "),
+        Expression::Or { expressions } => translate_all(expressions, " OR "),
+        Expression::BinaryComparisonOperator {
+            column:
+                ComparisonTarget::Column {
+                    name,
+                    field_path: None,
+                    path,
+                },
+            operator,
+            value: ComparisonValue::Scalar { value },
+        } if path.is_empty() => translate_comparison(name.as_str(), operator.as_str(), value),
+        _ => None,
+    }
+}
+
+/// Translates every expression and joins them by the separator in parentheses, or nothing if
+/// any of them cannot be translated
+fn translate_all(expressions: &[Expression], separator: &str) -> Option<String> {
+    if expressions.is_empty() {
+        return None;
+    }
+    let translated = expressions
+        .iter()
+        .map(translate_expression)
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("({})", translated.join(separator)))
+}
+
+fn translate_comparison(column: &str, operator: &str, value: &Value) -> Option<String> {
+    match (column, operator, value) {
+        (column, EQUAL_OPERATOR, Value::String(value)) if STRING_COLUMNS.contains(&column) => {
+            Some(format!("{column}={}", quote(value)))
+        }
+        (column, NOT_EQUAL_OPERATOR, Value::String(value)) if STRING_COLUMNS.contains(&column) => {
+            Some(format!("{column}!={}", quote(value)))
+        }
+        (column, IN_OPERATOR, Value::Array(values)) if STRING_COLUMNS.contains(&column) => {
+            let values = values
+                .iter()
+                .map(|value| value.as_str().map(quote))
+                .collect::<Option<Vec<_>>>()?;
+            (!values.is_empty()).then(|| format!("{column} IN ({})", values.join(",")))
+        }
+        ("last_event_time", operator, Value::String(timestamp)) => {
+            let operator = match operator {
+                EQUAL_OPERATOR => "=",
+                NOT_EQUAL_OPERATOR => "!=",
+                GREATER_THAN_OPERATOR => ">",
+                GREATER_THAN_OR_EQUAL_OPERATOR => ">=",
+                LESS_THAN_OPERATOR => "<",
+                LESS_THAN_OR_EQUAL_OPERATOR => "<=",
+                _ => return None,
+            };
+            Some(format!(
+                "last_event_time{operator}TIMESTAMP {}",
+                quote(timestamp)
+            ))
+        }
+        ("categories", CONTAINS_OPERATOR, Value::String(category)) => {
+            Some(format!("Contains(categories,{})", quote(category)))
+        }
+        ("unique_args", CONTAINS_OPERATOR, Value::Object(args)) if !args.is_empty() => {
+            let comparisons = args
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Value::String(value) => quote(value),
+                        Value::Number(number) => number.to_string(),
+                        _ => return None,
+                    };
+                    Some(format!(
+                        "unique_args['{}']={value}",
+                        key.replace('\'', "\\'")
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(format!("({})", comparisons.join(" AND ")))
+        }
+        _ => None,
+    }
+}
+
+/// The first column of a residual predicate that the list endpoint never returns, if any. Such a
+/// comparison can only be evaluated by SendGrid; evaluated against the returned messages it
+/// would silently match nothing.
+pub fn unreturned_column(residual: &Expression) -> Option<&'static str> {
+    match residual {
+        Expression::And { expressions } | Expression::Or { expressions } => {
+            expressions.iter().find_map(unreturned_column)
+        }
+        Expression::Not { expression } => unreturned_column(expression),
+        Expression::UnaryComparisonOperator { column, .. } => unreturned_target(column),
+        Expression::BinaryComparisonOperator { column, value, .. } => {
+            unreturned_target(column).or(match value {
+                ComparisonValue::Column { column } => unreturned_target(column),
+                _ => None,
+            })
+        }
+        Expression::Exists { .. } => None,
+    }
+}
+
+fn unreturned_target(target: &ComparisonTarget) -> Option<&'static str> {
+    let name = match target {
+        ComparisonTarget::Column { name, .. }
+        | ComparisonTarget::RootCollectionColumn { name, .. } => name,
+    };
+    UNRETURNED_COLUMNS
+        .into_iter()
+        .find(|column| *column == name.as_str())
+}
+
+/// Quotes a string literal for the activity query language
+pub fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use ndc_sdk::models::{ComparisonOperatorName, FieldName};
+    use serde_json::json;
+
+    use super::*;
+
+    fn compare(column: &str, operator: &str, value: Value) -> Expression {
+        Expression::BinaryComparisonOperator {
+            column: ComparisonTarget::Column {
+                name: FieldName::from(column),
+                field_path: None,
+                path: vec![],
+            },
+            operator: ComparisonOperatorName::from(operator),
+            value: ComparisonValue::Scalar { value },
+        }
+    }
+
+    #[test]
+    fn translatable_conjuncts_become_the_query_and_the_rest_the_residual() {
+        let untranslatable = compare("clicks_count", GREATER_THAN_OPERATOR, json!(0));
+        let translated = translate_predicate(&Expression::And {
+            expressions: vec![
+                compare("status", EQUAL_OPERATOR, json!("delivered")),
+                compare(
+                    "last_event_time",
+                    GREATER_THAN_OR_EQUAL_OPERATOR,
+                    json!("2024-01-01T00:00:00Z"),
+                ),
+                untranslatable.clone(),
+            ],
+        });
+        assert_eq!(
+            translated.query.as_deref(),
+            Some(r#"status="delivered" AND last_event_time>=TIMESTAMP "2024-01-01T00:00:00Z""#)
+        );
+        assert_eq!(translated.residual, Some(untranslatable));
+    }
+
+    #[test]
+    fn disjunctions_translate_only_if_every_branch_does() {
+        let translated = translate_predicate(&Expression::Or {
+            expressions: vec![
+                compare(
+                    "to_email",
+                    IN_OPERATOR,
+                    json!(["a@example.com", "b@example.com"]),
+                ),
+                compare("categories", CONTAINS_OPERATOR, json!("welcome")),
+            ],
+        });
+        assert_eq!(
+            translated.query.as_deref(),
+            Some(
+                r#"(to_email IN ("a@example.com","b@example.com") OR Contains(categories,"welcome"))"#
+            )
+        );
+        assert_eq!(translated.residual, None);
+
+        let predicate = Expression::Or {
+            expressions: vec![
+                compare("categories", CONTAINS_OPERATOR, json!("welcome")),
+                compare("clicks_count", GREATER_THAN_OPERATOR, json!(0)),
+            ],
+        };
+        let translated = translate_predicate(&predicate);
+        assert_eq!(translated.query, None);
+        assert_eq!(translated.residual, Some(predicate));
+    }
+
+    #[test]
+    fn unique_args_translate_strings_and_numbers_only() {
+        let translated = translate_predicate(&compare(
+            "unique_args",
+            CONTAINS_OPERATOR,
+            json!({ "campaign": "spring", "user_id": 42 }),
+        ));
+        assert_eq!(
+            translated.query.as_deref(),
+            Some(r#"(unique_args['campaign']="spring" AND unique_args['user_id']=42)"#)
+        );
+
+        let translated = translate_predicate(&compare(
+            "unique_args",
+            CONTAINS_OPERATOR,
+            json!({ "beta": true }),
+        ));
+        assert_eq!(translated.query, None);
+        assert_eq!(
+            translated.residual.as_ref().and_then(unreturned_column),
+            Some("unique_args")
+        );
+    }
+
+    #[test]
+    fn unreturned_columns_are_found_anywhere_in_the_residual() {
+        let residual = Expression::Not {
+            expression: Box::new(Expression::Or {
+                expressions: vec![
+                    compare("clicks_count", GREATER_THAN_OPERATOR, json!(0)),
+                    compare("categories", CONTAINS_OPERATOR, json!("welcome")),
+                ],
+            }),
+        };
+        assert_eq!(unreturned_column(&residual), Some("categories"));
+        assert_eq!(
+            unreturned_column(&compare("clicks_count", GREATER_THAN_OPERATOR, json!(0))),
+            None
+        );
+    }
+
+    #[test]
+    fn quote_escapes_backslashes_and_quotes() {
+        assert_eq!(quote(r#"say "hi" \o/"#), r#""say \"hi\" \\o/""#);
+    }
+}
@@ -0,0 +1,142 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use indexmap::IndexMap;
+use ndc_sdk::{
+    connector::{self, QueryError},
+    models::{Aggregate, AggregateFunctionName, FieldName},
+};
+use serde_json::Value;
+
+use super::filter::compare_values;
+
+pub const SUM_FUNCTION: &str = "sum";
+pub const AVG_FUNCTION: &str = "avg";
+pub const MIN_FUNCTION: &str = "min";
+pub const MAX_FUNCTION: &str = "max";
+
+/// Evaluates the aggregates requested by a query over the rows of a collection
+pub fn eval_aggregates(
+    aggregates: &IndexMap<FieldName, Aggregate>,
+    rows: &[IndexMap<String, Value>],
+) -> connector::Result<IndexMap<FieldName, Value>> {
+    aggregates
+        .iter()
+        .map(|(name, aggregate)| Ok((name.clone(), eval_aggregate(aggregate, rows)?)))
+        .collect()
+}
+
+fn eval_aggregate(
+    aggregate: &Aggregate,
+    rows: &[IndexMap<String, Value>],
+) -> connector::Result<Value> {
+    match aggregate {
+        Aggregate::StarCount {} => Ok(Value::from(rows.len())),
+        Aggregate::ColumnCount {
+            column,
+            field_path,
+            distinct,
+        } => {
+            let values = column_values(rows, column, field_path.as_deref());
+            let count = if *distinct {
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<BTreeSet<_>>()
+                    .len()
+            } else {
+                values.len()
+            };
+            Ok(Value::from(count))
+        }
+        Aggregate::SingleColumn {
+            column,
+            field_path,
+            function,
+        } => eval_aggregate_function(
+            function,
+            &column_values(rows, column, field_path.as_deref()),
+        ),
+    }
+}
+
+fn eval_aggregate_function(
+    function: &AggregateFunctionName,
+    values: &[Value],
+) -> connector::Result<Value> {
+    match function.as_str() {
+        SUM_FUNCTION => {
+            // Integer columns sum to an integer, as declared on the Int scalar type
+            match values.iter().map(Value::as_i64).collect::<Option<Vec<_>>>() {
+                Some(integers) => Ok(Value::from(integers.into_iter().sum::<i64>())),
+                None => Ok(Value::from(
+                    numbers(function, values)?.into_iter().sum::<f64>(),
+                )),
+            }
+        }
+        AVG_FUNCTION => {
+            let numbers = numbers(function, values)?;
+            if numbers.is_empty() {
+                return Ok(Value::Null);
+            }
+            Ok(Value::from(
+                numbers.iter().sum::<f64>() / numbers.len() as f64,
+            ))
+        }
+        MIN_FUNCTION => Ok(extreme(values, Ordering::Less)),
+        MAX_FUNCTION => Ok(extreme(values, Ordering::Greater)),
+        unknown_function => Err(QueryError::new_unsupported_operation(&format!(
+            "Unknown aggregate function: {unknown_function}"
+        ))
+        .into()),
+    }
+}
+
+/// The non-null values of a column across the rows
+fn column_values(
+    rows: &[IndexMap<String, Value>],
+    column: &FieldName,
+    field_path: Option<&[FieldName]>,
+) -> Vec<Value> {
+    rows.iter()
+        .map(|row| {
+            let value = row.get(column.as_str()).cloned().unwrap_or(Value::Null);
+            field_path
+                .into_iter()
+                .flatten()
+                .fold(value, |value, field_name| {
+                    value
+                        .get(field_name.as_str())
+                        .cloned()
+                        .unwrap_or(Value::Null)
+                })
+        })
+        .filter(|value| !value.is_null())
+        .collect()
+}
+
+fn numbers(function: &AggregateFunctionName, values: &[Value]) -> connector::Result<Vec<f64>> {
+    values
+        .iter()
+        .map(|value| {
+            value.as_f64().ok_or_else(|| {
+                QueryError::new_invalid_request(&format!(
+                    "The {function} aggregate function can only be applied to numeric columns"
+                ))
+                .into()
+            })
+        })
+        .collect()
+}
+
+/// The smallest or largest value, or null if there are no values
+fn extreme(values: &[Value], wanted: Ordering) -> Value {
+    values
+        .iter()
+        .fold(None, |extreme: Option<&Value>, value| match extreme {
+            Some(current) if compare_values(value, current) != Some(wanted) => Some(current),
+            _ => Some(value),
+        })
+        .cloned()
+        .unwrap_or(Value::Null)
+}
@@ -0,0 +1,306 @@
+use serde::Serialize;
+
+/// Whether the address itself should be considered undeliverable. A hard bounce means the
+/// address should not be mailed again; a soft bounce means the address is fine but this delivery
+/// failed, for example because of the sender's reputation or a full mailbox.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BounceSeverity {
+    Hard,
+    Soft,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BounceCategory {
+    InvalidMailbox,
+    InvalidDomain,
+    MailboxDisabled,
+    MailboxFull,
+    MessageTooLarge,
+    Policy,
+    Reputation,
+    SpamContent,
+    Authentication,
+    RateLimited,
+    TemporaryFailure,
+    Unknown,
+}
+
+impl BounceCategory {
+    /// The severity implied by the category, if the category settles it
+    fn severity(self) -> Option<BounceSeverity> {
+        match self {
+            BounceCategory::InvalidMailbox
+            | BounceCategory::InvalidDomain
+            | BounceCategory::MailboxDisabled => Some(BounceSeverity::Hard),
+            BounceCategory::MailboxFull
+            | BounceCategory::MessageTooLarge
+            | BounceCategory::Policy
+            | BounceCategory::Reputation
+            | BounceCategory::SpamContent
+            | BounceCategory::Authentication
+            | BounceCategory::RateLimited
+            | BounceCategory::TemporaryFailure => Some(BounceSeverity::Soft),
+            BounceCategory::Unknown => None,
+        }
+    }
+
+    /// Whether a more specific category may be found in the reason's wording
+    fn is_generic(self) -> bool {
+        matches!(
+            self,
+            BounceCategory::Policy | BounceCategory::TemporaryFailure | BounceCategory::Unknown
+        )
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct BounceClassification {
+    pub severity: BounceSeverity,
+    pub category: BounceCategory,
+    pub enhanced_status_code: Option<String>,
+    pub smtp_code: Option<u16>,
+}
+
+/// Phrases that receiving servers commonly use, checked in order against the lowercased reason
+const PHRASES: &[(&str, BounceCategory)] = &[
+    ("mailbox full", BounceCategory::MailboxFull),
+    ("mailbox is full", BounceCategory::MailboxFull),
+    ("over quota", BounceCategory::MailboxFull),
+    ("quota exceeded", BounceCategory::MailboxFull),
+    ("exceeded storage", BounceCategory::MailboxFull),
+    ("insufficient storage", BounceCategory::MailboxFull),
+    ("user unknown", BounceCategory::InvalidMailbox),
+    ("unknown user", BounceCategory::InvalidMailbox),
+    ("no such user", BounceCategory::InvalidMailbox),
+    ("does not exist", BounceCategory::InvalidMailbox),
+    ("recipient not found", BounceCategory::InvalidMailbox),
+    ("invalid recipient", BounceCategory::InvalidMailbox),
+    ("address rejected", BounceCategory::InvalidMailbox),
+    ("no mailbox", BounceCategory::InvalidMailbox),
+    ("mailbox unavailable", BounceCategory::InvalidMailbox),
+    ("domain not found", BounceCategory::InvalidDomain),
+    ("host not found", BounceCategory::InvalidDomain),
+    ("host unknown", BounceCategory::InvalidDomain),
+    ("no mx", BounceCategory::InvalidDomain),
+    ("name or service not known", BounceCategory::InvalidDomain),
+    ("nxdomain", BounceCategory::InvalidDomain),
+    ("unrouteable", BounceCategory::InvalidDomain),
+    ("account disabled", BounceCategory::MailboxDisabled),
+    ("account has been disabled", BounceCategory::MailboxDisabled),
+    ("mailbox disabled", BounceCategory::MailboxDisabled),
+    ("inactive", BounceCategory::MailboxDisabled),
+    ("suspended", BounceCategory::MailboxDisabled),
+    ("too large", BounceCategory::MessageTooLarge),
+    ("size limit", BounceCategory::MessageTooLarge),
+    ("spamhaus", BounceCategory::Reputation),
+    ("blacklist", BounceCategory::Reputation),
+    ("blocklist", BounceCategory::Reputation),
+    ("reputation", BounceCategory::Reputation),
+    ("barracuda", BounceCategory::Reputation),
+    ("rbl", BounceCategory::Reputation),
+    ("spf", BounceCategory::Authentication),
+    ("dkim", BounceCategory::Authentication),
+    ("dmarc", BounceCategory::Authentication),
+    ("authenticat", BounceCategory::Authentication),
+    ("spam", BounceCategory::SpamContent),
+    ("content rejected", BounceCategory::SpamContent),
+    ("rate limit", BounceCategory::RateLimited),
+    ("too many", BounceCategory::RateLimited),
+    ("throttl", BounceCategory::RateLimited),
+    ("try again later", BounceCategory::TemporaryFailure),
+    ("temporarily", BounceCategory::TemporaryFailure),
+    ("timed out", BounceCategory::TemporaryFailure),
+    ("policy", BounceCategory::Policy),
+    ("not allowed", BounceCategory::Policy),
+    ("relay", BounceCategory::Policy),
+];
+
+/// Classifies the reason a receiving server gave for a bounce or block, using the enhanced
+/// status code (RFC 3463) where there is one and the wording of the reason otherwise
+pub fn classify(reason: &str, status: Option<&str>) -> BounceClassification {
+    let enhanced_status_code = status
+        .and_then(find_enhanced_status_code)
+        .or_else(|| find_enhanced_status_code(reason));
+    let smtp_code = find_smtp_code(reason);
+
+    let code_category = enhanced_status_code
+        .as_deref()
+        .map_or(BounceCategory::Unknown, category_for_enhanced_code);
+    let phrase_category = category_for_phrase(&reason.to_lowercase());
+    let category = match phrase_category {
+        Some(phrase_category)
+            if code_category == BounceCategory::Unknown
+                || (code_category.is_generic() && !phrase_category.is_generic()) =>
+        {
+            phrase_category
+        }
+        _ => code_category,
+    };
+
+    let class = enhanced_status_code
+        .as_deref()
+        .and_then(|code| code.chars().next())
+        .or_else(|| smtp_code.and_then(|code| code.to_string().chars().next()));
+    let severity = category.severity().unwrap_or(match class {
+        Some('5') => BounceSeverity::Hard,
+        Some('4') => BounceSeverity::Soft,
+        _ => BounceSeverity::Unknown,
+    });
+
+    BounceClassification {
+        severity,
+        category,
+        enhanced_status_code,
+        smtp_code,
+    }
+}
+
+fn category_for_enhanced_code(code: &str) -> BounceCategory {
+    let mut parts = code.splitn(3, '.');
+    let (class, subject, detail) = (parts.next(), parts.next(), parts.next());
+    match (class, subject, detail) {
+        (_, Some("1"), Some("1" | "3")) => BounceCategory::InvalidMailbox,
+        (_, Some("1"), Some("2" | "10")) => BounceCategory::InvalidDomain,
+        (_, Some("1"), Some("6")) | (_, Some("2"), Some("1")) => BounceCategory::MailboxDisabled,
+        (_, Some("2"), Some("2")) => BounceCategory::MailboxFull,
+        (_, Some("2"), Some("3")) | (_, Some("3"), Some("4")) => BounceCategory::MessageTooLarge,
+        (Some("5"), Some("4"), Some("4")) => BounceCategory::InvalidDomain,
+        (Some("4"), Some("7"), Some("28")) => BounceCategory::RateLimited,
+        (_, Some("7"), Some("23" | "25" | "26" | "27")) => BounceCategory::Authentication,
+        (_, Some("7"), _) => BounceCategory::Policy,
+        (Some("4"), _, _) => BounceCategory::TemporaryFailure,
+        _ => BounceCategory::Unknown,
+    }
+}
+
+fn category_for_phrase(reason: &str) -> Option<BounceCategory> {
+    PHRASES
+        .iter()
+        .find_map(|(phrase, category)| contains_word_prefix(reason, phrase).then_some(*category))
+}
+
+/// Whether the phrase occurs in the text starting at a word boundary, so that short phrases such
+/// as "rbl" do not match inside longer words
+fn contains_word_prefix(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(index, _)| {
+        !matches!(text[..index].chars().next_back(), Some(previous) if previous.is_alphanumeric())
+    })
+}
+
+/// Finds the first token shaped like an enhanced status code, such as 5.1.1 or 4.7.28
+fn find_enhanced_status_code(text: &str) -> Option<String> {
+    text.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map(|token| token.trim_matches('.'))
+        .find(|token| {
+            let parts = token.split('.').collect::<Vec<_>>();
+            parts.len() == 3
+                && matches!(parts[0], "2" | "4" | "5")
+                && parts[1..].iter().all(|part| {
+                    (1..=3).contains(&part.len()) && part.chars().all(|c| c.is_ascii_digit())
+                })
+        })
+        .map(str::to_owned)
+}
+
+/// Finds the first three-digit SMTP reply code in the 4xx or 5xx range
+fn find_smtp_code(text: &str) -> Option<u16> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .filter(|token| token.len() == 3)
+        .filter_map(|token| token.parse::<u16>().ok())
+        .find(|code| (400..600).contains(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn severity_and_category(
+        reason: &str,
+        status: Option<&str>,
+    ) -> (BounceSeverity, BounceCategory) {
+        let classification = classify(reason, status);
+        (classification.severity, classification.category)
+    }
+
+    #[test]
+    fn enhanced_status_codes_decide_the_category() {
+        let classification = classify(
+            "550 5.1.1 The email account that you tried to reach does not exist",
+            Some("5.1.1"),
+        );
+        assert_eq!(
+            classification,
+            BounceClassification {
+                severity: BounceSeverity::Hard,
+                category: BounceCategory::InvalidMailbox,
+                enhanced_status_code: Some(String::from("5.1.1")),
+                smtp_code: Some(550),
+            }
+        );
+        assert_eq!(
+            severity_and_category("421 4.7.28 Our system has detected an unusual rate", None),
+            (BounceSeverity::Soft, BounceCategory::RateLimited)
+        );
+    }
+
+    #[test]
+    fn specific_wording_refines_a_generic_code() {
+        assert_eq!(
+            severity_and_category(
+                "554 5.7.1 Service unavailable; Client host [1.2.3.4] blocked using Spamhaus",
+                Some("5.7.1"),
+            ),
+            (BounceSeverity::Soft, BounceCategory::Reputation)
+        );
+        assert_eq!(
+            severity_and_category(
+                "452 4.2.2 The email account that you tried to reach is over quota",
+                None
+            ),
+            (BounceSeverity::Soft, BounceCategory::MailboxFull)
+        );
+    }
+
+    #[test]
+    fn wording_is_used_without_a_code() {
+        assert_eq!(
+            severity_and_category("Domain not found", None),
+            (BounceSeverity::Hard, BounceCategory::InvalidDomain)
+        );
+        assert_eq!(
+            severity_and_category("550 Requested action not taken: mailbox unavailable", None),
+            (BounceSeverity::Hard, BounceCategory::InvalidMailbox)
+        );
+    }
+
+    #[test]
+    fn the_reply_code_class_decides_the_severity_of_unknown_reasons() {
+        assert_eq!(
+            severity_and_category("550 Something went wrong", None),
+            (BounceSeverity::Hard, BounceCategory::Unknown)
+        );
+        assert_eq!(
+            severity_and_category("weird", None),
+            (BounceSeverity::Unknown, BounceCategory::Unknown)
+        );
+    }
+
+    #[test]
+    fn short_phrases_only_match_at_word_boundaries() {
+        assert!(contains_word_prefix("listed on an rbl", "rbl"));
+        assert!(!contains_word_prefix("verbless reply", "rbl"));
+    }
+
+    #[test]
+    fn codes_are_found_in_the_reason() {
+        assert_eq!(
+            find_enhanced_status_code("rejected (5.7.26): unauthenticated"),
+            Some(String::from("5.7.26"))
+        );
+        assert_eq!(find_enhanced_status_code("version 1.2.3 of 10.0.0.1"), None);
+        assert_eq!(find_smtp_code("status 250 then 421 later"), Some(421));
+    }
+}
@@ -1,28 +1,215 @@
 use ndc_sdk::connector;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{env, path::Path};
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
 
-use super::sendgrid_api::{ApiKeyError, SendGridApiKey};
+use super::recipients::normalize_domain;
+use super::sendgrid_api::{ApiKeyError, SendGridApiKey, SENDGRID_BASE_URL};
+use super::webhook_signature;
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
 pub struct SendGridConfiguration {
     pub sendgrid_api_key: SendGridApiKey,
+    /// The API key used for the Email Address Validation API, which SendGrid issues separately.
+    /// Falls back to the main API key when not set.
+    pub sendgrid_validation_api_key: Option<SendGridApiKey>,
+    /// The base URL of the SendGrid v3 API. Can be overridden to point at a local stand-in.
+    pub sendgrid_api_base_url: String,
+    /// When set, the scheme and host of the upload URLs SendGrid hands out for contact imports
+    /// are replaced with this base URL.
+    pub sendgrid_upload_base_url: Option<String>,
+    /// When set, the suppression lists are synced into a local cache at this interval, in
+    /// seconds, and suppression lookups are served from the cache.
+    pub suppression_cache_refresh_seconds: Option<u64>,
+    /// How long, in seconds, after its last successful sync the suppression cache is still used.
+    /// Lookups made after that go to the SendGrid API until the cache syncs again.
+    pub suppression_cache_max_age_seconds: Option<u64>,
+    /// Restrictions on who mail can be sent to, for non-production environments
+    pub recipient_safety: RecipientSafetyConfiguration,
+    /// The secret and URL used to create signed preference center and unsubscribe links
+    pub preference_links: Option<PreferenceLinkConfiguration>,
+    /// Where events posted to the Event Webhook route are served from and stored, if enabled
+    pub event_webhook: Option<EventWebhookConfiguration>,
+    /// The key and tolerance that Signed Event Webhook requests are verified with, if enabled
+    pub event_webhook_verification: Option<EventWebhookVerificationConfiguration>,
+    /// The directory the configuration was read from. Relative file paths passed to the
+    /// connector are resolved against this directory.
+    pub configuration_dir: PathBuf,
 }
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct RecipientSafetyConfiguration {
+    /// The domains and addresses mail may be delivered to, lowercased and with their domains
+    /// encoded as punycode like recipient addresses are. When only a catch-all address is set,
+    /// every recipient is redirected.
+    pub allowlist: Vec<String>,
+    /// The catch-all address that recipients not on the allowlist are rewritten to
+    pub redirect_to: Option<String>,
+    /// Whether every mail is sent with SendGrid's sandbox mode enabled, so it is validated but
+    /// never delivered
+    pub force_sandbox_mode: bool,
+}
+
+impl RecipientSafetyConfiguration {
+    /// Recipients are restricted once an allowlist or a catch-all address is set
+    pub fn restricts_recipients(&self) -> bool {
+        !self.allowlist.is_empty() || self.redirect_to.is_some()
+    }
+
+    /// Whether mail may be delivered to an address: it is on the allowlist, its domain is on the
+    /// allowlist, or it is the catch-all address itself
+    pub fn is_allowed(&self, email: &str) -> bool {
+        let email = email.to_lowercase();
+        let domain = email.rsplit_once('@').map(|(_, domain)| domain);
+        let is_redirect_address = self
+            .redirect_to
+            .as_ref()
+            .is_some_and(|redirect_to| redirect_to.eq_ignore_ascii_case(&email));
+        is_redirect_address
+            || self
+                .allowlist
+                .iter()
+                .any(|entry| *entry == email || Some(entry.as_str()) == domain)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PreferenceLinkConfiguration {
+    /// The secret preference tokens are signed with
+    pub secret: String,
+    /// The URL of the preference center, which the signed token is appended to as the `token`
+    /// query parameter. It must also accept RFC 8058 one-click unsubscribe POST requests.
+    pub base_url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct EventWebhookConfiguration {
+    /// The SQLite database file that received events are stored in
+    pub store_path: PathBuf,
+    /// The address the Event Webhook route is served on
+    pub host: IpAddr,
+    /// The port the Event Webhook route is served on, alongside the connector's own port
+    pub port: u16,
+    /// Whether the route may accept unsigned requests on an address other than loopback
+    pub allow_unverified: bool,
+    /// How many days received events are kept, if not forever
+    pub retention_days: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct EventWebhookVerificationConfiguration {
+    /// The base64 encoded verification key SendGrid shows for the Signed Event Webhook
+    pub public_key: String,
+    /// How far a request's signed timestamp may be from the current time, in seconds, before the
+    /// request is rejected as a possible replay
+    pub timestamp_tolerance_seconds: u64,
+}
+
+/// The address the Event Webhook route is served on when SENDGRID_EVENT_WEBHOOK_HOST is not set
+const DEFAULT_EVENT_WEBHOOK_HOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// The port the Event Webhook route is served on when SENDGRID_EVENT_WEBHOOK_PORT is not set
+const DEFAULT_EVENT_WEBHOOK_PORT: u16 = 8081;
+
+/// How many refresh intervals old the suppression cache can get before it stops being used, when
+/// SENDGRID_SUPPRESSION_CACHE_MAX_AGE_SECONDS is not set
+const DEFAULT_SUPPRESSION_CACHE_MAX_AGE_INTERVALS: u64 = 3;
+
+/// The timestamp tolerance used when SENDGRID_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS is not set
+const DEFAULT_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS: u64 = 300;
+
 pub fn parse_configuration(
-    _configuration_dir: impl AsRef<Path> + Send,
+    configuration_dir: impl AsRef<Path> + Send,
 ) -> connector::Result<SendGridConfiguration> {
+    let sendgrid_api_key = parse_api_key()?;
+    let sendgrid_validation_api_key = read_optional_env_var("SENDGRID_VALIDATION_API_KEY")?
+        .map(|key| SendGridApiKey::new(&key))
+        .transpose()
+        .map_err(|err| match err {
+            ApiKeyError::CannotBeBlank => connector::ErrorResponse::from(
+                "The SENDGRID_VALIDATION_API_KEY environment variable cannot be blank".to_owned(),
+            ),
+        })?;
+    let sendgrid_api_base_url = read_optional_env_var("SENDGRID_API_BASE_URL")?
+        .unwrap_or_else(|| SENDGRID_BASE_URL.to_owned());
+    let sendgrid_upload_base_url = read_optional_env_var("SENDGRID_UPLOAD_BASE_URL")?;
+    let suppression_cache_refresh_seconds =
+        read_optional_env_var("SENDGRID_SUPPRESSION_CACHE_REFRESH_SECONDS")?
+            .map(|value| {
+                value.parse::<u64>().ok().filter(|seconds| *seconds > 0).ok_or_else(|| {
+                    connector::ErrorResponse::from(format!(
+                        "The SENDGRID_SUPPRESSION_CACHE_REFRESH_SECONDS environment variable must be a positive number of seconds, but was '{value}'"
+                    ))
+                })
+            })
+            .transpose()?;
+    let suppression_cache_max_age_seconds =
+        read_optional_env_var("SENDGRID_SUPPRESSION_CACHE_MAX_AGE_SECONDS")?
+            .map(|value| {
+                value.parse::<u64>().ok().filter(|seconds| *seconds > 0).ok_or_else(|| {
+                    connector::ErrorResponse::from(format!(
+                        "The SENDGRID_SUPPRESSION_CACHE_MAX_AGE_SECONDS environment variable must be a positive number of seconds, but was '{value}'"
+                    ))
+                })
+            })
+            .transpose()?
+            .or_else(|| {
+                suppression_cache_refresh_seconds.map(|refresh_seconds| {
+                    refresh_seconds.saturating_mul(DEFAULT_SUPPRESSION_CACHE_MAX_AGE_INTERVALS)
+                })
+            });
+
+    let recipient_safety = parse_recipient_safety()?;
+    let preference_links = match (
+        read_optional_env_var("SENDGRID_PREFERENCE_LINK_SECRET")?,
+        read_optional_env_var("SENDGRID_PREFERENCE_LINK_BASE_URL")?,
+    ) {
+        (Some(secret), Some(base_url)) => Some(PreferenceLinkConfiguration { secret, base_url }),
+        (None, None) => None,
+        _ => Err(connector::ErrorResponse::from(
+            "SENDGRID_PREFERENCE_LINK_SECRET and SENDGRID_PREFERENCE_LINK_BASE_URL must be set together".to_owned(),
+        ))?,
+    };
+
+    let event_webhook = parse_event_webhook(configuration_dir.as_ref())?;
+    let event_webhook_verification = parse_event_webhook_verification()?;
+    if let Some(event_webhook) = &event_webhook {
+        if !event_webhook.host.is_loopback()
+            && !event_webhook.allow_unverified
+            && event_webhook_verification.is_none()
+        {
+            return Err(connector::ErrorResponse::from(
+                "Serving the Event Webhook on an address other than loopback requires SENDGRID_EVENT_WEBHOOK_PUBLIC_KEY to be set, or SENDGRID_EVENT_WEBHOOK_ALLOW_UNVERIFIED to be 'true'".to_owned(),
+            ));
+        }
+    }
+
+    Ok(SendGridConfiguration {
+        sendgrid_api_key,
+        sendgrid_validation_api_key,
+        sendgrid_api_base_url,
+        sendgrid_upload_base_url,
+        suppression_cache_refresh_seconds,
+        suppression_cache_max_age_seconds,
+        recipient_safety,
+        preference_links,
+        event_webhook,
+        event_webhook_verification,
+        configuration_dir: configuration_dir.as_ref().to_path_buf(),
+    })
+}
+
+fn parse_api_key() -> connector::Result<SendGridApiKey> {
     match env::var("SENDGRID_API_KEY") {
-        Ok(key) => SendGridApiKey::new(key.as_str())
-            .map(|api_key| SendGridConfiguration {
-                sendgrid_api_key: api_key,
-            })
-            .map_err(|err| match err {
-                ApiKeyError::CannotBeBlank => connector::ErrorResponse::from(
-                    "The SENDGRID_API_KEY environment variable cannot be blank".to_owned(),
-                ),
-            }),
+        Ok(key) => SendGridApiKey::new(key.as_str()).map_err(|err| match err {
+            ApiKeyError::CannotBeBlank => connector::ErrorResponse::from(
+                "The SENDGRID_API_KEY environment variable cannot be blank".to_owned(),
+            ),
+        }),
         Err(env::VarError::NotPresent) => Err(connector::ErrorResponse::from(
             "The SENDGRID_API_KEY environment variable is required".to_owned(),
         )),
@@ -31,3 +218,181 @@ pub fn parse_configuration(
         )),
     }
 }
+
+fn parse_recipient_safety() -> connector::Result<RecipientSafetyConfiguration> {
+    let allowlist = read_optional_env_var("SENDGRID_RECIPIENT_ALLOWLIST")?
+        .map(|value| parse_allowlist(&value))
+        .transpose()?
+        .unwrap_or_default();
+    let redirect_to = read_optional_env_var("SENDGRID_RECIPIENT_REDIRECT_TO")?;
+    let force_sandbox_mode = match read_optional_env_var("SENDGRID_FORCE_SANDBOX_MODE")? {
+        Some(value) => value.parse::<bool>().map_err(|_| {
+            connector::ErrorResponse::from(format!(
+                "The SENDGRID_FORCE_SANDBOX_MODE environment variable must be 'true' or 'false', but was '{value}'"
+            ))
+        })?,
+        None => false,
+    };
+
+    Ok(RecipientSafetyConfiguration {
+        allowlist,
+        redirect_to,
+        force_sandbox_mode,
+    })
+}
+
+/// Splits the comma-separated allowlist, normalizing each domain the way recipient addresses are
+/// normalized so that internationalized domains match
+fn parse_allowlist(value: &str) -> connector::Result<Vec<String>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (local_part, domain) = match entry.rsplit_once('@') {
+                Some((local_part, domain)) => (Some(local_part), domain),
+                None => (None, entry),
+            };
+            let domain = normalize_domain(domain).ok_or_else(|| {
+                connector::ErrorResponse::from(format!(
+                    "The SENDGRID_RECIPIENT_ALLOWLIST entry '{entry}' does not have a valid domain"
+                ))
+            })?;
+            Ok(match local_part {
+                Some(local_part) => format!("{}@{domain}", local_part.to_lowercase()),
+                None => domain,
+            })
+        })
+        .collect()
+}
+
+fn parse_event_webhook(
+    configuration_dir: &Path,
+) -> connector::Result<Option<EventWebhookConfiguration>> {
+    let port = read_optional_env_var("SENDGRID_EVENT_WEBHOOK_PORT")?
+        .map(|value| {
+            value.parse::<u16>().ok().filter(|port| *port > 0).ok_or_else(|| {
+                connector::ErrorResponse::from(format!(
+                    "The SENDGRID_EVENT_WEBHOOK_PORT environment variable must be a port number, but was '{value}'"
+                ))
+            })
+        })
+        .transpose()?;
+    let host = read_optional_env_var("SENDGRID_EVENT_WEBHOOK_HOST")?
+        .map(|value| {
+            value.parse::<IpAddr>().map_err(|_| {
+                connector::ErrorResponse::from(format!(
+                    "The SENDGRID_EVENT_WEBHOOK_HOST environment variable must be an IP address, but was '{value}'"
+                ))
+            })
+        })
+        .transpose()?;
+    let allow_unverified = read_optional_env_var("SENDGRID_EVENT_WEBHOOK_ALLOW_UNVERIFIED")?
+        .map(|value| {
+            value.parse::<bool>().map_err(|_| {
+                connector::ErrorResponse::from(format!(
+                    "The SENDGRID_EVENT_WEBHOOK_ALLOW_UNVERIFIED environment variable must be 'true' or 'false', but was '{value}'"
+                ))
+            })
+        })
+        .transpose()?;
+    let retention_days = read_optional_env_var("SENDGRID_EVENT_RETENTION_DAYS")?
+        .map(|value| {
+            value.parse::<u64>().ok().filter(|days| *days > 0).ok_or_else(|| {
+                connector::ErrorResponse::from(format!(
+                    "The SENDGRID_EVENT_RETENTION_DAYS environment variable must be a positive number of days, but was '{value}'"
+                ))
+            })
+        })
+        .transpose()?;
+
+    match read_optional_env_var("SENDGRID_EVENT_STORE_PATH")? {
+        Some(store_path) => Ok(Some(EventWebhookConfiguration {
+            // Relative paths are resolved against the configuration directory
+            store_path: configuration_dir.join(store_path),
+            host: host.unwrap_or(DEFAULT_EVENT_WEBHOOK_HOST),
+            port: port.unwrap_or(DEFAULT_EVENT_WEBHOOK_PORT),
+            allow_unverified: allow_unverified.unwrap_or(false),
+            retention_days,
+        })),
+        None if port.is_some()
+            || host.is_some()
+            || allow_unverified.is_some()
+            || retention_days.is_some() =>
+        {
+            Err(connector::ErrorResponse::from(
+                "SENDGRID_EVENT_WEBHOOK_HOST, SENDGRID_EVENT_WEBHOOK_PORT, SENDGRID_EVENT_WEBHOOK_ALLOW_UNVERIFIED and SENDGRID_EVENT_RETENTION_DAYS require SENDGRID_EVENT_STORE_PATH to be set".to_owned(),
+            ))
+        }
+        None => Ok(None),
+    }
+}
+
+fn parse_event_webhook_verification(
+) -> connector::Result<Option<EventWebhookVerificationConfiguration>> {
+    let timestamp_tolerance_seconds =
+        read_optional_env_var("SENDGRID_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS")?
+            .map(|value| {
+                value.parse::<u64>().ok().filter(|seconds| *seconds > 0).ok_or_else(|| {
+                    connector::ErrorResponse::from(format!(
+                        "The SENDGRID_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS environment variable must be a positive number of seconds, but was '{value}'"
+                    ))
+                })
+            })
+            .transpose()?;
+
+    match read_optional_env_var("SENDGRID_EVENT_WEBHOOK_PUBLIC_KEY")? {
+        Some(public_key) => {
+            webhook_signature::parse_public_key(&public_key).map_err(|_| {
+                connector::ErrorResponse::from(
+                    "The SENDGRID_EVENT_WEBHOOK_PUBLIC_KEY environment variable must be the base64 encoded verification key of the Signed Event Webhook".to_owned(),
+                )
+            })?;
+            Ok(Some(EventWebhookVerificationConfiguration {
+                public_key,
+                timestamp_tolerance_seconds: timestamp_tolerance_seconds
+                    .unwrap_or(DEFAULT_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS),
+            }))
+        }
+        None if timestamp_tolerance_seconds.is_some() => Err(connector::ErrorResponse::from(
+            "SENDGRID_EVENT_WEBHOOK_TIMESTAMP_TOLERANCE_SECONDS requires SENDGRID_EVENT_WEBHOOK_PUBLIC_KEY to be set".to_owned(),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn read_optional_env_var(name: &str) -> connector::Result<Option<String>> {
+    match env::var(name) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value.trim().to_owned())),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(connector::ErrorResponse::from(format!(
+            "The {name} environment variable value is not valid unicode"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist_domains_are_normalized_like_recipient_addresses() {
+        let allowlist = parse_allowlist(" Bücher.example, Ada@CORP.example ,,").unwrap();
+        assert_eq!(
+            allowlist,
+            vec![
+                String::from("xn--bcher-kva.example"),
+                String::from("ada@corp.example"),
+            ]
+        );
+
+        let safety = RecipientSafetyConfiguration {
+            allowlist,
+            ..RecipientSafetyConfiguration::default()
+        };
+        assert!(safety.is_allowed("grace@xn--bcher-kva.example"));
+        assert!(safety.is_allowed("ADA@corp.example"));
+        assert!(!safety.is_allowed("grace@corp.example"));
+    }
+}
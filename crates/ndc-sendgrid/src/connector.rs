@@ -4,18 +4,26 @@ use ndc_sdk::connector;
 use ndc_sdk::json_response::JsonResponse;
 use ndc_sdk::models;
 use std::path::Path;
+use std::time::Duration;
 
 use super::configuration;
+use super::event_store::EventStore;
+use super::event_webhook;
 use super::mutation;
 use super::query;
 use super::schema;
+use super::suppression_cache::SuppressionCache;
 
 #[derive(Clone, Default)]
 pub struct SendGridConnector {}
 
 #[derive(Clone, Debug)]
 pub struct SendGridConnectorState {
-    http_client: reqwest::Client,
+    pub http_client: reqwest::Client,
+    /// The local copy of the suppression lists, if enabled in the configuration
+    pub suppression_cache: Option<SuppressionCache>,
+    /// The events received by the Event Webhook route, if enabled in the configuration
+    pub event_store: Option<EventStore>,
 }
 
 #[async_trait]
@@ -45,11 +53,62 @@ impl connector::ConnectorSetup for SendGridConnector {
     /// This may be called repeatedly until it succeeds.
     async fn try_init_state(
         &self,
-        _configuration: &<Self::Connector as connector::Connector>::Configuration,
-        _metrics: &mut prometheus::Registry,
+        configuration: &<Self::Connector as connector::Connector>::Configuration,
+        metrics: &mut prometheus::Registry,
     ) -> connector::Result<<Self::Connector as connector::Connector>::State> {
+        let http_client = reqwest::Client::new();
+
+        // The event store is opened and the Event Webhook's address bound first, so that a failure
+        // to start can be retried without the suppression cache's metrics having been registered
+        let event_webhook = match &configuration.event_webhook {
+            Some(event_webhook) => {
+                let event_store = EventStore::open(
+                    &event_webhook.store_path,
+                    event_webhook
+                        .retention_days
+                        .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+                )?;
+                let server = event_webhook::bind_server(
+                    event_store.clone(),
+                    configuration.event_webhook_verification.clone(),
+                    event_webhook.host,
+                    event_webhook.port,
+                )?;
+                Some((event_store, server))
+            }
+            None => None,
+        };
+        let suppression_cache = match (
+            configuration.suppression_cache_refresh_seconds,
+            configuration.suppression_cache_max_age_seconds,
+        ) {
+            (Some(_), Some(max_age_seconds)) => Some(SuppressionCache::new(
+                metrics,
+                Duration::from_secs(max_age_seconds),
+            )?),
+            _ => None,
+        };
+
+        // Nothing is spawned until every step that can fail has succeeded
+        if let (Some(cache), Some(refresh_seconds)) = (
+            &suppression_cache,
+            configuration.suppression_cache_refresh_seconds,
+        ) {
+            cache.start_sync_task(
+                http_client.clone(),
+                configuration.clone(),
+                Duration::from_secs(refresh_seconds),
+            );
+        }
+        let event_store = event_webhook.map(|(event_store, server)| {
+            tokio::spawn(server);
+            event_store
+        });
+
         Ok(SendGridConnectorState {
-            http_client: reqwest::Client::new(),
+            http_client,
+            suppression_cache,
+            event_store,
         })
     }
 }
@@ -70,8 +129,11 @@ impl connector::Connector for SendGridConnector {
     /// can be polled but not updated directly.
     fn fetch_metrics(
         _configuration: &configuration::SendGridConfiguration,
-        _state: &SendGridConnectorState,
+        state: &SendGridConnectorState,
     ) -> connector::Result<()> {
+        if let Some(suppression_cache) = &state.suppression_cache {
+            suppression_cache.update_metrics();
+        }
         Ok(())
     }
 
@@ -95,7 +157,7 @@ impl connector::Connector for SendGridConnector {
     async fn get_capabilities() -> models::Capabilities {
         models::Capabilities {
             query: models::QueryCapabilities {
-                aggregates: None,
+                aggregates: Some(models::LeafCapability {}),
                 variables: None,
                 explain: None,
                 exists: models::ExistsCapabilities {
@@ -164,7 +226,7 @@ impl connector::Connector for SendGridConnector {
         state: &SendGridConnectorState,
         request: models::MutationRequest,
     ) -> connector::Result<JsonResponse<models::MutationResponse>> {
-        mutation::execute(&state.http_client, configuration, request)
+        mutation::execute(state, configuration, request)
             .await
             .map(JsonResponse::Value)
     }
@@ -178,7 +240,7 @@ impl connector::Connector for SendGridConnector {
         state: &SendGridConnectorState,
         query_request: models::QueryRequest,
     ) -> connector::Result<JsonResponse<models::QueryResponse>> {
-        query::execute(&state.http_client, configuration, query_request)
+        query::execute(state, configuration, query_request)
             .await
             .map(JsonResponse::Value)
     }
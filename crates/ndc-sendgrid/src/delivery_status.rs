@@ -0,0 +1,615 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use ndc_sdk::connector::{self, QueryError};
+
+use super::activity_query::quote;
+use super::configuration;
+use super::event_store::{EmailEvent, EventFilter, EventStore};
+use super::schema::{
+    DeliveryStatus, DeliveryStatusResponse, GetDeliveryStatusArgs, RecipientDeliveryStatus,
+};
+use super::sendgrid_api::{self, Message, ScheduledSendStatus};
+
+/// The custom argument send_mail tags batched mail with, so that it can be found by batch ID
+pub const BATCH_ID_CUSTOM_ARG: &str = "batch_id";
+
+/// The most messages the Email Activity API returns for a single query
+const MESSAGES_LIMIT_MAX: u32 = 1000;
+
+/// The most pages of messages listed for a single send
+const MESSAGE_PAGES_MAX: usize = 10;
+
+/// How many message details are fetched from the Email Activity API at once
+const MESSAGE_DETAIL_CONCURRENCY: usize = 10;
+
+/// The most message details fetched for a single send. The status of the remaining recipients
+/// that need one is taken from the message's summary instead.
+const MESSAGE_DETAILS_MAX: usize = 100;
+
+/// How the messages of a send are found
+enum SendLookup {
+    /// The message ID returned when the mail was sent, which prefixes the ID of each message sent
+    MessageId(String),
+    CustomArg {
+        name: String,
+        value: String,
+    },
+}
+
+/// Works out how far the most recent message of a send to each recipient got, from the events
+/// received by the Event Webhook route, if enabled, and from the Email Activity feed. Where both
+/// know of a message, whichever saw it get further counts.
+pub async fn get_delivery_status(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    event_store: Option<&EventStore>,
+    args: GetDeliveryStatusArgs,
+) -> connector::Result<DeliveryStatusResponse> {
+    let lookup = match (args.message_id, &args.batch_id, args.custom_arg) {
+        (Some(message_id), None, None) => SendLookup::MessageId(message_id),
+        (None, Some(batch_id), None) => SendLookup::CustomArg {
+            name: BATCH_ID_CUSTOM_ARG.to_owned(),
+            value: batch_id.clone(),
+        },
+        (None, None, Some(custom_arg)) => SendLookup::CustomArg {
+            name: custom_arg.name,
+            value: custom_arg.value,
+        },
+        _ => {
+            return Err(QueryError::new_invalid_request(
+                &"Exactly one of message_id, batch_id or custom_arg must be given",
+            )
+            .into())
+        }
+    };
+
+    let batch_status = match &args.batch_id {
+        Some(batch_id) => get_batch_status(http_client, configuration, batch_id).await?,
+        None => None,
+    };
+
+    let mut recipients = BTreeMap::new();
+    let mut complete = true;
+    if let Some(event_store) = event_store {
+        let events = event_store.query_events(lookup.event_filter()).await?;
+        for status in stored_event_statuses(events) {
+            merge_status(&mut recipients, status);
+        }
+    }
+    match activity_statuses(http_client, configuration, &lookup).await {
+        Ok(activity) => {
+            for status in activity.statuses {
+                merge_status(&mut recipients, status);
+            }
+            complete = activity.complete;
+        }
+        // The Email Activity API is a paid add-on, so the stored events are answer enough
+        Err(err) if event_store.is_some() => {
+            tracing::warn!(error = ?err, "Falling back to stored events for the delivery status");
+        }
+        Err(err) => return Err(err),
+    }
+
+    // A batch with no tagged mail may have been sent untagged, which cannot be told apart from
+    // it having no recipients. A paused or cancelled batch may not have been sent at all.
+    if let (Some(batch_id), None) = (&args.batch_id, batch_status) {
+        if recipients.is_empty() {
+            return Err(QueryError::new_invalid_request(&format!(
+                "No mail tagged with the batch ID '{batch_id}' was found. Mail can only be found by batch ID if it was sent by send_mail with tag_batch, and may take a few minutes to appear"
+            ))
+            .into());
+        }
+    }
+
+    Ok(DeliveryStatusResponse {
+        batch_status,
+        recipients: recipients.into_values().collect(),
+        complete,
+    })
+}
+
+impl SendLookup {
+    fn activity_query(&self) -> String {
+        match self {
+            SendLookup::MessageId(message_id) => {
+                format!("msg_id LIKE {}", quote(&format!("{message_id}%")))
+            }
+            SendLookup::CustomArg { name, value } => {
+                format!(
+                    "unique_args['{}']={}",
+                    name.replace('\'', "\\'"),
+                    quote(value)
+                )
+            }
+        }
+    }
+
+    fn event_filter(&self) -> EventFilter {
+        match self {
+            SendLookup::MessageId(message_id) => EventFilter {
+                sg_message_id_prefix: Some(message_id.clone()),
+                ..EventFilter::default()
+            },
+            SendLookup::CustomArg { name, value } => EventFilter {
+                custom_arg: Some((name.clone(), value.clone())),
+                ..EventFilter::default()
+            },
+        }
+    }
+}
+
+/// Keeps the status of the most recent message to a recipient, or the furthest status of the
+/// same message
+fn merge_status(
+    recipients: &mut BTreeMap<String, RecipientDeliveryStatus>,
+    status: RecipientDeliveryStatus,
+) {
+    let key = status.email.to_lowercase();
+    let replaces = match recipients.get(&key) {
+        Some(current) if current.msg_id == status.msg_id => outranks(&status, current),
+        Some(current) => event_time(&status.last_event_time) > event_time(&current.last_event_time),
+        None => true,
+    };
+    if replaces {
+        recipients.insert(key, status);
+    }
+}
+
+/// Whether one status of a message says it got further than another. A bounce or drop no
+/// earlier than a delivery wins over it, as the delivery did not stick.
+fn outranks(status: &RecipientDeliveryStatus, other: &RecipientDeliveryStatus) -> bool {
+    let time = event_time(&status.last_event_time);
+    let other_time = event_time(&other.last_event_time);
+    if is_failure(status.status) && was_delivered(other.status) {
+        time >= other_time
+    } else if was_delivered(status.status) && is_failure(other.status) {
+        time > other_time
+    } else {
+        status_rank(status.status) > status_rank(other.status)
+    }
+}
+
+/// Parses an event time, which the Email Activity feed gives with milliseconds and the stored
+/// events without, so that the two can be compared
+fn event_time(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(timestamp).ok()
+}
+
+/// The status of each message in the stored events, which are in the order they happened
+fn stored_event_statuses(events: Vec<EmailEvent>) -> Vec<RecipientDeliveryStatus> {
+    let mut messages: BTreeMap<(String, String), Vec<EmailEvent>> = BTreeMap::new();
+    for event in events {
+        let key = (
+            event.email.clone(),
+            event.sg_message_id.clone().unwrap_or_default(),
+        );
+        messages.entry(key).or_default().push(event);
+    }
+
+    messages
+        .into_iter()
+        .map(|((email, msg_id), events)| {
+            let (status, reason) = furthest_status(
+                events
+                    .iter()
+                    .map(|event| (event.event.as_str(), event.reason.as_deref())),
+            );
+            RecipientDeliveryStatus {
+                email,
+                status,
+                msg_id,
+                last_event_time: events
+                    .last()
+                    .map(|event| event.timestamp.clone())
+                    .unwrap_or_default(),
+                reason,
+            }
+        })
+        .collect()
+}
+
+/// The statuses found in the Email Activity feed
+struct ActivityStatuses {
+    statuses: Vec<RecipientDeliveryStatus>,
+    /// Whether every message of the send was listed and every status that needed a message's
+    /// details got them
+    complete: bool,
+}
+
+/// The status of the most recent message of the send to each recipient in the Email Activity feed
+async fn activity_statuses(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    lookup: &SendLookup,
+) -> connector::Result<ActivityStatuses> {
+    let (messages, mut complete) = list_send_messages(http_client, configuration, lookup).await?;
+
+    // Only the most recent message to each recipient counts
+    let mut latest_messages: BTreeMap<String, Message> = BTreeMap::new();
+    for message in messages {
+        let key = message.to_email.to_lowercase();
+        match latest_messages.get(&key) {
+            Some(latest)
+                if event_time(&latest.last_event_time) >= event_time(&message.last_event_time) => {}
+            _ => {
+                latest_messages.insert(key, message);
+            }
+        }
+    }
+
+    let mut details_left = MESSAGE_DETAILS_MAX;
+    let messages = latest_messages
+        .into_values()
+        .map(|message| {
+            let needs_detail = message.status != "delivered";
+            let fetch_detail = needs_detail && details_left > 0;
+            if fetch_detail {
+                details_left -= 1;
+            } else if needs_detail {
+                complete = false;
+            }
+            (message, fetch_detail)
+        })
+        .collect::<Vec<_>>();
+
+    let statuses = stream::iter(messages)
+        .map(|(message, fetch_detail)| {
+            recipient_status(http_client, configuration, message, fetch_detail)
+        })
+        .buffered(MESSAGE_DETAIL_CONCURRENCY)
+        .try_collect()
+        .await?;
+    Ok(ActivityStatuses { statuses, complete })
+}
+
+/// Lists the messages of a send, a page at a time. The feed lists the most recent messages first,
+/// so each page after the first asks for messages no more recent than the oldest one seen. Returns
+/// whether every message was listed.
+async fn list_send_messages(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    lookup: &SendLookup,
+) -> connector::Result<(Vec<Message>, bool)> {
+    let mut messages: BTreeMap<String, Message> = BTreeMap::new();
+    let mut oldest_event_time: Option<String> = None;
+    for _ in 0..MESSAGE_PAGES_MAX {
+        let query = match &oldest_event_time {
+            Some(oldest_event_time) => format!(
+                "{} AND last_event_time<=TIMESTAMP {}",
+                lookup.activity_query(),
+                quote(oldest_event_time)
+            ),
+            None => lookup.activity_query(),
+        };
+        let page = sendgrid_api::invoke_list_messages(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            Some(&query),
+            MESSAGES_LIMIT_MAX,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?
+        .messages;
+
+        let last_page = page.len() < MESSAGES_LIMIT_MAX as usize;
+        oldest_event_time = page
+            .iter()
+            .map(|message| &message.last_event_time)
+            .min_by_key(|last_event_time| event_time(last_event_time))
+            .cloned();
+        let mut found_new = false;
+        for message in page {
+            found_new |= messages.insert(message.msg_id.clone(), message).is_none();
+        }
+        if last_page {
+            return Ok((messages.into_values().collect(), true));
+        }
+        // More messages than fit in a page share the oldest event time
+        if !found_new {
+            break;
+        }
+    }
+    Ok((messages.into_values().collect(), false))
+}
+
+/// Works out a recipient's status from the message's summary where it settles it, and from the
+/// message's events otherwise. Without the events, a message that was not delivered counts as
+/// bounced and any other as queued.
+async fn recipient_status(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    message: Message,
+    fetch_detail: bool,
+) -> connector::Result<RecipientDeliveryStatus> {
+    let (status, reason) = match message.status.as_str() {
+        "delivered" if message.clicks_count > 0 => (DeliveryStatus::Clicked, None),
+        "delivered" if message.opens_count > 0 => (DeliveryStatus::Opened, None),
+        "delivered" => (DeliveryStatus::Delivered, None),
+        "not_delivered" if !fetch_detail => (DeliveryStatus::Bounced, None),
+        _ if !fetch_detail => (DeliveryStatus::Queued, None),
+        _ => {
+            let detail = sendgrid_api::invoke_get_message(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &message.msg_id,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            furthest_status(
+                detail
+                    .events
+                    .iter()
+                    .map(|event| (event.event_name.as_str(), event.reason.as_deref())),
+            )
+        }
+    };
+
+    Ok(RecipientDeliveryStatus {
+        email: message.to_email,
+        status,
+        msg_id: message.msg_id,
+        last_event_time: message.last_event_time,
+        reason,
+    })
+}
+
+/// The furthest status a message's events, in the order they happened, reach, with the reason
+/// for the last event that gave it. A message is delivered once any event says so, even if earlier
+/// attempts were deferred, but a bounce or drop after the delivery wins, as the delivery did not
+/// stick.
+fn furthest_status<'a>(
+    events: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+) -> (DeliveryStatus, Option<String>) {
+    let mut status = DeliveryStatus::Queued;
+    let mut reason = None;
+    for (event_name, event_reason) in events {
+        let Some(event_status) = event_status(event_name) else {
+            continue;
+        };
+        let undoes_delivery = is_failure(event_status) && was_delivered(status);
+        if undoes_delivery || status_rank(event_status) > status_rank(status) {
+            status = event_status;
+        }
+        if event_status == status {
+            reason = event_reason.map(str::to_owned);
+        }
+    }
+    (status, reason)
+}
+
+fn event_status(event_name: &str) -> Option<DeliveryStatus> {
+    match event_name {
+        "processed" => Some(DeliveryStatus::Queued),
+        "deferred" => Some(DeliveryStatus::Deferred),
+        "dropped" => Some(DeliveryStatus::Dropped),
+        "bounce" | "blocked" => Some(DeliveryStatus::Bounced),
+        "delivered" => Some(DeliveryStatus::Delivered),
+        "open" => Some(DeliveryStatus::Opened),
+        "click" => Some(DeliveryStatus::Clicked),
+        _ => None,
+    }
+}
+
+/// Orders the statuses by how far the message got. A message that was delivered after being
+/// deferred is delivered; one that bounced after being deferred bounced.
+fn status_rank(status: DeliveryStatus) -> u8 {
+    match status {
+        DeliveryStatus::Queued => 0,
+        DeliveryStatus::Deferred => 1,
+        DeliveryStatus::Dropped => 2,
+        DeliveryStatus::Bounced => 3,
+        DeliveryStatus::Delivered => 4,
+        DeliveryStatus::Opened => 5,
+        DeliveryStatus::Clicked => 6,
+    }
+}
+
+/// Whether the message never reached the recipient's mailbox
+fn is_failure(status: DeliveryStatus) -> bool {
+    matches!(status, DeliveryStatus::Dropped | DeliveryStatus::Bounced)
+}
+
+fn was_delivered(status: DeliveryStatus) -> bool {
+    status_rank(status) >= status_rank(DeliveryStatus::Delivered)
+}
+
+/// Whether a scheduled batch has been paused or cancelled
+async fn get_batch_status(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    batch_id: &str,
+) -> connector::Result<Option<&'static str>> {
+    let scheduled_send = sendgrid_api::invoke_get_scheduled_send(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        batch_id,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    Ok(
+        scheduled_send.map(|scheduled_send| match scheduled_send.status {
+            ScheduledSendStatus::Pause => "paused",
+            ScheduledSendStatus::Cancel => "cancelled",
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(
+        msg_id: &str,
+        status: DeliveryStatus,
+        last_event_time: &str,
+    ) -> RecipientDeliveryStatus {
+        RecipientDeliveryStatus {
+            email: String::from("Ada@example.com"),
+            status,
+            msg_id: String::from(msg_id),
+            last_event_time: String::from(last_event_time),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn delivery_after_a_deferral_counts_as_delivered() {
+        assert_eq!(
+            furthest_status(
+                [
+                    ("processed", None),
+                    ("deferred", Some("421 try again later")),
+                    ("delivered", None),
+                ]
+                .into_iter()
+            ),
+            (DeliveryStatus::Delivered, None)
+        );
+    }
+
+    #[test]
+    fn a_bounce_after_delivery_counts_as_bounced() {
+        assert_eq!(
+            furthest_status(
+                [
+                    ("processed", None),
+                    ("delivered", None),
+                    ("open", None),
+                    ("bounce", Some("550 mailbox unavailable")),
+                ]
+                .into_iter()
+            ),
+            (
+                DeliveryStatus::Bounced,
+                Some(String::from("550 mailbox unavailable"))
+            )
+        );
+        assert_eq!(
+            furthest_status([("delivered", None), ("dropped", None)].into_iter()),
+            (DeliveryStatus::Dropped, None)
+        );
+    }
+
+    #[test]
+    fn the_reason_comes_from_the_last_event_with_the_furthest_status() {
+        assert_eq!(
+            furthest_status(
+                [
+                    ("processed", None),
+                    ("deferred", Some("first")),
+                    ("deferred", Some("second")),
+                    ("group_unsubscribe", None),
+                ]
+                .into_iter()
+            ),
+            (DeliveryStatus::Deferred, Some(String::from("second")))
+        );
+        assert_eq!(
+            furthest_status(std::iter::empty()),
+            (DeliveryStatus::Queued, None)
+        );
+    }
+
+    #[test]
+    fn statuses_rank_by_how_far_the_message_got() {
+        let ranked = [
+            DeliveryStatus::Queued,
+            DeliveryStatus::Deferred,
+            DeliveryStatus::Dropped,
+            DeliveryStatus::Bounced,
+            DeliveryStatus::Delivered,
+            DeliveryStatus::Opened,
+            DeliveryStatus::Clicked,
+        ];
+        assert!(ranked
+            .windows(2)
+            .all(|pair| status_rank(pair[0]) < status_rank(pair[1])));
+    }
+
+    #[test]
+    fn merge_keeps_the_furthest_status_of_the_same_message() {
+        let mut recipients = BTreeMap::new();
+        merge_status(
+            &mut recipients,
+            status("msg-1", DeliveryStatus::Opened, "2024-05-01T12:00:21Z"),
+        );
+        merge_status(
+            &mut recipients,
+            status(
+                "msg-1",
+                DeliveryStatus::Delivered,
+                "2024-05-01T12:00:30.000Z",
+            ),
+        );
+        assert_eq!(recipients["ada@example.com"].status, DeliveryStatus::Opened);
+    }
+
+    #[test]
+    fn merge_keeps_a_bounce_after_the_delivery_of_the_same_message() {
+        let mut recipients = BTreeMap::new();
+        merge_status(
+            &mut recipients,
+            status("msg-1", DeliveryStatus::Bounced, "2024-05-01T12:05:00Z"),
+        );
+        merge_status(
+            &mut recipients,
+            status(
+                "msg-1",
+                DeliveryStatus::Delivered,
+                "2024-05-01T12:00:30.000Z",
+            ),
+        );
+        assert_eq!(
+            recipients["ada@example.com"].status,
+            DeliveryStatus::Bounced
+        );
+
+        merge_status(
+            &mut recipients,
+            status(
+                "msg-1",
+                DeliveryStatus::Delivered,
+                "2024-05-01T12:06:00.000Z",
+            ),
+        );
+        assert_eq!(
+            recipients["ada@example.com"].status,
+            DeliveryStatus::Delivered
+        );
+        merge_status(
+            &mut recipients,
+            status("msg-1", DeliveryStatus::Dropped, "2024-05-01T12:06:00Z"),
+        );
+        assert_eq!(
+            recipients["ada@example.com"].status,
+            DeliveryStatus::Dropped
+        );
+    }
+
+    #[test]
+    fn merge_compares_event_times_across_formats() {
+        let mut recipients = BTreeMap::new();
+        merge_status(
+            &mut recipients,
+            status("msg-2", DeliveryStatus::Bounced, "2024-05-01T12:00:21Z"),
+        );
+        // Later, although it sorts before the stored event's time as a string
+        merge_status(
+            &mut recipients,
+            status("msg-3", DeliveryStatus::Queued, "2024-05-01T12:00:21.500Z"),
+        );
+        assert_eq!(recipients["ada@example.com"].msg_id, "msg-3");
+
+        merge_status(
+            &mut recipients,
+            status("msg-1", DeliveryStatus::Clicked, "2024-05-01T12:00:20.999Z"),
+        );
+        assert_eq!(recipients["ada@example.com"].msg_id, "msg-3");
+    }
+}
@@ -0,0 +1,712 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use ndc_sdk::{
+    connector,
+    models::{ComparisonTarget, ComparisonValue, Expression, OrderDirection},
+};
+use rusqlite::{params, params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::filter::{
+    EQUAL_OPERATOR, GREATER_THAN_OPERATOR, GREATER_THAN_OR_EQUAL_OPERATOR, IN_OPERATOR,
+    LESS_THAN_OPERATOR, LESS_THAN_OR_EQUAL_OPERATOR,
+};
+
+/// The fields of an Event Webhook event that SendGrid sets itself. Any other field of an event is
+/// a custom argument of the mail it is about.
+const SENDGRID_EVENT_FIELDS: [&str; 25] = [
+    "asm_group_id",
+    "bounce_classification",
+    "cert_err",
+    "marketing_campaign_id",
+    "marketing_campaign_name",
+    "marketing_campaign_split_id",
+    "marketing_campaign_version",
+    "mc_stats",
+    "newsletter",
+    "phase_id",
+    "pool",
+    "post_type",
+    "send_at",
+    "sg_content_type",
+    "sg_machine_open",
+    "sg_template_id",
+    "sg_template_name",
+    "singlesend_id",
+    "singlesend_name",
+    "smtp-id",
+    "template_hash",
+    "template_id",
+    "template_name",
+    "template_version_id",
+    "tls",
+];
+
+const CREATE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS email_events (
+        sg_event_id TEXT PRIMARY KEY,
+        sg_message_id TEXT,
+        email TEXT NOT NULL,
+        event TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        categories TEXT,
+        reason TEXT,
+        status TEXT,
+        response TEXT,
+        attempt INTEGER,
+        bounce_type TEXT,
+        url TEXT,
+        useragent TEXT,
+        ip TEXT,
+        unique_args TEXT,
+        payload TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS email_events_sg_message_id ON email_events (sg_message_id);
+    CREATE INDEX IF NOT EXISTS email_events_email ON email_events (email);
+    CREATE INDEX IF NOT EXISTS email_events_timestamp ON email_events (timestamp);
+";
+
+const EVENT_COLUMNS: &str = "sg_event_id, sg_message_id, email, event, timestamp, categories, reason, status, response, attempt, bounce_type, url, useragent, ip, unique_args";
+
+/// The columns that SQLite sorts the same way as the email_events collection does. The
+/// categories and unique_args columns hold JSON, so are not among them.
+pub const SORTABLE_COLUMNS: [&str; 13] = [
+    "sg_event_id",
+    "sg_message_id",
+    "email",
+    "event",
+    "timestamp",
+    "reason",
+    "status",
+    "response",
+    "attempt",
+    "bounce_type",
+    "url",
+    "useragent",
+    "ip",
+];
+
+/// A store of the events SendGrid has posted to the Event Webhook, kept in a SQLite database
+#[derive(Clone, Debug)]
+pub struct EventStore {
+    connection: Arc<Mutex<Connection>>,
+    /// How long events are kept, if not forever
+    retention: Option<Duration>,
+}
+
+/// An event as posted by SendGrid's Event Webhook
+#[derive(Deserialize, Clone, Debug)]
+pub struct WebhookEvent {
+    pub sg_event_id: String,
+    pub sg_message_id: Option<String>,
+    pub email: String,
+    pub event: String,
+    /// The unix time the event happened
+    pub timestamp: i64,
+    /// A single category is posted as a string, several as an array
+    pub category: Option<WebhookCategories>,
+    pub reason: Option<String>,
+    pub status: Option<String>,
+    pub response: Option<String>,
+    /// Posted as a string by SendGrid
+    pub attempt: Option<Value>,
+    /// Either bounce or blocked, on bounce events
+    #[serde(rename = "type")]
+    pub bounce_type: Option<String>,
+    pub url: Option<String>,
+    pub useragent: Option<String>,
+    pub ip: Option<String>,
+    /// Every other field of the event: the custom arguments of the mail, and fields of SendGrid's
+    /// that the store does not have a column for
+    #[serde(flatten)]
+    pub other_fields: BTreeMap<String, Value>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum WebhookCategories {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A stored event, as a row of the email_events collection
+#[derive(Serialize, Clone, Debug)]
+pub struct EmailEvent {
+    pub sg_event_id: String,
+    pub sg_message_id: Option<String>,
+    pub email: String,
+    pub event: String,
+    pub timestamp: String,
+    pub categories: Option<Vec<String>>,
+    pub reason: Option<String>,
+    pub status: Option<String>,
+    pub response: Option<String>,
+    pub attempt: Option<i64>,
+    pub bounce_type: Option<String>,
+    pub url: Option<String>,
+    pub useragent: Option<String>,
+    pub ip: Option<String>,
+    pub unique_args: Option<Value>,
+}
+
+/// Restrictions on which stored events are read, which are evaluated by SQLite
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    pub sg_event_ids: Option<Vec<String>>,
+    pub sg_message_ids: Option<Vec<String>>,
+    /// Matches the events of messages whose ID starts with this prefix. The message ID returned
+    /// when sending mail prefixes the IDs of each of the messages it sent.
+    pub sg_message_id_prefix: Option<String>,
+    pub emails: Option<Vec<String>>,
+    pub events: Option<Vec<String>>,
+    /// Matches the events of mail sent with this custom argument
+    pub custom_arg: Option<(String, String)>,
+    /// The earliest unix time of the events, inclusive
+    pub since: Option<i64>,
+    /// The latest unix time of the events, inclusive
+    pub until: Option<i64>,
+    /// The columns the events are sorted by, which must be among the sortable columns. Events
+    /// that tie are in the order they happened.
+    pub order_by: Vec<(&'static str, OrderDirection)>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// A query's `where` clause split into the part SQLite evaluates and the rest
+#[derive(Clone, Debug, Default)]
+pub struct TranslatedPredicate {
+    pub filter: EventFilter,
+    /// The conjuncts of the predicate that could not be translated, which must be evaluated
+    /// against the returned events. Addresses compared to the email column are lowercased, as
+    /// the stored addresses are.
+    pub residual: Option<Expression>,
+}
+
+/// Translates as much of a predicate on the email_events collection as possible into a filter.
+/// `_eq` and `_in` comparisons on sg_event_id, sg_message_id, email and event, and comparisons
+/// of timestamp to a time, are translated exactly; every other conjunct is left in the residual
+/// predicate.
+pub fn translate_predicate(predicate: &Expression) -> TranslatedPredicate {
+    let conjuncts = match predicate {
+        Expression::And { expressions } => expressions.iter().collect::<Vec<_>>(),
+        expression => vec![expression],
+    };
+
+    let mut filter = EventFilter::default();
+    let mut residual = vec![];
+    for conjunct in conjuncts {
+        if !translate_conjunct(conjunct, &mut filter) {
+            residual.push(normalize_email_comparisons(conjunct));
+        }
+    }
+
+    TranslatedPredicate {
+        filter,
+        residual: match residual.len() {
+            0 => None,
+            1 => residual.pop(),
+            _ => Some(Expression::And {
+                expressions: residual,
+            }),
+        },
+    }
+}
+
+/// Adds a conjunct to the filter, returning whether it could be
+fn translate_conjunct(conjunct: &Expression, filter: &mut EventFilter) -> bool {
+    let Expression::BinaryComparisonOperator {
+        column:
+            ComparisonTarget::Column {
+                name,
+                field_path: None,
+                path,
+            },
+        operator,
+        value: ComparisonValue::Scalar { value },
+    } = conjunct
+    else {
+        return false;
+    };
+    if !path.is_empty() {
+        return false;
+    }
+
+    if name.as_str() == "timestamp" {
+        let Some(time) = value
+            .as_str()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        else {
+            return false;
+        };
+        // Stored times are whole seconds
+        let (seconds, whole) = (time.timestamp(), time.timestamp_subsec_nanos() == 0);
+        let (since, until) = match operator.as_str() {
+            EQUAL_OPERATOR if whole => (Some(seconds), Some(seconds)),
+            // Matches nothing
+            EQUAL_OPERATOR => (Some(seconds + 1), Some(seconds)),
+            GREATER_THAN_OPERATOR => (Some(seconds + 1), None),
+            GREATER_THAN_OR_EQUAL_OPERATOR if whole => (Some(seconds), None),
+            GREATER_THAN_OR_EQUAL_OPERATOR => (Some(seconds + 1), None),
+            LESS_THAN_OPERATOR if whole => (None, Some(seconds - 1)),
+            LESS_THAN_OPERATOR | LESS_THAN_OR_EQUAL_OPERATOR => (None, Some(seconds)),
+            _ => return false,
+        };
+        if let Some(since) = since {
+            filter.since = Some(filter.since.map_or(since, |current| current.max(since)));
+        }
+        if let Some(until) = until {
+            filter.until = Some(filter.until.map_or(until, |current| current.min(until)));
+        }
+        return true;
+    }
+
+    let values = match (operator.as_str(), value) {
+        (EQUAL_OPERATOR, Value::String(value)) => vec![value.clone()],
+        (IN_OPERATOR, Value::Array(values)) => match values
+            .iter()
+            .map(|value| value.as_str().map(str::to_owned))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(values) => values,
+            None => return false,
+        },
+        _ => return false,
+    };
+    let list = match name.as_str() {
+        "sg_event_id" => &mut filter.sg_event_ids,
+        "sg_message_id" => &mut filter.sg_message_ids,
+        "email" => &mut filter.emails,
+        "event" => &mut filter.events,
+        _ => return false,
+    };
+    match list {
+        // A second comparison on the same column is left to the residual predicate
+        Some(_) => false,
+        None => {
+            *list = Some(values);
+            true
+        }
+    }
+}
+
+/// Lowercases the addresses an expression compares the email column to
+fn normalize_email_comparisons(expression: &Expression) -> Expression {
+    match expression {
+        Expression::And { expressions } => Expression::And {
+            expressions: expressions
+                .iter()
+                .map(normalize_email_comparisons)
+                .collect(),
+        },
+        Expression::Or { expressions } => Expression::Or {
+            expressions: expressions
+                .iter()
+                .map(normalize_email_comparisons)
+                .collect(),
+        },
+        Expression::Not { expression } => Expression::Not {
+            expression: Box::new(normalize_email_comparisons(expression)),
+        },
+        Expression::BinaryComparisonOperator {
+            column,
+            operator,
+            value: ComparisonValue::Scalar { value },
+        } if matches!(column, ComparisonTarget::Column { name, .. } if name.as_str() == "email") => {
+            let value = match value {
+                Value::String(email) => Value::String(normalize_email(email)),
+                Value::Array(values) => Value::Array(
+                    values
+                        .iter()
+                        .map(|value| match value {
+                            Value::String(email) => Value::String(normalize_email(email)),
+                            value => value.clone(),
+                        })
+                        .collect(),
+                ),
+                value => value.clone(),
+            };
+            Expression::BinaryComparisonOperator {
+                column: column.clone(),
+                operator: operator.clone(),
+                value: ComparisonValue::Scalar { value },
+            }
+        }
+        expression => expression.clone(),
+    }
+}
+
+impl EventStore {
+    /// Opens the database at the path, creating it and its tables if they do not exist. When a
+    /// retention period is given, events older than it are deleted as new events arrive.
+    pub fn open(path: &Path, retention: Option<Duration>) -> connector::Result<EventStore> {
+        let connection = Connection::open(path).map_err(|err| {
+            connector::ErrorResponse::from(format!(
+                "Unable to open the event store at {}: {err}",
+                path.display()
+            ))
+        })?;
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .map_err(store_error)?;
+        connection
+            .execute_batch(CREATE_SCHEMA)
+            .map_err(store_error)?;
+
+        Ok(EventStore {
+            connection: Arc::new(Mutex::new(connection)),
+            retention,
+        })
+    }
+
+    /// Stores the events, returning how many were new. SendGrid retries deliveries that fail, so
+    /// events that are already stored are ignored, as are events that are not well formed.
+    pub async fn insert_events(&self, events: Vec<Value>) -> connector::Result<usize> {
+        let connection = self.connection.clone();
+        let retention = self.retention;
+        run_blocking(move || {
+            let mut connection = connection.lock().map_err(|_| lock_error())?;
+            let transaction = connection.transaction().map_err(store_error)?;
+            let mut inserted = 0;
+            {
+                let mut statement = transaction
+                    .prepare_cached(&format!(
+                        "INSERT OR IGNORE INTO email_events ({EVENT_COLUMNS}, payload) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"
+                    ))
+                    .map_err(store_error)?;
+                for payload in events {
+                    let event = match WebhookEvent::deserialize(&payload) {
+                        Ok(event) => event,
+                        Err(err) => {
+                            tracing::warn!(error = %err, "Ignoring a malformed Event Webhook event");
+                            continue;
+                        }
+                    };
+                    let categories = event.category.map(|category| match category {
+                        WebhookCategories::One(category) => vec![category],
+                        WebhookCategories::Many(categories) => categories,
+                    });
+                    let attempt = event.attempt.as_ref().and_then(|attempt| match attempt {
+                        Value::Number(number) => number.as_i64(),
+                        Value::String(string) => string.parse().ok(),
+                        _ => None,
+                    });
+                    let unique_args = event
+                        .other_fields
+                        .into_iter()
+                        .filter(|(name, _)| !SENDGRID_EVENT_FIELDS.contains(&name.as_str()))
+                        .collect::<serde_json::Map<_, _>>();
+
+                    inserted += statement
+                        .execute(params![
+                            event.sg_event_id,
+                            event.sg_message_id,
+                            normalize_email(&event.email),
+                            event.event,
+                            event.timestamp,
+                            categories.map(|categories| Value::from(categories).to_string()),
+                            event.reason,
+                            event.status,
+                            event.response,
+                            attempt,
+                            event.bounce_type,
+                            event.url,
+                            event.useragent,
+                            event.ip,
+                            (!unique_args.is_empty()).then(|| Value::Object(unique_args).to_string()),
+                            payload.to_string(),
+                        ])
+                        .map_err(store_error)?;
+                }
+            }
+            if let Some(retention) = retention {
+                let cutoff = Utc::now().timestamp() - retention.as_secs() as i64;
+                let expired = transaction
+                    .execute("DELETE FROM email_events WHERE timestamp < ?1", [cutoff])
+                    .map_err(store_error)?;
+                if expired > 0 {
+                    tracing::debug!(expired, "Deleted events older than the retention period");
+                }
+            }
+            transaction.commit().map_err(store_error)?;
+            Ok(inserted)
+        })
+        .await
+    }
+
+    /// Reads the stored events that match the filter, in the order they happened unless the
+    /// filter sorts them otherwise
+    pub async fn query_events(&self, filter: EventFilter) -> connector::Result<Vec<EmailEvent>> {
+        let connection = self.connection.clone();
+        run_blocking(move || {
+            let mut conditions = vec![];
+            let mut params: Vec<String> = vec![];
+            for (column, values) in [
+                ("sg_event_id", filter.sg_event_ids),
+                ("sg_message_id", filter.sg_message_ids),
+                (
+                    "email",
+                    filter
+                        .emails
+                        .map(|emails| emails.iter().map(|email| normalize_email(email)).collect()),
+                ),
+                ("event", filter.events),
+            ] {
+                if let Some(values) = values {
+                    let placeholders = vec!["?"; values.len()].join(", ");
+                    conditions.push(format!("{column} IN ({placeholders})"));
+                    params.extend(values);
+                }
+            }
+            if let Some(prefix) = filter.sg_message_id_prefix {
+                conditions.push("substr(sg_message_id, 1, length(?)) = ?".to_owned());
+                params.push(prefix.clone());
+                params.push(prefix);
+            }
+            if let Some((name, value)) = filter.custom_arg {
+                conditions.push("json_extract(unique_args, ?) = ?".to_owned());
+                params.push(format!("$.\"{}\"", name.replace('"', "\\\"")));
+                params.push(value);
+            }
+
+            if let Some(since) = filter.since {
+                conditions.push(format!("timestamp >= {since}"));
+            }
+            if let Some(until) = filter.until {
+                conditions.push(format!("timestamp <= {until}"));
+            }
+
+            let mut sql = format!("SELECT {EVENT_COLUMNS} FROM email_events");
+            if !conditions.is_empty() {
+                sql.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+            }
+            let mut sort_keys = vec![];
+            for (column, direction) in filter.order_by {
+                if !SORTABLE_COLUMNS.contains(&column) {
+                    return Err(connector::ErrorResponse::from(format!(
+                        "The events cannot be sorted by {column}"
+                    )));
+                }
+                sort_keys.push(match direction {
+                    OrderDirection::Asc => format!("{column} ASC"),
+                    OrderDirection::Desc => format!("{column} DESC"),
+                });
+            }
+            sort_keys.extend(["timestamp".to_owned(), "sg_event_id".to_owned()]);
+            sql.push_str(&format!(" ORDER BY {}", sort_keys.join(", ")));
+            match (filter.limit, filter.offset) {
+                (Some(limit), offset) => {
+                    sql.push_str(&format!(" LIMIT {limit} OFFSET {}", offset.unwrap_or(0)))
+                }
+                (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {offset}")),
+                (None, None) => {}
+            }
+
+            let connection = connection.lock().map_err(|_| lock_error())?;
+            let mut statement = connection.prepare_cached(&sql).map_err(store_error)?;
+            let rows = statement
+                .query_map(params_from_iter(params), |row| {
+                    Ok(EmailEvent {
+                        sg_event_id: row.get(0)?,
+                        sg_message_id: row.get(1)?,
+                        email: row.get(2)?,
+                        event: row.get(3)?,
+                        timestamp: format_timestamp(row.get(4)?),
+                        categories: row
+                            .get::<_, Option<String>>(5)?
+                            .and_then(|categories| serde_json::from_str(&categories).ok()),
+                        reason: row.get(6)?,
+                        status: row.get(7)?,
+                        response: row.get(8)?,
+                        attempt: row.get(9)?,
+                        bounce_type: row.get(10)?,
+                        url: row.get(11)?,
+                        useragent: row.get(12)?,
+                        ip: row.get(13)?,
+                        unique_args: row
+                            .get::<_, Option<String>>(14)?
+                            .and_then(|unique_args| serde_json::from_str(&unique_args).ok()),
+                    })
+                })
+                .map_err(store_error)?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(store_error)
+        })
+        .await
+    }
+}
+
+/// Formats a unix time the way the Email Activity API formats its timestamps
+pub fn format_timestamp(unix_time: i64) -> String {
+    DateTime::from_timestamp(unix_time, 0)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Addresses are stored lowercased, so that events can be looked up by address ignoring case
+pub fn normalize_email(email: &str) -> String {
+    email.to_lowercase()
+}
+
+/// Runs a database operation on the blocking thread pool, as SQLite calls block
+async fn run_blocking<T: Send + 'static>(
+    operation: impl FnOnce() -> connector::Result<T> + Send + 'static,
+) -> connector::Result<T> {
+    tokio::task::spawn_blocking(operation)
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?
+}
+
+fn store_error(err: rusqlite::Error) -> connector::ErrorResponse {
+    connector::ErrorResponse::from(format!("Event store error: {err}"))
+}
+
+fn lock_error() -> connector::ErrorResponse {
+    connector::ErrorResponse::from("The event store is unavailable".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use ndc_sdk::models::{ComparisonOperatorName, FieldName};
+    use serde_json::json;
+
+    use super::*;
+
+    fn compare(column: &str, operator: &str, value: Value) -> Expression {
+        Expression::BinaryComparisonOperator {
+            column: ComparisonTarget::Column {
+                name: FieldName::from(column),
+                field_path: None,
+                path: vec![],
+            },
+            operator: ComparisonOperatorName::from(operator),
+            value: ComparisonValue::Scalar { value },
+        }
+    }
+
+    fn event(sg_event_id: &str, email: &str, event: &str, timestamp: i64) -> Value {
+        json!({
+            "sg_event_id": sg_event_id,
+            "sg_message_id": "msg-1.filter",
+            "email": email,
+            "event": event,
+            "timestamp": timestamp,
+            "campaign": "spring",
+        })
+    }
+
+    fn event_ids(events: &[EmailEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|event| event.sg_event_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn timestamp_comparisons_become_inclusive_whole_second_bounds() {
+        let translated = translate_predicate(&Expression::And {
+            expressions: vec![
+                compare(
+                    "timestamp",
+                    GREATER_THAN_OPERATOR,
+                    json!("2024-05-01T12:00:00Z"),
+                ),
+                compare(
+                    "timestamp",
+                    LESS_THAN_OR_EQUAL_OPERATOR,
+                    json!("2024-05-01T12:10:00.500Z"),
+                ),
+                compare("event", IN_OPERATOR, json!(["delivered", "open"])),
+            ],
+        });
+        assert_eq!(translated.filter.since, Some(1714564801));
+        assert_eq!(translated.filter.until, Some(1714565400));
+        assert_eq!(
+            translated.filter.events,
+            Some(vec![String::from("delivered"), String::from("open")])
+        );
+        assert!(translated.residual.is_none());
+    }
+
+    #[test]
+    fn untranslated_conjuncts_compare_lowercased_addresses() {
+        let translated = translate_predicate(&Expression::Or {
+            expressions: vec![
+                compare("email", EQUAL_OPERATOR, json!("Ada@Example.com")),
+                compare("reason", EQUAL_OPERATOR, json!("Mailbox full")),
+            ],
+        });
+        assert_eq!(translated.filter.emails, None);
+        assert_eq!(
+            translated.residual,
+            Some(Expression::Or {
+                expressions: vec![
+                    compare("email", EQUAL_OPERATOR, json!("ada@example.com")),
+                    compare("reason", EQUAL_OPERATOR, json!("Mailbox full")),
+                ],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn events_are_filtered_sorted_and_paginated_by_sqlite() {
+        let store = EventStore::open(Path::new(":memory:"), None).unwrap();
+        let inserted = store
+            .insert_events(vec![
+                event("e1", "Ada@Example.com", "processed", 100),
+                event("e2", "ada@example.com", "delivered", 200),
+                event("e3", "grace@example.com", "delivered", 300),
+                event("e4", "ada@example.com", "open", 400),
+                event("e2", "ada@example.com", "delivered", 200),
+                json!({ "not": "an event" }),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(inserted, 4);
+
+        let events = store
+            .query_events(EventFilter {
+                emails: Some(vec![String::from("ADA@example.com")]),
+                since: Some(150),
+                order_by: vec![("timestamp", OrderDirection::Desc)],
+                offset: Some(1),
+                limit: Some(5),
+                ..EventFilter::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(event_ids(&events), vec!["e2"]);
+        assert_eq!(events[0].email, "ada@example.com");
+        assert_eq!(events[0].timestamp, "1970-01-01T00:03:20Z");
+        assert_eq!(events[0].unique_args, Some(json!({ "campaign": "spring" })));
+
+        let events = store
+            .query_events(EventFilter {
+                custom_arg: Some((String::from("campaign"), String::from("spring"))),
+                sg_message_id_prefix: Some(String::from("msg-1")),
+                ..EventFilter::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(event_ids(&events), vec!["e1", "e2", "e3", "e4"]);
+    }
+
+    #[tokio::test]
+    async fn events_older_than_the_retention_period_are_deleted() {
+        let store =
+            EventStore::open(Path::new(":memory:"), Some(Duration::from_secs(3600))).unwrap();
+        let now = Utc::now().timestamp();
+        store
+            .insert_events(vec![
+                event("old", "ada@example.com", "delivered", now - 7200),
+                event("new", "ada@example.com", "open", now - 60),
+            ])
+            .await
+            .unwrap();
+
+        let events = store.query_events(EventFilter::default()).await.unwrap();
+        assert_eq!(event_ids(&events), vec!["new"]);
+    }
+}
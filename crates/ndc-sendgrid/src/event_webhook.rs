@@ -0,0 +1,120 @@
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use ndc_sdk::connector;
+use serde_json::Value;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+
+use super::configuration::EventWebhookVerificationConfiguration;
+use super::event_store::EventStore;
+use super::webhook_signature::{self, SignatureError, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+/// The route SendGrid's Event Webhook should be configured to post events to
+pub const EVENT_WEBHOOK_ROUTE: &str = "/webhooks/sendgrid/events";
+
+#[derive(Clone, Debug)]
+struct WebhookState {
+    event_store: EventStore,
+    /// When set, only requests signed by SendGrid are accepted
+    verification: Option<EventWebhookVerificationConfiguration>,
+}
+
+/// Binds the address the Event Webhook route is served on, returning the server that stores the
+/// events SendGrid posts to it. Nothing is served until the server is spawned, so the connector can
+/// still fail to start after binding without leaving a task behind.
+pub fn bind_server(
+    event_store: EventStore,
+    verification: Option<EventWebhookVerificationConfiguration>,
+    host: IpAddr,
+    port: u16,
+) -> connector::Result<impl Future<Output = ()> + Send + 'static> {
+    let router = Router::new()
+        .route(EVENT_WEBHOOK_ROUTE, post(receive_events))
+        .with_state(WebhookState {
+            event_store,
+            verification,
+        });
+    let address = SocketAddr::from((host, port));
+    let server = axum::Server::try_bind(&address)
+        .map_err(|err| {
+            connector::ErrorResponse::from(format!(
+                "Unable to serve the Event Webhook on {address}: {err}"
+            ))
+        })?
+        .serve(router.into_make_service());
+
+    Ok(async move {
+        if let Err(err) = server.await {
+            tracing::error!(error = ?err, "The Event Webhook server stopped");
+        }
+    })
+}
+
+/// Stores a batch of events posted by SendGrid, after checking its signature if verification is
+/// enabled. Any response other than a success makes SendGrid retry the batch, so only failures to
+/// store the events are reported as server errors.
+async fn receive_events(
+    State(state): State<WebhookState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    if let Some(verification) = &state.verification {
+        if let Err(err) = verify_request(verification, &headers, &body) {
+            tracing::warn!(error = %err, "Rejecting an Event Webhook request that failed verification");
+            return (
+                StatusCode::UNAUTHORIZED,
+                format!("The Event Webhook request failed verification: {err}"),
+            );
+        }
+    }
+
+    let events = match serde_json::from_slice::<Vec<Value>>(&body) {
+        Ok(events) => events,
+        Err(err) => {
+            tracing::warn!(error = %err, "Rejecting an Event Webhook request that is not a JSON array of events");
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("The Event Webhook request is not a JSON array of events: {err}"),
+            );
+        }
+    };
+
+    match state.event_store.insert_events(events).await {
+        Ok(inserted) => {
+            tracing::debug!(inserted, "Stored Event Webhook events");
+            (StatusCode::OK, String::new())
+        }
+        Err(err) => {
+            tracing::error!(error = ?err, "Failed to store Event Webhook events");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                String::from("The events could not be stored"),
+            )
+        }
+    }
+}
+
+fn verify_request(
+    verification: &EventWebhookVerificationConfiguration,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<(), SignatureError> {
+    let header = |name: &'static str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(SignatureError::MissingHeader(name))
+    };
+    webhook_signature::verify_signature(
+        &verification.public_key,
+        body,
+        header(SIGNATURE_HEADER)?,
+        header(TIMESTAMP_HEADER)?,
+        verification.timestamp_tolerance_seconds,
+    )
+}
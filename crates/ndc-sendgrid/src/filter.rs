@@ -0,0 +1,458 @@
+use std::cmp::Ordering;
+
+use indexmap::IndexMap;
+use ndc_sdk::{
+    connector::{self, QueryError},
+    models::{
+        ComparisonOperatorName, ComparisonTarget, ComparisonValue, Expression,
+        UnaryComparisonOperator,
+    },
+};
+use serde_json::Value;
+
+pub const EQUAL_OPERATOR: &str = "_eq";
+pub const NOT_EQUAL_OPERATOR: &str = "_neq";
+pub const IN_OPERATOR: &str = "_in";
+pub const GREATER_THAN_OPERATOR: &str = "_gt";
+pub const GREATER_THAN_OR_EQUAL_OPERATOR: &str = "_gte";
+pub const LESS_THAN_OPERATOR: &str = "_lt";
+pub const LESS_THAN_OR_EQUAL_OPERATOR: &str = "_lte";
+pub const CONTAINS_OPERATOR: &str = "_contains";
+
+/// Evaluates a predicate from a query's `where` clause against a row of a collection
+pub fn eval_predicate(
+    predicate: &Expression,
+    row: &IndexMap<String, Value>,
+) -> connector::Result<bool> {
+    match predicate {
+        Expression::And { expressions } => {
+            for expression in expressions {
+                if !eval_predicate(expression, row)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        Expression::Or { expressions } => {
+            for expression in expressions {
+                if eval_predicate(expression, row)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Expression::Not { expression } => Ok(!eval_predicate(expression, row)?),
+        Expression::UnaryComparisonOperator { column, operator } => match operator {
+            UnaryComparisonOperator::IsNull => Ok(eval_comparison_target(column, row)?.is_null()),
+        },
+        Expression::BinaryComparisonOperator {
+            column,
+            operator,
+            value,
+        } => {
+            let left = eval_comparison_target(column, row)?;
+            let right = eval_comparison_value(value, row)?;
+            eval_binary_comparison(operator, &left, &right)
+        }
+        Expression::Exists { .. } => Err(QueryError::new_unsupported_operation(
+            &"Exists expressions are not supported",
+        )
+        .into()),
+    }
+}
+
+fn eval_binary_comparison(
+    operator: &ComparisonOperatorName,
+    left: &Value,
+    right: &Value,
+) -> connector::Result<bool> {
+    match operator.as_str() {
+        EQUAL_OPERATOR => Ok(compare_values(left, right) == Some(Ordering::Equal)),
+        NOT_EQUAL_OPERATOR => Ok(compare_values(left, right) != Some(Ordering::Equal)),
+        IN_OPERATOR => match right {
+            Value::Array(values) => Ok(values
+                .iter()
+                .any(|value| compare_values(left, value) == Some(Ordering::Equal))),
+            _ => Err(QueryError::new_invalid_request(&format!(
+                "The {IN_OPERATOR} operator requires an array value"
+            ))
+            .into()),
+        },
+        GREATER_THAN_OPERATOR => Ok(compare_ordered(left, right) == Some(Ordering::Greater)),
+        GREATER_THAN_OR_EQUAL_OPERATOR => Ok(matches!(
+            compare_ordered(left, right),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        LESS_THAN_OPERATOR => Ok(compare_ordered(left, right) == Some(Ordering::Less)),
+        LESS_THAN_OR_EQUAL_OPERATOR => Ok(matches!(
+            compare_ordered(left, right),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        CONTAINS_OPERATOR => Ok(contains(left, right)),
+        unknown_operator => Err(QueryError::new_unsupported_operation(&format!(
+            "Unknown comparison operator: {unknown_operator}"
+        ))
+        .into()),
+    }
+}
+
+fn eval_comparison_target(
+    target: &ComparisonTarget,
+    row: &IndexMap<String, Value>,
+) -> connector::Result<Value> {
+    let (name, field_path) = match target {
+        ComparisonTarget::Column {
+            name,
+            field_path,
+            path,
+        } => {
+            if !path.is_empty() {
+                return Err(QueryError::new_unsupported_operation(
+                    &"Comparisons across relationships are not supported",
+                )
+                .into());
+            }
+            (name, field_path)
+        }
+        ComparisonTarget::RootCollectionColumn { name, field_path } => (name, field_path),
+    };
+
+    let value = row.get(name.as_str()).cloned().unwrap_or(Value::Null);
+    Ok(field_path
+        .iter()
+        .flatten()
+        .fold(value, |value, field_name| {
+            value
+                .get(field_name.as_str())
+                .cloned()
+                .unwrap_or(Value::Null)
+        }))
+}
+
+fn eval_comparison_value(
+    value: &ComparisonValue,
+    row: &IndexMap<String, Value>,
+) -> connector::Result<Value> {
+    match value {
+        ComparisonValue::Column { column } => eval_comparison_target(column, row),
+        ComparisonValue::Scalar { value } => Ok(value.clone()),
+        ComparisonValue::Variable { .. } => {
+            Err(QueryError::new_unsupported_operation(&"Variables not currently supported").into())
+        }
+    }
+}
+
+/// Whether an array contains a value, or an object contains every entry of another object
+fn contains(container: &Value, contained: &Value) -> bool {
+    match (container, contained) {
+        (Value::Array(values), contained) => values
+            .iter()
+            .any(|value| compare_values(value, contained) == Some(Ordering::Equal)),
+        (Value::Object(entries), Value::Object(contained_entries)) => {
+            contained_entries.iter().all(|(key, contained_value)| {
+                entries.get(key).is_some_and(|value| {
+                    compare_values(value, contained_value) == Some(Ordering::Equal)
+                })
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Compares two values for an ordering comparison operator. Null is not ordered relative to any
+/// value, so a comparison with a null operand never matches.
+fn compare_ordered(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        _ => compare_values(left, right),
+    }
+}
+
+/// Compares two scalar values, returning `None` if they are not comparable. Null sorts before
+/// every other value, as when sorting rows.
+pub fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        (Value::Null, _) => Some(Ordering::Less),
+        (_, Value::Null) => Some(Ordering::Greater),
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+/// The scalar values a predicate compares a column to, for each of the comparisons on that
+/// column that must hold for the whole predicate to hold. Used to push parts of a predicate
+/// down to the SendGrid API; the full predicate is still evaluated against the returned rows.
+pub fn required_comparisons<'a>(
+    predicate: &'a Expression,
+    column: &str,
+) -> Vec<(&'a str, &'a Value)> {
+    match predicate {
+        Expression::And { expressions } => expressions
+            .iter()
+            .flat_map(|expression| required_comparisons(expression, column))
+            .collect(),
+        Expression::BinaryComparisonOperator {
+            column:
+                ComparisonTarget::Column {
+                    name,
+                    field_path: None,
+                    path,
+                },
+            operator,
+            value: ComparisonValue::Scalar { value },
+        } if name.as_str() == column && path.is_empty() => vec![(operator.as_str(), value)],
+        _ => vec![],
+    }
+}
+
+/// The lower and upper bounds a predicate places on an integer column, if any
+pub fn required_range(predicate: &Expression, column: &str) -> (Option<i64>, Option<i64>) {
+    required_comparisons(predicate, column).into_iter().fold(
+        (None, None),
+        |(lower, upper), (operator, value)| match (operator, value.as_i64()) {
+            (GREATER_THAN_OPERATOR | GREATER_THAN_OR_EQUAL_OPERATOR, Some(bound)) => (
+                Some(lower.map_or(bound, |lower: i64| lower.max(bound))),
+                upper,
+            ),
+            (LESS_THAN_OPERATOR | LESS_THAN_OR_EQUAL_OPERATOR, Some(bound)) => (
+                lower,
+                Some(upper.map_or(bound, |upper: i64| upper.min(bound))),
+            ),
+            (EQUAL_OPERATOR, Some(bound)) => (Some(bound), Some(bound)),
+            _ => (lower, upper),
+        },
+    )
+}
+
+/// The value a predicate requires a column to be equal to, if any
+pub fn required_equality<'a>(predicate: &'a Expression, column: &str) -> Option<&'a Value> {
+    required_comparisons(predicate, column)
+        .into_iter()
+        .find(|(operator, _)| *operator == EQUAL_OPERATOR)
+        .map(|(_, value)| value)
+}
+
+/// The values a predicate requires a column to be one of, from an `_eq` or `_in` comparison, if
+/// any
+pub fn required_values<'a>(predicate: &'a Expression, column: &str) -> Option<Vec<&'a Value>> {
+    required_comparisons(predicate, column)
+        .into_iter()
+        .find_map(|(operator, value)| match (operator, value) {
+            (EQUAL_OPERATOR, value) => Some(vec![value]),
+            (IN_OPERATOR, Value::Array(values)) => Some(values.iter().collect()),
+            _ => None,
+        })
+}
+
+/// The first of the given columns that a predicate compares, if any
+pub fn referenced_column<'a>(predicate: &Expression, columns: &[&'a str]) -> Option<&'a str> {
+    let find = |target: &ComparisonTarget| {
+        let name = match target {
+            ComparisonTarget::Column { name, .. }
+            | ComparisonTarget::RootCollectionColumn { name, .. } => name,
+        };
+        columns
+            .iter()
+            .copied()
+            .find(|column| *column == name.as_str())
+    };
+    match predicate {
+        Expression::And { expressions } | Expression::Or { expressions } => expressions
+            .iter()
+            .find_map(|expression| referenced_column(expression, columns)),
+        Expression::Not { expression } => referenced_column(expression, columns),
+        Expression::UnaryComparisonOperator { column, .. } => find(column),
+        Expression::BinaryComparisonOperator { column, value, .. } => {
+            find(column).or(match value {
+                ComparisonValue::Column { column } => find(column),
+                _ => None,
+            })
+        }
+        Expression::Exists { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndc_sdk::models::FieldName;
+    use serde_json::json;
+
+    use super::*;
+
+    fn compare(column: &str, operator: &str, value: Value) -> Expression {
+        Expression::BinaryComparisonOperator {
+            column: ComparisonTarget::Column {
+                name: FieldName::from(column),
+                field_path: None,
+                path: vec![],
+            },
+            operator: ComparisonOperatorName::from(operator),
+            value: ComparisonValue::Scalar { value },
+        }
+    }
+
+    fn row(entries: Value) -> IndexMap<String, Value> {
+        serde_json::from_value(entries).unwrap()
+    }
+
+    #[test]
+    fn required_range_combines_the_bounds_of_every_conjunct() {
+        let predicate = Expression::And {
+            expressions: vec![
+                compare("created", GREATER_THAN_OR_EQUAL_OPERATOR, json!(100)),
+                compare("created", GREATER_THAN_OPERATOR, json!(150)),
+                compare("created", LESS_THAN_OPERATOR, json!(300)),
+                compare("email", EQUAL_OPERATOR, json!("ada@example.com")),
+            ],
+        };
+        assert_eq!(
+            required_range(&predicate, "created"),
+            (Some(150), Some(300))
+        );
+        assert_eq!(
+            required_equality(&predicate, "email"),
+            Some(&json!("ada@example.com"))
+        );
+    }
+
+    #[test]
+    fn referenced_column_finds_comparisons_at_any_depth() {
+        let predicate = Expression::And {
+            expressions: vec![
+                compare("delivered", GREATER_THAN_OPERATOR, json!(100)),
+                Expression::Not {
+                    expression: Box::new(compare("name", EQUAL_OPERATOR, json!("gmail"))),
+                },
+            ],
+        };
+        assert_eq!(
+            referenced_column(&predicate, &["date", "name"]),
+            Some("name")
+        );
+        assert_eq!(referenced_column(&predicate, &["date"]), None);
+    }
+
+    #[test]
+    fn comparisons_under_or_and_not_are_not_required() {
+        let predicate = Expression::Or {
+            expressions: vec![
+                compare("created", GREATER_THAN_OPERATOR, json!(100)),
+                Expression::Not {
+                    expression: Box::new(compare("created", LESS_THAN_OPERATOR, json!(50))),
+                },
+            ],
+        };
+        assert_eq!(required_range(&predicate, "created"), (None, None));
+        assert!(required_comparisons(&predicate, "created").is_empty());
+    }
+
+    #[test]
+    fn eval_predicate_evaluates_boolean_combinations() {
+        let row = row(json!({ "email": "ada@example.com", "created": 120, "reason": null }));
+        let predicate = Expression::And {
+            expressions: vec![
+                compare("created", GREATER_THAN_OR_EQUAL_OPERATOR, json!(100)),
+                Expression::Or {
+                    expressions: vec![
+                        compare("email", EQUAL_OPERATOR, json!("grace@example.com")),
+                        compare(
+                            "email",
+                            IN_OPERATOR,
+                            json!(["ada@example.com", "alan@example.com"]),
+                        ),
+                    ],
+                },
+                Expression::Not {
+                    expression: Box::new(compare("reason", EQUAL_OPERATOR, json!("spam"))),
+                },
+            ],
+        };
+        assert!(eval_predicate(&predicate, &row).unwrap());
+        assert!(
+            !eval_predicate(&compare("created", LESS_THAN_OPERATOR, json!(100)), &row).unwrap()
+        );
+    }
+
+    #[test]
+    fn required_values_accepts_an_equality_or_a_list() {
+        let predicate = Expression::And {
+            expressions: vec![
+                compare("country", IN_OPERATOR, json!(["DE", "FR"])),
+                compare("device_type", EQUAL_OPERATOR, json!("Phone")),
+            ],
+        };
+        assert_eq!(
+            required_values(&predicate, "country"),
+            Some(vec![&json!("DE"), &json!("FR")])
+        );
+        assert_eq!(
+            required_values(&predicate, "device_type"),
+            Some(vec![&json!("Phone")])
+        );
+        assert_eq!(required_values(&predicate, "browser"), None);
+    }
+
+    #[test]
+    fn ordering_comparisons_never_match_null() {
+        let row = row(json!({ "reason": null, "created": 120 }));
+        for operator in [
+            GREATER_THAN_OPERATOR,
+            GREATER_THAN_OR_EQUAL_OPERATOR,
+            LESS_THAN_OPERATOR,
+            LESS_THAN_OR_EQUAL_OPERATOR,
+        ] {
+            assert!(!eval_predicate(&compare("reason", operator, json!("m")), &row).unwrap());
+            assert!(!eval_predicate(&compare("missing", operator, json!(1)), &row).unwrap());
+            assert!(!eval_predicate(&compare("created", operator, Value::Null), &row).unwrap());
+        }
+        assert!(eval_predicate(&compare("reason", EQUAL_OPERATOR, Value::Null), &row).unwrap());
+        assert!(eval_predicate(&compare("created", LESS_THAN_OPERATOR, json!(200)), &row).unwrap());
+    }
+
+    #[test]
+    fn compare_values_sorts_null_first_and_refuses_mixed_types() {
+        assert_eq!(
+            compare_values(&Value::Null, &json!(1)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_values(&json!(2), &json!(1.5)),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(compare_values(&json!("1"), &json!(1)), None);
+    }
+
+    #[test]
+    fn contains_matches_array_elements_and_object_entries() {
+        let row = row(json!({
+            "categories": ["welcome", "onboarding"],
+            "unique_args": { "campaign": "spring", "user_id": "42" },
+        }));
+        assert!(eval_predicate(
+            &compare("categories", CONTAINS_OPERATOR, json!("welcome")),
+            &row
+        )
+        .unwrap());
+        assert!(eval_predicate(
+            &compare(
+                "unique_args",
+                CONTAINS_OPERATOR,
+                json!({ "campaign": "spring" })
+            ),
+            &row
+        )
+        .unwrap());
+        assert!(!eval_predicate(
+            &compare(
+                "unique_args",
+                CONTAINS_OPERATOR,
+                json!({ "campaign": "autumn" })
+            ),
+            &row
+        )
+        .unwrap());
+    }
+}
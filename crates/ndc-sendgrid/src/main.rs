@@ -1,10 +1,21 @@
+mod activity_query;
+mod aggregates;
+mod bounce_classifier;
 mod configuration;
 mod connector;
+mod delivery_status;
+mod event_store;
+mod event_webhook;
 mod fields;
+mod filter;
 mod mutation;
+mod preference_links;
 mod query;
+mod recipients;
 mod schema;
 mod sendgrid_api;
+mod suppression_cache;
+mod webhook_signature;
 
 use connector::SendGridConnector;
 use ndc_sdk::default_main::default_main;
@@ -1,27 +1,47 @@
+use futures::stream::{self, StreamExt, TryStreamExt};
 use ndc_sdk::connector::{self, MutationError};
 use ndc_sdk::models::{
     ArgumentName, MutationOperation, MutationOperationResults, MutationRequest, MutationResponse,
     NestedField,
 };
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
 
+use crate::connector::SendGridConnectorState;
 use crate::fields::eval_nested_field;
-use crate::schema::SEND_MAIL;
+use crate::schema::{
+    ADD_GLOBAL_UNSUBSCRIBES, ADD_GROUP_SUPPRESSIONS, CANCEL_SCHEDULED_SEND, CREATE_BATCH_ID,
+    CREATE_UNSUBSCRIBE_GROUP, CREATE_VERIFIED_SENDER, DELETE_BLOCKS, DELETE_BOUNCES,
+    DELETE_GLOBAL_UNSUBSCRIBE, DELETE_GROUP_SUPPRESSION, DELETE_INVALID_EMAILS,
+    DELETE_SPAM_REPORTS, DELETE_UNSUBSCRIBE_GROUP, DELETE_VERIFIED_SENDER, DUPLICATE_DESIGN,
+    EXPORT_CONTACTS, IMPORT_CONTACTS, PAUSE_SCHEDULED_SEND, REFRESH_SUPPRESSION_CACHE,
+    RESEND_SENDER_VERIFICATION, RESUME_SCHEDULED_SEND, SEND_MAIL, UPDATE_UNSUBSCRIBE_GROUP,
+};
+use crate::sendgrid_api::{
+    RequestError, ScheduledSend, ScheduledSendStatus, ScheduledSendStatusRequest, SuppressionList,
+};
+use crate::suppression_cache::SuppressionCache;
 
 use super::configuration;
+use super::delivery_status::BATCH_ID_CUSTOM_ARG;
+use super::preference_links;
+use super::query;
+use super::recipients;
 use super::schema;
 use super::sendgrid_api;
 
 pub async fn execute(
-    http_client: &reqwest::Client,
+    state: &SendGridConnectorState,
     configuration: &configuration::SendGridConfiguration,
     mutation_request: MutationRequest,
 ) -> connector::Result<MutationResponse> {
     let mut operation_results = vec![];
 
     for operation in mutation_request.operations {
-        let result = process_operation(http_client, configuration, operation).await?;
+        let result = process_operation(state, configuration, operation).await?;
         operation_results.push(result)
     }
 
@@ -29,17 +49,165 @@ pub async fn execute(
 }
 
 async fn process_operation(
-    http_client: &reqwest::Client,
+    state: &SendGridConnectorState,
     configuration: &configuration::SendGridConfiguration,
     mutation_operation: MutationOperation,
 ) -> connector::Result<MutationOperationResults> {
+    let http_client = &state.http_client;
+    let suppression_cache = state.suppression_cache.as_ref();
+
     match mutation_operation {
         MutationOperation::Procedure {
             name,
             arguments,
             fields,
         } => match name.as_str() {
-            SEND_MAIL => process_send_mail(http_client, configuration, arguments, fields).await,
+            SEND_MAIL => {
+                process_send_mail(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            CREATE_BATCH_ID => process_create_batch_id(http_client, configuration, fields).await,
+            PAUSE_SCHEDULED_SEND => {
+                process_set_scheduled_send_status(
+                    http_client,
+                    configuration,
+                    ScheduledSendStatus::Pause,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            CANCEL_SCHEDULED_SEND => {
+                process_set_scheduled_send_status(
+                    http_client,
+                    configuration,
+                    ScheduledSendStatus::Cancel,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            RESUME_SCHEDULED_SEND => {
+                process_resume_scheduled_send(http_client, configuration, arguments, fields).await
+            }
+            IMPORT_CONTACTS => {
+                process_import_contacts(http_client, configuration, arguments, fields).await
+            }
+            EXPORT_CONTACTS => {
+                process_export_contacts(http_client, configuration, arguments, fields).await
+            }
+            DUPLICATE_DESIGN => {
+                process_duplicate_design(http_client, configuration, arguments, fields).await
+            }
+            CREATE_VERIFIED_SENDER => {
+                process_create_verified_sender(http_client, configuration, arguments, fields).await
+            }
+            RESEND_SENDER_VERIFICATION => {
+                process_resend_sender_verification(http_client, configuration, arguments, fields)
+                    .await
+            }
+            DELETE_VERIFIED_SENDER => {
+                process_delete_verified_sender(http_client, configuration, arguments, fields).await
+            }
+            DELETE_BOUNCES => {
+                process_delete_suppressions(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    SuppressionList::Bounces,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            DELETE_BLOCKS => {
+                process_delete_suppressions(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    SuppressionList::Blocks,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            DELETE_SPAM_REPORTS => {
+                process_delete_suppressions(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    SuppressionList::SpamReports,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            ADD_GLOBAL_UNSUBSCRIBES => {
+                process_add_global_unsubscribes(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            DELETE_GLOBAL_UNSUBSCRIBE => {
+                process_delete_global_unsubscribe(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            REFRESH_SUPPRESSION_CACHE => {
+                process_refresh_suppression_cache(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
+            CREATE_UNSUBSCRIBE_GROUP => {
+                process_create_unsubscribe_group(http_client, configuration, arguments, fields)
+                    .await
+            }
+            UPDATE_UNSUBSCRIBE_GROUP => {
+                process_update_unsubscribe_group(http_client, configuration, arguments, fields)
+                    .await
+            }
+            DELETE_UNSUBSCRIBE_GROUP => {
+                process_delete_unsubscribe_group(http_client, configuration, arguments, fields)
+                    .await
+            }
+            ADD_GROUP_SUPPRESSIONS => {
+                process_add_group_suppressions(http_client, configuration, arguments, fields).await
+            }
+            DELETE_GROUP_SUPPRESSION => {
+                process_delete_group_suppression(http_client, configuration, arguments, fields)
+                    .await
+            }
+            DELETE_INVALID_EMAILS => {
+                process_delete_suppressions(
+                    http_client,
+                    configuration,
+                    suppression_cache,
+                    SuppressionList::InvalidEmails,
+                    arguments,
+                    fields,
+                )
+                .await
+            }
             unknown_procedure => Err(MutationError::new_invalid_request(&format!(
                 "Unknown procedure: {unknown_procedure}"
             ))
@@ -51,45 +219,456 @@ async fn process_operation(
 async fn process_send_mail(
     http_client: &reqwest::Client,
     configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
     arguments: BTreeMap<ArgumentName, Value>,
     fields: Option<NestedField>,
 ) -> connector::Result<MutationOperationResults> {
-    let request = parse_send_mail_args(&arguments)?;
+    let options = parse_procedure_args::<schema::SendMailOptions>(&arguments)?;
+    let (mut request, mut recipient_changes) = parse_send_mail_args(&arguments, &options)?;
+    let auto_batch = options.auto_batch.unwrap_or(false);
+    if auto_batch && request.batch_id.is_some() {
+        return Err(MutationError::new_invalid_request(
+            &"auto_batch cannot be used together with batch_id",
+        )
+        .into());
+    }
+    recipient_changes.extend(recipients::apply_recipient_safety(
+        &mut request,
+        &configuration.recipient_safety,
+    )?);
 
-    sendgrid_api::invoke_send_mail(http_client, &configuration.sendgrid_api_key, &request)
+    if options.verify_from_address.unwrap_or(false) {
+        check_from_addresses_verified(http_client, configuration, &request).await?;
+    }
+
+    let suppression_outcome = match options.suppression_policy {
+        Some(policy) => {
+            apply_suppression_policy(
+                http_client,
+                configuration,
+                suppression_cache,
+                policy,
+                &mut request,
+            )
+            .await?
+        }
+        None => SuppressionPolicyOutcome::default(),
+    };
+    recipient_changes.extend(suppression_outcome.recipient_changes);
+
+    if options.list_unsubscribe.unwrap_or(false) {
+        let preference_links = configuration.preference_links.as_ref().ok_or_else(|| {
+            MutationError::new_invalid_request(
+                &"list_unsubscribe requires SENDGRID_PREFERENCE_LINK_SECRET and SENDGRID_PREFERENCE_LINK_BASE_URL to be set",
+            )
+        })?;
+        preference_links::add_list_unsubscribe_headers(
+            preference_links,
+            &mut request,
+            configuration.recipient_safety.redirect_to.as_deref(),
+        );
+    }
+
+    let sent = !request.personalizations.is_empty();
+    if sent && auto_batch {
+        let batch = sendgrid_api::invoke_create_batch_id(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+        )
         .await
         .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+        request.batch_id = Some(batch.batch_id);
+    }
 
-    let batch_id_value = request.batch_id.clone().map_or(Value::Null, Value::String);
-    let result_value = Value::Object(serde_json::Map::from_iter([(
-        "batch_id".to_string(),
-        batch_id_value,
-    )]));
+    // Tag batched mail so that get_delivery_status can find it by batch ID
+    if let (true, Some(batch_id)) = (options.tag_batch.unwrap_or(false), &request.batch_id) {
+        request
+            .custom_args
+            .get_or_insert_with(BTreeMap::new)
+            .entry(BATCH_ID_CUSTOM_ARG.to_owned())
+            .or_insert_with(|| batch_id.clone());
+    }
 
-    let projected_result_value = match fields {
-        Some(fields) => eval_nested_field(result_value, &fields)?.0,
-        None => result_value,
+    let message_id = if sent {
+        sendgrid_api::invoke_send_mail(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &request,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?
+    } else {
+        None
     };
 
-    Ok(MutationOperationResults::Procedure {
-        result: projected_result_value,
-    })
+    procedure_result(
+        schema::SendMailResponse {
+            batch_id: request.batch_id,
+            message_id,
+            sent,
+            recipient_changes,
+            suppressed_recipients: suppression_outcome.suppressed_recipients,
+            skipped_personalizations: suppression_outcome.skipped_personalizations,
+        },
+        fields,
+    )
+}
+
+async fn process_create_batch_id(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let batch = sendgrid_api::invoke_create_batch_id(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(batch, fields)
+}
+
+/// Pauses or cancels a batch's scheduled send. A batch that is already paused or cancelled has
+/// its status changed instead, and one that already has the status is left as it is.
+async fn process_set_scheduled_send_status(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    status: ScheduledSendStatus,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::BatchIdArgs>(&arguments)?;
+
+    let current = sendgrid_api::invoke_get_scheduled_send(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &args.batch_id,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    let scheduled_send = match current {
+        Some(current) if current.status == status => current,
+        // Pausing a cancelled batch would make its discarded mail sendable again on resume
+        Some(ScheduledSend {
+            status: ScheduledSendStatus::Cancel,
+            ..
+        }) if status == ScheduledSendStatus::Pause => {
+            return Err(MutationError::new_invalid_request(&format!(
+                "The scheduled send of batch {} is cancelled, so it cannot be paused. Resume it first.",
+                args.batch_id
+            ))
+            .into())
+        }
+        Some(_) => {
+            sendgrid_api::invoke_update_scheduled_send(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &args.batch_id,
+                &ScheduledSendStatusRequest { status },
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            ScheduledSend {
+                batch_id: args.batch_id,
+                status,
+            }
+        }
+        None => sendgrid_api::invoke_create_scheduled_send(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &ScheduledSend {
+                batch_id: args.batch_id,
+                status,
+            },
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?,
+    };
+
+    procedure_result(scheduled_send, fields)
+}
+
+async fn process_resume_scheduled_send(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::BatchIdArgs>(&arguments)?;
+
+    match sendgrid_api::invoke_delete_scheduled_send(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &args.batch_id,
+    )
+    .await
+    {
+        Ok(()) => {}
+        // A batch that was never paused or cancelled is already going ahead
+        Err(RequestError::UnexpectedStatusCode { status_code })
+            if status_code == reqwest::StatusCode::NOT_FOUND => {}
+        Err(err) => return Err(connector::ErrorResponse::from(err.to_string())),
+    }
+
+    procedure_result(
+        sendgrid_api::MailBatch {
+            batch_id: args.batch_id,
+        },
+        fields,
+    )
+}
+
+/// Checks every recipient of the mail against the account's suppression lists. Under the drop
+/// policy, suppressed recipients are removed and personalizations left without 'to' recipients are
+/// skipped, with their remaining 'cc' and 'bcc' recipients reported as changes; under the reject
+/// policy, the send fails if any recipient is suppressed.
+async fn apply_suppression_policy(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    policy: schema::SuppressionPolicy,
+    request: &mut sendgrid_api::SendMailRequest,
+) -> connector::Result<SuppressionPolicyOutcome> {
+    let emails = request
+        .personalizations
+        .iter()
+        .flat_map(personalization_recipients)
+        .map(|address| address.email.to_lowercase())
+        .collect::<BTreeSet<_>>();
+    let suppressions: BTreeMap<String, Vec<SuppressionList>> = stream::iter(emails)
+        .map(|email| async move {
+            let lists = match suppression_cache.and_then(|cache| cache.lookup(&email)) {
+                Some(lists) => lists,
+                None => find_suppression_lists(http_client, configuration, &email).await?,
+            };
+            connector::Result::Ok((email, lists))
+        })
+        .buffered(SUPPRESSION_LOOKUP_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut outcome = SuppressionPolicyOutcome::default();
+    let personalizations = std::mem::take(&mut request.personalizations);
+    for (index, mut personalization) in personalizations.into_iter().enumerate() {
+        let suppressed_recipients = &mut outcome.suppressed_recipients;
+        let mut retain_unsuppressed = |field, addresses: &mut Vec<sendgrid_api::MailAddress>| {
+            addresses.retain(
+                |address| match suppressions.get(&address.email.to_lowercase()) {
+                    Some(lists) if !lists.is_empty() => {
+                        suppressed_recipients.push(schema::SuppressedRecipient {
+                            email: address.email.clone(),
+                            personalization: index,
+                            field,
+                            lists: lists.clone(),
+                        });
+                        false
+                    }
+                    _ => true,
+                },
+            )
+        };
+        retain_unsuppressed("to", &mut personalization.to);
+        if let Some(cc) = personalization.cc.as_mut() {
+            retain_unsuppressed("cc", cc);
+        }
+        if let Some(bcc) = personalization.bcc.as_mut() {
+            retain_unsuppressed("bcc", bcc);
+        }
+
+        // SendGrid requires at least one 'to' recipient in each personalization
+        if personalization.to.is_empty() {
+            outcome.skipped_personalizations.push(index);
+            for (field, addresses) in [("cc", personalization.cc), ("bcc", personalization.bcc)] {
+                outcome
+                    .recipient_changes
+                    .extend(addresses.into_iter().flatten().map(|address| {
+                        schema::RecipientChange {
+                            personalization: index,
+                            field,
+                            original: address.email,
+                            email: None,
+                            change: "skipped",
+                            kept_in: None,
+                        }
+                    }));
+            }
+        } else {
+            request.personalizations.push(personalization);
+        }
+    }
+
+    if policy == schema::SuppressionPolicy::Reject && !outcome.suppressed_recipients.is_empty() {
+        let emails = outcome
+            .suppressed_recipients
+            .iter()
+            .map(|recipient| recipient.email.as_str())
+            .collect::<Vec<_>>();
+        return Err(MutationError::new_invalid_request(&format!(
+            "The mail was not sent because these recipients are suppressed: {}",
+            emails.join(", ")
+        ))
+        .into());
+    }
+
+    Ok(outcome)
+}
+
+/// The number of addresses whose suppression lists are looked up at once
+const SUPPRESSION_LOOKUP_CONCURRENCY: usize = 10;
+
+/// The changes the suppression policy made to a mail
+#[derive(Debug, Default)]
+struct SuppressionPolicyOutcome {
+    suppressed_recipients: Vec<schema::SuppressedRecipient>,
+    skipped_personalizations: Vec<usize>,
+    /// The 'cc' and 'bcc' recipients of the skipped personalizations
+    recipient_changes: Vec<schema::RecipientChange>,
 }
 
+fn personalization_recipients(
+    personalization: &sendgrid_api::MailPersonalization,
+) -> impl Iterator<Item = &sendgrid_api::MailAddress> {
+    personalization
+        .to
+        .iter()
+        .chain(personalization.cc.iter().flatten())
+        .chain(personalization.bcc.iter().flatten())
+}
+
+/// The suppression lists an address is on, looked up with the SendGrid API. Group suppressions are left to SendGrid, since they
+/// only apply to mail sent with the matching unsubscribe group.
+async fn find_suppression_lists(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    email: &str,
+) -> connector::Result<Vec<SuppressionList>> {
+    let base_url = configuration.sendgrid_api_base_url.as_str();
+    let api_key = &configuration.sendgrid_api_key;
+    let is_on_list = |list| async move {
+        sendgrid_api::invoke_get_suppression(http_client, base_url, api_key, list, email)
+            .await
+            .map(|suppressions| (!suppressions.is_empty()).then_some(list))
+    };
+
+    let (global_unsubscribe, bounced, blocked, spam_reported, invalid) = tokio::try_join!(
+        sendgrid_api::invoke_get_global_unsubscribe(http_client, base_url, api_key, email),
+        is_on_list(SuppressionList::Bounces),
+        is_on_list(SuppressionList::Blocks),
+        is_on_list(SuppressionList::SpamReports),
+        is_on_list(SuppressionList::InvalidEmails),
+    )
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    let globally_unsubscribed = global_unsubscribe
+        .recipient_email
+        .is_some()
+        .then_some(SuppressionList::GlobalUnsubscribes);
+
+    Ok([
+        bounced,
+        blocked,
+        spam_reported,
+        invalid,
+        globally_unsubscribed,
+    ]
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+/// Rejects the mail unless every 'From' address is a verified sender or belongs to an
+/// authenticated domain
+async fn check_from_addresses_verified(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    request: &sendgrid_api::SendMailRequest,
+) -> connector::Result<()> {
+    let verified_senders = query::fetch_verified_senders(http_client, configuration).await?;
+    let authenticated_domains = list_all_authenticated_domains(http_client, configuration).await?;
+
+    let from_addresses = std::iter::once(&request.from).chain(
+        request
+            .personalizations
+            .iter()
+            .filter_map(|personalization| personalization.from.as_ref()),
+    );
+    let mut unverified_emails = from_addresses
+        .map(|address| address.email.as_str())
+        .filter(|email| {
+            let is_verified_sender = verified_senders
+                .iter()
+                .any(|sender| sender.verified && sender.from_email.eq_ignore_ascii_case(email));
+            let is_authenticated_domain = email.rsplit_once('@').is_some_and(|(_, domain)| {
+                authenticated_domains.iter().any(|authenticated_domain| {
+                    authenticated_domain.valid
+                        && authenticated_domain.domain.eq_ignore_ascii_case(domain)
+                })
+            });
+            !is_verified_sender && !is_authenticated_domain
+        })
+        .collect::<Vec<_>>();
+    unverified_emails.sort_unstable();
+    unverified_emails.dedup();
+
+    if unverified_emails.is_empty() {
+        Ok(())
+    } else {
+        Err(MutationError::new_invalid_request(&format!(
+            "The 'From' address {} is not a verified sender and does not belong to an authenticated domain",
+            unverified_emails.join(", ")
+        ))
+        .into())
+    }
+}
+
+const AUTHENTICATED_DOMAINS_PAGE_SIZE: u32 = 100;
+
+async fn list_all_authenticated_domains(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+) -> connector::Result<Vec<sendgrid_api::AuthenticatedDomain>> {
+    let mut authenticated_domains = vec![];
+    loop {
+        let params = sendgrid_api::ListAuthenticatedDomainsParams {
+            limit: AUTHENTICATED_DOMAINS_PAGE_SIZE,
+            offset: authenticated_domains.len() as u32,
+        };
+        let page = sendgrid_api::invoke_list_authenticated_domains(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &params,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        let is_last_page = page.len() < AUTHENTICATED_DOMAINS_PAGE_SIZE as usize;
+        authenticated_domains.extend(page);
+        if is_last_page {
+            break;
+        }
+    }
+    Ok(authenticated_domains)
+}
+
+/// Converts the send_mail arguments into a SendGrid request, normalizing and de-duplicating the
+/// recipients of each personalization and returning the changes made to them
 fn parse_send_mail_args(
     in_args: &BTreeMap<ArgumentName, Value>,
-) -> Result<sendgrid_api::SendMailRequest, MutationError> {
-    let args_request = serde_json::Value::Object(serde_json::Map::from_iter(
-        in_args
-            .iter()
-            .map(|(name, value)| (name.as_str().to_owned(), value.clone())),
-    ));
-    let schema_request =
-        serde_json::from_value::<schema::SendMailRequest>(args_request).map_err(|err| {
-            MutationError::new_invalid_request(&format!("Unable to deserialize arguments: {err}"))
-        })?;
+    options: &schema::SendMailOptions,
+) -> Result<(sendgrid_api::SendMailRequest, Vec<schema::RecipientChange>), MutationError> {
+    let schema_request = parse_procedure_args::<schema::SendMailRequest>(in_args)?;
 
-    let request = sendgrid_api::SendMailRequest {
+    let mut request = sendgrid_api::SendMailRequest {
         personalizations: schema_request
             .personalizations
             .into_iter()
@@ -134,6 +713,768 @@ fn parse_send_mail_args(
         send_at: schema_request.send_at,
         batch_id: schema_request.batch_id,
         asm: schema_request.asm,
+        mail_settings: None,
+        custom_args: schema_request.custom_args.map(|custom_args| {
+            custom_args
+                .into_iter()
+                .map(|custom_arg| (custom_arg.name, custom_arg.value))
+                .collect()
+        }),
+    };
+
+    let recipient_changes = recipients::normalize_recipients(
+        &mut request.personalizations,
+        options.duplicate_recipients.unwrap_or_default(),
+    )?;
+    Ok((request, recipient_changes))
+}
+
+const DEFAULT_IMPORT_POLL_TIMEOUT_SECONDS: u32 = 60;
+const IMPORT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+async fn process_import_contacts(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::ImportContactsArgs>(&arguments)?;
+
+    let csv_content = match (args.csv_content, args.csv_path) {
+        (Some(csv_content), None) => csv_content.into_bytes(),
+        (None, Some(csv_path)) => {
+            let path = resolve_configuration_path(&configuration.configuration_dir, &csv_path)?;
+            tokio::fs::read(&path).await.map_err(|err| {
+                MutationError::new_invalid_request(&format!(
+                    "Unable to read CSV file '{csv_path}': {err}"
+                ))
+            })?
+        }
+        _ => Err(MutationError::new_invalid_request(
+            &"Exactly one of csv_content or csv_path must be provided",
+        ))?,
+    };
+
+    let import_request = sendgrid_api::ImportContactsRequest {
+        list_ids: args.list_ids,
+        file_type: String::from("csv"),
+        field_mappings: args.field_mappings,
+    };
+    let import_response = sendgrid_api::invoke_import_contacts(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &import_request,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    let upload_uri = match &configuration.sendgrid_upload_base_url {
+        Some(upload_base_url) => rewrite_upload_uri(&import_response.upload_uri, upload_base_url)?,
+        None => import_response.upload_uri.clone(),
+    };
+    sendgrid_api::invoke_upload_contacts_file(
+        http_client,
+        &upload_uri,
+        &import_response.upload_headers,
+        csv_content,
+    )
+    .await
+    .map_err(|err| {
+        connector::ErrorResponse::from(format!("Unable to upload contacts file: {err}"))
+    })?;
+
+    let poll_timeout = Duration::from_secs(u64::from(
+        args.poll_timeout_seconds
+            .unwrap_or(DEFAULT_IMPORT_POLL_TIMEOUT_SECONDS),
+    ));
+    let deadline = tokio::time::Instant::now() + poll_timeout;
+    let import_job = loop {
+        let import_job = sendgrid_api::invoke_get_contact_import(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &import_response.job_id,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        if import_job.status != "pending" || tokio::time::Instant::now() >= deadline {
+            break import_job;
+        }
+        tokio::time::sleep(IMPORT_POLL_INTERVAL).await;
+    };
+
+    procedure_result(import_job, fields)
+}
+
+/// Resolves a path relative to the configuration directory, refusing paths that could escape it
+fn resolve_configuration_path(
+    configuration_dir: &Path,
+    relative_path: &str,
+) -> Result<PathBuf, MutationError> {
+    let path = Path::new(relative_path);
+    let stays_within_configuration_dir = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if stays_within_configuration_dir {
+        Ok(configuration_dir.join(path))
+    } else {
+        Err(MutationError::new_invalid_request(&format!(
+            "The path '{relative_path}' must be relative to the configuration directory"
+        )))
+    }
+}
+
+/// Replaces the scheme, host and port of the upload URI returned by SendGrid with the configured
+/// upload base URL, keeping its path and query string
+fn rewrite_upload_uri(upload_uri: &str, upload_base_url: &str) -> connector::Result<String> {
+    let original = reqwest::Url::parse(upload_uri).map_err(|err| {
+        connector::ErrorResponse::from(format!("Invalid upload URI '{upload_uri}': {err}"))
+    })?;
+    let mut rewritten = reqwest::Url::parse(upload_base_url).map_err(|err| {
+        connector::ErrorResponse::from(format!(
+            "Invalid SENDGRID_UPLOAD_BASE_URL '{upload_base_url}': {err}"
+        ))
+    })?;
+    let base_path = rewritten.path().trim_end_matches('/').to_owned();
+    rewritten.set_path(&format!("{base_path}{}", original.path()));
+    rewritten.set_query(original.query());
+    Ok(rewritten.to_string())
+}
+
+async fn process_export_contacts(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::ExportContactsArgs>(&arguments)?;
+
+    let export_request = sendgrid_api::ExportContactsRequest {
+        list_ids: args.list_ids,
+        segment_ids: args.segment_ids,
+        file_type: args.file_type,
+        max_file_size: args.max_file_size,
+    };
+    let export_response = sendgrid_api::invoke_export_contacts(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &export_request,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(export_response, fields)
+}
+
+async fn process_duplicate_design(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::DuplicateDesignArgs>(&arguments)?;
+
+    let duplicate_request = sendgrid_api::DuplicateDesignRequest {
+        name: args.name,
+        editor: args.editor,
+    };
+    let design = sendgrid_api::invoke_duplicate_design(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &args.id,
+        &duplicate_request,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(design, fields)
+}
+
+async fn process_create_verified_sender(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let request = parse_procedure_args::<sendgrid_api::CreateVerifiedSenderRequest>(&arguments)?;
+
+    let verified_sender = sendgrid_api::invoke_create_verified_sender(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &request,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(verified_sender, fields)
+}
+
+async fn process_resend_sender_verification(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::VerifiedSenderIdArgs>(&arguments)?;
+
+    sendgrid_api::invoke_resend_sender_verification(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.id,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(serde_json::json!({ "id": args.id }), fields)
+}
+
+async fn process_delete_verified_sender(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::VerifiedSenderIdArgs>(&arguments)?;
+
+    sendgrid_api::invoke_delete_verified_sender(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.id,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(serde_json::json!({ "id": args.id }), fields)
+}
+
+async fn process_delete_suppressions(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    list: SuppressionList,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::DeleteSuppressionsArgs>(&arguments)?;
+
+    let delete_request = match (args.emails, args.delete_all.unwrap_or(false)) {
+        (Some(emails), false) if !emails.is_empty() => sendgrid_api::DeleteSuppressionsRequest {
+            delete_all: None,
+            emails: Some(emails),
+        },
+        (None, true) => sendgrid_api::DeleteSuppressionsRequest {
+            delete_all: Some(true),
+            emails: None,
+        },
+        _ => Err(MutationError::new_invalid_request(
+            &"Either a non-empty list of emails or delete_all must be provided, but not both",
+        ))?,
     };
-    Ok(request)
+
+    sendgrid_api::invoke_delete_suppressions(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        list,
+        &delete_request,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    if let Some(cache) = suppression_cache {
+        match &delete_request.emails {
+            Some(emails) => emails.iter().for_each(|email| cache.remove(email, list)),
+            None => cache.clear(list),
+        }
+    }
+
+    procedure_result(
+        schema::DeleteSuppressionsResponse {
+            deleted_all: delete_request.delete_all.unwrap_or(false),
+            emails: delete_request.emails,
+        },
+        fields,
+    )
+}
+
+async fn process_add_global_unsubscribes(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::AddGlobalUnsubscribesArgs>(&arguments)?;
+
+    let response = sendgrid_api::invoke_add_global_unsubscribes(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &sendgrid_api::RecipientEmails {
+            recipient_emails: args.emails,
+        },
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    if let Some(cache) = suppression_cache {
+        for email in &response.recipient_emails {
+            cache.insert(email, SuppressionList::GlobalUnsubscribes);
+        }
+    }
+
+    procedure_result(
+        schema::GlobalUnsubscribesResponse {
+            emails: response.recipient_emails,
+        },
+        fields,
+    )
+}
+
+async fn process_delete_global_unsubscribe(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::EmailArgs>(&arguments)?;
+
+    sendgrid_api::invoke_delete_global_unsubscribe(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &args.email,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    if let Some(cache) = suppression_cache {
+        cache.remove(&args.email, SuppressionList::GlobalUnsubscribes);
+    }
+
+    procedure_result(
+        schema::GlobalUnsubscribesResponse {
+            emails: vec![args.email],
+        },
+        fields,
+    )
+}
+
+async fn process_refresh_suppression_cache(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::RefreshSuppressionCacheArgs>(&arguments)?;
+    let cache = suppression_cache.ok_or_else(|| {
+        MutationError::new_invalid_request(
+            &"The suppression cache is not enabled. Set SENDGRID_SUPPRESSION_CACHE_REFRESH_SECONDS to enable it.",
+        )
+    })?;
+
+    let summary = cache
+        .sync(http_client, configuration, args.full.unwrap_or(true))
+        .await?;
+
+    procedure_result(schema::SuppressionCacheSync::from(summary), fields)
+}
+
+async fn process_create_unsubscribe_group(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::CreateUnsubscribeGroupArgs>(&arguments)?;
+
+    let group = sendgrid_api::invoke_create_unsubscribe_group(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        &sendgrid_api::UnsubscribeGroupRequest {
+            name: Some(args.name),
+            description: args.description,
+            is_default: args.is_default,
+        },
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(group, fields)
+}
+
+async fn process_update_unsubscribe_group(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::UpdateUnsubscribeGroupArgs>(&arguments)?;
+
+    let group = sendgrid_api::invoke_update_unsubscribe_group(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.id,
+        &sendgrid_api::UnsubscribeGroupRequest {
+            name: args.name,
+            description: args.description,
+            is_default: args.is_default,
+        },
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(group, fields)
+}
+
+async fn process_delete_unsubscribe_group(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::UnsubscribeGroupIdArgs>(&arguments)?;
+
+    sendgrid_api::invoke_delete_unsubscribe_group(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.id,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(serde_json::json!({ "id": args.id }), fields)
+}
+
+async fn process_add_group_suppressions(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::GroupSuppressionsArgs>(&arguments)?;
+
+    let response = sendgrid_api::invoke_add_group_suppressions(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.group_id,
+        &sendgrid_api::RecipientEmails {
+            recipient_emails: args.emails,
+        },
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(
+        schema::GroupSuppressionsResponse {
+            group_id: args.group_id,
+            emails: response.recipient_emails,
+        },
+        fields,
+    )
+}
+
+async fn process_delete_group_suppression(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    arguments: BTreeMap<ArgumentName, Value>,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let args = parse_procedure_args::<schema::GroupSuppressionArgs>(&arguments)?;
+
+    sendgrid_api::invoke_delete_group_suppression(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        args.group_id,
+        &args.email,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    procedure_result(
+        schema::GroupSuppressionsResponse {
+            group_id: args.group_id,
+            emails: vec![args.email],
+        },
+        fields,
+    )
+}
+
+fn parse_procedure_args<T: DeserializeOwned>(
+    in_args: &BTreeMap<ArgumentName, Value>,
+) -> Result<T, MutationError> {
+    let args_request = serde_json::Value::Object(serde_json::Map::from_iter(
+        in_args
+            .iter()
+            .map(|(name, value)| (name.as_str().to_owned(), value.clone())),
+    ));
+    serde_json::from_value::<T>(args_request).map_err(|err| {
+        MutationError::new_invalid_request(&format!("Unable to deserialize arguments: {err}"))
+    })
+}
+
+fn procedure_result<T: Serialize>(
+    result: T,
+    fields: Option<NestedField>,
+) -> connector::Result<MutationOperationResults> {
+    let result_value = serde_json::to_value(result)
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    let projected_result_value = match fields {
+        Some(fields) => eval_nested_field(result_value, &fields)?.0,
+        None => result_value,
+    };
+
+    Ok(MutationOperationResults::Procedure {
+        result: projected_result_value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Path, RawQuery, State},
+        http::{HeaderMap, StatusCode},
+        routing::{get, post, put},
+        Json, Router,
+    };
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::sendgrid_api::SendGridApiKey;
+
+    /// What the stand-in for SendGrid saw of the contacts file upload
+    #[derive(Clone, Default)]
+    struct Upload {
+        query: Option<String>,
+        content_type: Option<String>,
+        authorization: Option<String>,
+        body: Option<String>,
+    }
+
+    /// Serves the contact import endpoints of the SendGrid API and the pre-signed upload URL they
+    /// hand out, on an unused local port, returning the stand-in's base URL
+    fn start_stand_in(upload: Arc<Mutex<Upload>>) -> String {
+        let router = Router::new()
+            .route(
+                "/v3/marketing/contacts/imports",
+                put(|| async {
+                    Json(json!({
+                        "job_id": "job-1",
+                        "upload_uri": "https://uploads.sendgrid.example/contacts/job-1?signature=abc",
+                        "upload_headers": [{ "header": "Content-Type", "value": "text/csv" }],
+                    }))
+                }),
+            )
+            .route(
+                "/v3/marketing/contacts/imports/:job_id",
+                get(|Path(job_id): Path<String>| async move {
+                    Json(json!({
+                        "id": job_id,
+                        "status": "completed",
+                        "job_type": "upsert",
+                        "results": { "requested_count": 2, "created_count": 2 },
+                    }))
+                }),
+            )
+            .route(
+                "/contacts/job-1",
+                put(
+                    |State(upload): State<Arc<Mutex<Upload>>>,
+                     RawQuery(query): RawQuery,
+                     headers: HeaderMap,
+                     body: String| async move {
+                        let header = |name: &str| {
+                            headers
+                                .get(name)
+                                .and_then(|value| value.to_str().ok())
+                                .map(str::to_owned)
+                        };
+                        *upload.lock().unwrap() = Upload {
+                            query,
+                            content_type: header("content-type"),
+                            authorization: header("authorization"),
+                            body: Some(body),
+                        };
+                    },
+                ),
+            )
+            .with_state(upload);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+        base_url
+    }
+
+    /// Serves the mail send endpoint of the SendGrid API on an unused local port, recording the
+    /// mail it is sent, and returns the stand-in's base URL
+    fn start_mail_send_stand_in(sent: Arc<Mutex<Option<Value>>>) -> String {
+        let router = Router::new()
+            .route(
+                "/v3/mail/send",
+                post(
+                    |State(sent): State<Arc<Mutex<Option<Value>>>>, Json(mail): Json<Value>| async move {
+                        *sent.lock().unwrap() = Some(mail);
+                        (StatusCode::ACCEPTED, [("X-Message-Id", "msg-1")])
+                    },
+                ),
+            )
+            .with_state(sent);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+        base_url
+    }
+
+    fn test_configuration(base_url: &str) -> configuration::SendGridConfiguration {
+        let Ok(api_key) = SendGridApiKey::new("test-api-key") else {
+            panic!("The API key is not blank");
+        };
+        configuration::SendGridConfiguration {
+            sendgrid_api_key: api_key,
+            sendgrid_validation_api_key: None,
+            sendgrid_api_base_url: base_url.to_owned(),
+            sendgrid_upload_base_url: Some(base_url.to_owned()),
+            suppression_cache_refresh_seconds: None,
+            suppression_cache_max_age_seconds: None,
+            recipient_safety: configuration::RecipientSafetyConfiguration::default(),
+            preference_links: None,
+            event_webhook: None,
+            event_webhook_verification: None,
+            configuration_dir: PathBuf::from("."),
+        }
+    }
+
+    #[tokio::test]
+    async fn import_contacts_uploads_to_the_configured_upload_base_url() {
+        let upload = Arc::new(Mutex::new(Upload::default()));
+        let base_url = start_stand_in(upload.clone());
+        let csv_content = "email,first_name\nada@example.com,Ada\ngrace@example.com,Grace\n";
+        let arguments = BTreeMap::from([
+            (ArgumentName::from("csv_content"), json!(csv_content)),
+            (
+                ArgumentName::from("field_mappings"),
+                json!(["_rf0_T", "_rf1_T"]),
+            ),
+        ]);
+
+        let result = process_import_contacts(
+            &reqwest::Client::new(),
+            &test_configuration(&base_url),
+            arguments,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let MutationOperationResults::Procedure { result } = result;
+        assert_eq!(result["id"], json!("job-1"));
+        assert_eq!(result["status"], json!("completed"));
+        assert_eq!(result["results"]["created_count"], json!(2));
+
+        let upload = upload.lock().unwrap().clone();
+        assert_eq!(upload.body.as_deref(), Some(csv_content));
+        assert_eq!(upload.query.as_deref(), Some("signature=abc"));
+        assert_eq!(upload.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(upload.authorization, None);
+    }
+
+    #[tokio::test]
+    async fn redirected_recipients_get_no_list_unsubscribe_headers() {
+        let sent = Arc::new(Mutex::new(None));
+        let base_url = start_mail_send_stand_in(sent.clone());
+        let configuration = configuration::SendGridConfiguration {
+            recipient_safety: configuration::RecipientSafetyConfiguration {
+                allowlist: vec![String::from("corp.example")],
+                redirect_to: Some(String::from("inbox@corp.example")),
+                force_sandbox_mode: false,
+            },
+            preference_links: Some(configuration::PreferenceLinkConfiguration {
+                secret: String::from("preference-link-secret"),
+                base_url: String::from("https://corp.example/preferences"),
+            }),
+            ..test_configuration(&base_url)
+        };
+        let arguments = BTreeMap::from([
+            (
+                ArgumentName::from("personalizations"),
+                json!([
+                    { "to": [{ "email": "ada@corp.example" }] },
+                    { "to": [{ "email": "grace@elsewhere.example" }] },
+                ]),
+            ),
+            (
+                ArgumentName::from("from"),
+                json!({ "email": "news@corp.example" }),
+            ),
+            (ArgumentName::from("reply_to_list"), json!([])),
+            (ArgumentName::from("subject"), json!("News")),
+            (
+                ArgumentName::from("content"),
+                json!([{ "type": "text/plain", "value": "Hello" }]),
+            ),
+            (ArgumentName::from("list_unsubscribe"), json!(true)),
+        ]);
+
+        process_send_mail(
+            &reqwest::Client::new(),
+            &configuration,
+            None,
+            arguments,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mail = sent.lock().unwrap().clone().unwrap();
+        let allowed = &mail["personalizations"][0];
+        assert_eq!(allowed["to"][0]["email"], json!("ada@corp.example"));
+        assert!(allowed["headers"]["List-Unsubscribe"]
+            .as_str()
+            .is_some_and(|header| header.starts_with("<https://corp.example/preferences?token=")));
+
+        let redirected = &mail["personalizations"][1];
+        assert_eq!(redirected["to"][0]["email"], json!("inbox@corp.example"));
+        assert_eq!(
+            redirected["headers"]["X-Original-Recipients"],
+            json!("to: grace@elsewhere.example")
+        );
+        assert_eq!(redirected["headers"].get("List-Unsubscribe"), None);
+        assert_eq!(redirected["headers"].get("List-Unsubscribe-Post"), None);
+    }
+
+    #[test]
+    fn rewrite_upload_uri_keeps_the_path_and_query() {
+        assert_eq!(
+            rewrite_upload_uri(
+                "https://uploads.sendgrid.example/contacts/job-1?signature=abc",
+                "http://localhost:9000/stand-in/",
+            )
+            .unwrap(),
+            "http://localhost:9000/stand-in/contacts/job-1?signature=abc"
+        );
+    }
 }
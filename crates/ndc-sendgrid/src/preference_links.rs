@@ -0,0 +1,210 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::configuration::PreferenceLinkConfiguration;
+use super::sendgrid_api::SendMailRequest;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// What a preference token grants access to. Serialized as JSON to form the token's payload.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PreferenceClaims {
+    pub email: String,
+    pub group_ids: Vec<u32>,
+    /// The unix timestamp after which the token is no longer valid, if it expires
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenError {
+    Malformed,
+    BadSignature,
+    Expired,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::Malformed => write!(f, "The token is malformed"),
+            TokenError::BadSignature => write!(f, "The token's signature does not match"),
+            TokenError::Expired => write!(f, "The token has expired"),
+        }
+    }
+}
+
+/// Creates a token of the form `<payload>.<signature>`, where the payload is the base64url
+/// encoded claims and the signature is the base64url encoded HMAC-SHA256 of the payload
+pub fn sign_token(secret: &str, claims: &PreferenceClaims) -> String {
+    let payload = URL_SAFE_NO_PAD
+        .encode(serde_json::to_vec(claims).expect("preference claims always serialize to JSON"));
+    let signature = URL_SAFE_NO_PAD.encode(hmac(secret, &payload).finalize().into_bytes());
+    format!("{payload}.{signature}")
+}
+
+/// Checks a token's signature and expiry, returning its claims if it is valid
+pub fn verify_token(secret: &str, token: &str) -> Result<PreferenceClaims, TokenError> {
+    let (payload, signature) = token.trim().split_once('.').ok_or(TokenError::Malformed)?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| TokenError::Malformed)?;
+    hmac(secret, payload)
+        .verify_slice(&signature)
+        .map_err(|_| TokenError::BadSignature)?;
+
+    let claims = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<PreferenceClaims>(&json).ok())
+        .ok_or(TokenError::Malformed)?;
+    match claims.expires_at {
+        Some(expires_at) if expires_at <= unix_now() => Err(TokenError::Expired),
+        _ => Ok(claims),
+    }
+}
+
+/// The preference center URL for a token
+pub fn preference_url(configuration: &PreferenceLinkConfiguration, token: &str) -> String {
+    let separator = if configuration.base_url.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    format!("{}{separator}token={token}", configuration.base_url)
+}
+
+/// Adds RFC 8058 one-click unsubscribe headers to each personalization, with a link signed for
+/// the personalization's first 'to' recipient and the mail's unsubscribe group, if any. A
+/// personalization whose first 'to' recipient is the recipient safety catch-all address gets no
+/// headers, as the link would unsubscribe the catch-all address rather than the intended recipient.
+pub fn add_list_unsubscribe_headers(
+    configuration: &PreferenceLinkConfiguration,
+    request: &mut SendMailRequest,
+    redirect_to: Option<&str>,
+) {
+    let group_ids = request
+        .asm
+        .as_ref()
+        .map(|asm| vec![asm.group_id])
+        .unwrap_or_default();
+
+    for personalization in &mut request.personalizations {
+        let Some(recipient) = personalization.to.first() else {
+            continue;
+        };
+        if redirect_to.is_some_and(|redirect_to| redirect_to.eq_ignore_ascii_case(&recipient.email))
+        {
+            continue;
+        }
+        let token = sign_token(
+            &configuration.secret,
+            &PreferenceClaims {
+                email: recipient.email.clone(),
+                group_ids: group_ids.clone(),
+                expires_at: None,
+            },
+        );
+        let headers = personalization.headers.get_or_insert_with(Default::default);
+        headers.insert(
+            String::from("List-Unsubscribe"),
+            format!("<{}>", preference_url(configuration, &token)),
+        );
+        headers.insert(
+            String::from("List-Unsubscribe-Post"),
+            String::from("List-Unsubscribe=One-Click"),
+        );
+    }
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+fn hmac(secret: &str, payload: &str) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "preference-link-secret";
+
+    fn claims(expires_at: Option<i64>) -> PreferenceClaims {
+        PreferenceClaims {
+            email: String::from("ada@example.com"),
+            group_ids: vec![42],
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn signed_tokens_verify_to_their_claims() {
+        let claims = claims(Some(unix_now() + 3600));
+        let token = sign_token(SECRET, &claims);
+        assert_eq!(verify_token(SECRET, &token), Ok(claims));
+    }
+
+    #[test]
+    fn tokens_signed_with_another_secret_are_rejected() {
+        let token = sign_token("another-secret", &claims(None));
+        assert_eq!(verify_token(SECRET, &token), Err(TokenError::BadSignature));
+    }
+
+    #[test]
+    fn tokens_with_changed_claims_are_rejected() {
+        let token = sign_token(SECRET, &claims(None));
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged_payload = URL_SAFE_NO_PAD.encode(
+            serde_json::to_vec(&PreferenceClaims {
+                email: String::from("grace@example.com"),
+                ..claims(None)
+            })
+            .unwrap(),
+        );
+        assert_eq!(
+            verify_token(SECRET, &format!("{forged_payload}.{signature}")),
+            Err(TokenError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn expired_and_malformed_tokens_are_rejected() {
+        let token = sign_token(SECRET, &claims(Some(unix_now() - 1)));
+        assert_eq!(verify_token(SECRET, &token), Err(TokenError::Expired));
+        assert_eq!(
+            verify_token(SECRET, "no-signature"),
+            Err(TokenError::Malformed)
+        );
+        assert_eq!(
+            verify_token(SECRET, "payload.!!"),
+            Err(TokenError::Malformed)
+        );
+    }
+
+    #[test]
+    fn preference_url_appends_the_token_to_the_query_string() {
+        let configuration = |base_url: &str| PreferenceLinkConfiguration {
+            secret: String::from(SECRET),
+            base_url: String::from(base_url),
+        };
+        assert_eq!(
+            preference_url(&configuration("https://example.com/preferences"), "abc"),
+            "https://example.com/preferences?token=abc"
+        );
+        assert_eq!(
+            preference_url(
+                &configuration("https://example.com/preferences?lang=en"),
+                "abc"
+            ),
+            "https://example.com/preferences?lang=en&token=abc"
+        );
+    }
+}
@@ -1,15 +1,52 @@
+use futures::stream::{self, StreamExt, TryStreamExt};
 use indexmap::IndexMap;
 use ndc_sdk::{
     connector::{self, QueryError},
-    models::{Argument, ArgumentName, QueryRequest, QueryResponse, RowSet},
+    models::{
+        Argument, ArgumentName, Expression, OrderByTarget, OrderDirection, Query, QueryRequest,
+        QueryResponse, RowSet,
+    },
 };
+use serde::{de::DeserializeOwned, Serialize};
 use std::collections::BTreeMap;
 
+use crate::aggregates::eval_aggregates;
+use crate::connector::SendGridConnectorState;
 use crate::fields::eval_row;
+use crate::filter::{self, compare_values, eval_predicate};
 
+use super::activity_query;
+use super::bounce_classifier;
 use super::configuration;
-use super::schema::LIST_TEMPLATES_FUNCTION_NAME;
-use super::sendgrid_api::{invoke_list_function_templates, ListTransactionalTemplatesParams};
+use super::delivery_status;
+use super::event_store::{self, EventStore};
+use super::preference_links;
+use super::schema::{
+    self, AUTOMATION_STATS_FUNCTION_NAME, BLOCKS_COLLECTION_NAME, BOUNCES_COLLECTION_NAME,
+    BROWSER_STATS_COLLECTION_NAME, CATEGORY_STATS_FUNCTION_NAME, CATEGORY_STATS_SUMS_FUNCTION_NAME,
+    CLASSIFY_BOUNCE_FUNCTION_NAME, CLIENT_STATS_COLLECTION_NAME, DESIGNS_COLLECTION_NAME,
+    DEVICE_STATS_COLLECTION_NAME, EMAIL_EVENTS_COLLECTION_NAME, EMAIL_STATS_COLLECTION_NAME,
+    EXPORT_AUTOMATION_STATS_FUNCTION_NAME, EXPORT_SINGLESEND_STATS_FUNCTION_NAME,
+    GEO_STATS_COLLECTION_NAME, GET_CONTACT_EXPORT_FUNCTION_NAME, GET_DELIVERY_STATUS_FUNCTION_NAME,
+    GET_DESIGN_FUNCTION_NAME, GET_MESSAGE_FUNCTION_NAME, GET_SUPPRESSION_STATUS_FUNCTION_NAME,
+    GLOBAL_STATS_FUNCTION_NAME, GLOBAL_UNSUBSCRIBES_COLLECTION_NAME,
+    INVALID_EMAILS_COLLECTION_NAME, LIST_TEMPLATES_FUNCTION_NAME,
+    MAILBOX_PROVIDER_STATS_COLLECTION_NAME, MAKE_PREFERENCE_LINK_FUNCTION_NAME,
+    MESSAGES_COLLECTION_NAME, SCHEDULED_SENDS_COLLECTION_NAME,
+    SEARCH_GROUP_SUPPRESSIONS_FUNCTION_NAME, SINGLESEND_STATS_FUNCTION_NAME,
+    SPAM_REPORTS_COLLECTION_NAME, SUBUSER_STATS_FUNCTION_NAME, SUBUSER_STATS_SUMS_FUNCTION_NAME,
+    UNSUBSCRIBE_GROUPS_COLLECTION_NAME, VALIDATE_BATCH_ID_FUNCTION_NAME,
+    VALIDATE_EMAILS_FUNCTION_NAME, VALIDATE_EMAIL_FUNCTION_NAME, VERIFIED_SENDERS_COLLECTION_NAME,
+    VERIFY_EVENT_WEBHOOK_FUNCTION_NAME, VERIFY_PREFERENCE_TOKEN_FUNCTION_NAME,
+};
+use super::sendgrid_api::{
+    self, invoke_list_function_templates, EmailMetrics, EmailStatsBreakdown, EmailStatsParams,
+    EmailStatsSumsParams, ListDesignsParams, ListSuppressionsParams,
+    ListTransactionalTemplatesParams, ListVerifiedSendersParams, MarketingStatsKind,
+    MarketingStatsParams, RequestError, SuppressionList,
+};
+use super::suppression_cache::SuppressionCache;
+use super::webhook_signature;
 
 fn parse_list_templates_params(
     in_args: BTreeMap<ArgumentName, Argument>,
@@ -70,38 +107,498 @@ fn parse_list_templates_params(
 }
 
 pub async fn execute(
-    http_client: &reqwest::Client,
+    state: &SendGridConnectorState,
     configuration: &configuration::SendGridConfiguration,
     query_request: QueryRequest,
 ) -> connector::Result<QueryResponse> {
+    let http_client = &state.http_client;
+
     match query_request.collection.as_str() {
         LIST_TEMPLATES_FUNCTION_NAME => {
             let args = query_request.arguments;
             let params = parse_list_templates_params(args)?;
             let response = invoke_list_function_templates(
                 http_client,
+                &configuration.sendgrid_api_base_url,
                 &configuration.sendgrid_api_key,
                 &params,
             )
-            .await;
-
-            match response {
-                Ok(list_response) => {
-                    let result = serde_json::to_value(list_response.result)
-                        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
-                    let result_row = IndexMap::from([(String::from("__value"), result)]);
-                    let projected_row = query_request
-                        .query
-                        .fields
-                        .map(|fields| eval_row(&fields, &result_row))
-                        .transpose()?;
-                    Ok(QueryResponse(vec![RowSet {
-                        rows: projected_row.map(|row| vec![row]),
-                        aggregates: None,
-                    }]))
-                }
-                Err(err) => Err(connector::ErrorResponse::from(err.to_string())),
-            }
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            function_result(response.result, &query_request.query)
+        }
+        GET_CONTACT_EXPORT_FUNCTION_NAME => {
+            let args: schema::GetContactExportArgs = parse_function_args(query_request.arguments)?;
+            let response = sendgrid_api::invoke_get_contact_export(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &args.id,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            function_result(response, &query_request.query)
+        }
+        GET_DESIGN_FUNCTION_NAME => {
+            let args: schema::GetDesignArgs = parse_function_args(query_request.arguments)?;
+            let response = sendgrid_api::invoke_get_design(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &args.id,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            function_result(response, &query_request.query)
+        }
+        SINGLESEND_STATS_FUNCTION_NAME => {
+            let args: schema::MarketingStatsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_marketing_stats(
+                http_client,
+                configuration,
+                MarketingStatsKind::Singlesends,
+                args,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        AUTOMATION_STATS_FUNCTION_NAME => {
+            let args: schema::MarketingStatsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_marketing_stats(
+                http_client,
+                configuration,
+                MarketingStatsKind::Automations,
+                args,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        EXPORT_SINGLESEND_STATS_FUNCTION_NAME => {
+            let args: schema::ExportMarketingStatsArgs =
+                parse_function_args(query_request.arguments)?;
+            let csv = sendgrid_api::invoke_export_marketing_stats(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                MarketingStatsKind::Singlesends,
+                &args.ids,
+                args.timezone.as_deref(),
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            function_result(csv, &query_request.query)
+        }
+        EXPORT_AUTOMATION_STATS_FUNCTION_NAME => {
+            let args: schema::ExportMarketingStatsArgs =
+                parse_function_args(query_request.arguments)?;
+            let csv = sendgrid_api::invoke_export_marketing_stats(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                MarketingStatsKind::Automations,
+                &args.ids,
+                args.timezone.as_deref(),
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            function_result(csv, &query_request.query)
+        }
+        DESIGNS_COLLECTION_NAME => {
+            let designs = fetch_designs(http_client, configuration, &query_request.query).await?;
+            collection_result(designs, &query_request.query)
+        }
+        VERIFIED_SENDERS_COLLECTION_NAME => {
+            let verified_senders = fetch_verified_senders(http_client, configuration).await?;
+            collection_result(verified_senders, &query_request.query)
+        }
+        BOUNCES_COLLECTION_NAME => {
+            query_suppressions(
+                http_client,
+                configuration,
+                SuppressionList::Bounces,
+                &query_request.query,
+            )
+            .await
+        }
+        BLOCKS_COLLECTION_NAME => {
+            query_suppressions(
+                http_client,
+                configuration,
+                SuppressionList::Blocks,
+                &query_request.query,
+            )
+            .await
+        }
+        SPAM_REPORTS_COLLECTION_NAME => {
+            query_suppressions(
+                http_client,
+                configuration,
+                SuppressionList::SpamReports,
+                &query_request.query,
+            )
+            .await
+        }
+        INVALID_EMAILS_COLLECTION_NAME => {
+            query_suppressions(
+                http_client,
+                configuration,
+                SuppressionList::InvalidEmails,
+                &query_request.query,
+            )
+            .await
+        }
+        GLOBAL_UNSUBSCRIBES_COLLECTION_NAME => {
+            query_suppressions(
+                http_client,
+                configuration,
+                SuppressionList::GlobalUnsubscribes,
+                &query_request.query,
+            )
+            .await
+        }
+        UNSUBSCRIBE_GROUPS_COLLECTION_NAME => {
+            let groups = sendgrid_api::invoke_list_unsubscribe_groups(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            collection_result(groups, &query_request.query)
+        }
+        SEARCH_GROUP_SUPPRESSIONS_FUNCTION_NAME => {
+            let args: schema::GroupSuppressionsArgs = parse_function_args(query_request.arguments)?;
+            let emails = sendgrid_api::invoke_search_group_suppressions(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                args.group_id,
+                &sendgrid_api::RecipientEmails {
+                    recipient_emails: args.emails,
+                },
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            function_result(
+                schema::GroupSuppressionsResponse {
+                    group_id: args.group_id,
+                    emails,
+                },
+                &query_request.query,
+            )
+        }
+        GET_SUPPRESSION_STATUS_FUNCTION_NAME => {
+            let args: schema::EmailArgs = parse_function_args(query_request.arguments)?;
+            let status = get_suppression_status(
+                http_client,
+                configuration,
+                state.suppression_cache.as_ref(),
+                args.email,
+            )
+            .await?;
+            function_result(status, &query_request.query)
+        }
+        VALIDATE_EMAIL_FUNCTION_NAME => {
+            let args: schema::ValidateEmailArgs = parse_function_args(query_request.arguments)?;
+            let validation =
+                validate_email(http_client, configuration, args.email, args.source).await?;
+            function_result(validation, &query_request.query)
+        }
+        VALIDATE_EMAILS_FUNCTION_NAME => {
+            let args: schema::ValidateEmailsArgs = parse_function_args(query_request.arguments)?;
+            let validations = stream::iter(args.emails)
+                .map(|email| validate_email(http_client, configuration, email, args.source.clone()))
+                .buffered(EMAIL_VALIDATION_CONCURRENCY)
+                .try_collect::<Vec<_>>()
+                .await?;
+            function_result(validations, &query_request.query)
+        }
+        GLOBAL_STATS_FUNCTION_NAME => {
+            let args: schema::EmailStatsArgs = parse_function_args(query_request.arguments)?;
+            let days = sendgrid_api::invoke_get_global_stats(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &email_stats_params(args),
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            let rows = days
+                .into_iter()
+                .map(schema::EmailStatsRow::from)
+                .collect::<Vec<_>>();
+            function_result(rows, &query_request.query)
+        }
+        MAILBOX_PROVIDER_STATS_COLLECTION_NAME => {
+            let args: schema::EmailStatsArgs = parse_function_args(query_request.arguments)?;
+            query_dimension_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::MailboxProviders,
+                None,
+                args,
+                &query_request.query,
+            )
+            .await
+        }
+        BROWSER_STATS_COLLECTION_NAME => {
+            let args: schema::EmailStatsArgs = parse_function_args(query_request.arguments)?;
+            query_dimension_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Browsers,
+                None,
+                args,
+                &query_request.query,
+            )
+            .await
+        }
+        DEVICE_STATS_COLLECTION_NAME => {
+            let args: schema::EmailStatsArgs = parse_function_args(query_request.arguments)?;
+            query_dimension_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Devices,
+                None,
+                args,
+                &query_request.query,
+            )
+            .await
+        }
+        CLIENT_STATS_COLLECTION_NAME => {
+            let args: schema::EmailStatsArgs = parse_function_args(query_request.arguments)?;
+            query_dimension_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Clients,
+                None,
+                args,
+                &query_request.query,
+            )
+            .await
+        }
+        GEO_STATS_COLLECTION_NAME => {
+            let args: schema::GeoStatsArgs = parse_function_args(query_request.arguments)?;
+            query_dimension_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Geo,
+                args.country,
+                args.stats,
+                &query_request.query,
+            )
+            .await
+        }
+        MESSAGES_COLLECTION_NAME => {
+            query_messages(http_client, configuration, &query_request.query).await
+        }
+        GET_MESSAGE_FUNCTION_NAME => {
+            let args: schema::GetMessageArgs = parse_function_args(query_request.arguments)?;
+            let message = sendgrid_api::invoke_get_message(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &args.msg_id,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            function_result(message, &query_request.query)
+        }
+        GET_DELIVERY_STATUS_FUNCTION_NAME => {
+            let args: schema::GetDeliveryStatusArgs = parse_function_args(query_request.arguments)?;
+            let response = delivery_status::get_delivery_status(
+                http_client,
+                configuration,
+                state.event_store.as_ref(),
+                args,
+            )
+            .await?;
+
+            function_result(response, &query_request.query)
+        }
+        SCHEDULED_SENDS_COLLECTION_NAME => {
+            let scheduled_sends = sendgrid_api::invoke_list_scheduled_sends(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+            collection_result(scheduled_sends, &query_request.query)
+        }
+        EMAIL_EVENTS_COLLECTION_NAME => {
+            query_email_events(state.event_store.as_ref(), &query_request.query).await
+        }
+        EMAIL_STATS_COLLECTION_NAME => {
+            let args: schema::EmailStatsCollectionArgs =
+                parse_function_args(query_request.arguments)?;
+            query_email_stats(http_client, configuration, args, &query_request.query).await
+        }
+        CATEGORY_STATS_FUNCTION_NAME => {
+            let args: schema::CategoryStatsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_breakdown_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Categories,
+                &args.categories,
+                args.stats,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        CATEGORY_STATS_SUMS_FUNCTION_NAME => {
+            let args: schema::EmailStatsSumsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_breakdown_stats_sums(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Categories,
+                args,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        SUBUSER_STATS_FUNCTION_NAME => {
+            let args: schema::SubuserStatsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_breakdown_stats(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Subusers,
+                &args.subusers,
+                args.stats,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        SUBUSER_STATS_SUMS_FUNCTION_NAME => {
+            let args: schema::EmailStatsSumsArgs = parse_function_args(query_request.arguments)?;
+            let rows = get_breakdown_stats_sums(
+                http_client,
+                configuration,
+                EmailStatsBreakdown::Subusers,
+                args,
+            )
+            .await?;
+            function_result(rows, &query_request.query)
+        }
+        CLASSIFY_BOUNCE_FUNCTION_NAME => {
+            let args: schema::ClassifyBounceArgs = parse_function_args(query_request.arguments)?;
+            let classification = bounce_classifier::classify(&args.reason, args.status.as_deref());
+            function_result(classification, &query_request.query)
+        }
+        MAKE_PREFERENCE_LINK_FUNCTION_NAME => {
+            let args: schema::MakePreferenceLinkArgs =
+                parse_function_args(query_request.arguments)?;
+            let preference_links = preference_link_configuration(configuration)?;
+
+            let claims = preference_links::PreferenceClaims {
+                email: args.email,
+                group_ids: args.group_ids.unwrap_or_default(),
+                expires_at: args
+                    .expires_in
+                    .map(|expires_in| preference_links::unix_now() + i64::from(expires_in)),
+            };
+            let token = preference_links::sign_token(&preference_links.secret, &claims);
+
+            function_result(
+                schema::PreferenceLink {
+                    url: preference_links::preference_url(preference_links, &token),
+                    token,
+                    expires_at: claims.expires_at,
+                },
+                &query_request.query,
+            )
+        }
+        VERIFY_PREFERENCE_TOKEN_FUNCTION_NAME => {
+            let args: schema::VerifyPreferenceTokenArgs =
+                parse_function_args(query_request.arguments)?;
+            let preference_links = preference_link_configuration(configuration)?;
+
+            let verification =
+                match preference_links::verify_token(&preference_links.secret, &args.token) {
+                    Ok(claims) => schema::PreferenceTokenVerification {
+                        valid: true,
+                        error: None,
+                        email: Some(claims.email),
+                        group_ids: claims.group_ids,
+                        expires_at: claims.expires_at,
+                    },
+                    Err(err) => schema::PreferenceTokenVerification {
+                        valid: false,
+                        error: Some(err.to_string()),
+                        email: None,
+                        group_ids: vec![],
+                        expires_at: None,
+                    },
+                };
+            function_result(verification, &query_request.query)
+        }
+        VERIFY_EVENT_WEBHOOK_FUNCTION_NAME => {
+            let args: schema::VerifyEventWebhookArgs =
+                parse_function_args(query_request.arguments)?;
+            let verification = configuration
+                .event_webhook_verification
+                .as_ref()
+                .ok_or_else(|| {
+                    QueryError::new_invalid_request(
+                        &"Signed Event Webhook verification is not configured. Set SENDGRID_EVENT_WEBHOOK_PUBLIC_KEY to enable it.",
+                    )
+                })?;
+
+            let result = match webhook_signature::verify_signature(
+                &verification.public_key,
+                args.payload.as_bytes(),
+                &args.signature,
+                &args.timestamp,
+                verification.timestamp_tolerance_seconds,
+            ) {
+                Ok(()) => schema::EventWebhookVerification {
+                    valid: true,
+                    error: None,
+                },
+                Err(err) => schema::EventWebhookVerification {
+                    valid: false,
+                    error: Some(err.to_string()),
+                },
+            };
+            function_result(result, &query_request.query)
+        }
+        VALIDATE_BATCH_ID_FUNCTION_NAME => {
+            let args: schema::BatchIdArgs = parse_function_args(query_request.arguments)?;
+            let validation = match sendgrid_api::invoke_validate_batch_id(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &args.batch_id,
+            )
+            .await
+            {
+                Ok(batch) => schema::BatchIdValidation {
+                    batch_id: batch.batch_id,
+                    valid: true,
+                    error: None,
+                },
+                // SendGrid answers an invalid batch ID with a bad request or not found
+                Err(
+                    err @ (RequestError::ApiError { .. }
+                    | RequestError::UnexpectedStatusCode {
+                        status_code: reqwest::StatusCode::NOT_FOUND,
+                    }),
+                ) => schema::BatchIdValidation {
+                    batch_id: args.batch_id,
+                    valid: false,
+                    error: Some(err.to_string()),
+                },
+                Err(err) => return Err(connector::ErrorResponse::from(err.to_string())),
+            };
+            function_result(validation, &query_request.query)
         }
         unknown_collection => Err(QueryError::new_invalid_request(&format!(
             "Unknown collection: {unknown_collection}"
@@ -109,3 +606,1030 @@ pub async fn execute(
         .into()),
     }
 }
+
+/// Deserializes the literal arguments of a function into its arguments type
+fn parse_function_args<T: DeserializeOwned>(
+    in_args: BTreeMap<ArgumentName, Argument>,
+) -> connector::Result<T> {
+    let args = in_args
+        .into_iter()
+        .map(|(name, argument)| match argument {
+            Argument::Literal { value } => Ok((name.as_str().to_owned(), value)),
+            Argument::Variable { .. } => Err(QueryError::new_unsupported_operation(
+                &"Variables not currently supported",
+            )),
+        })
+        .collect::<Result<serde_json::Map<String, serde_json::Value>, QueryError>>()?;
+
+    serde_json::from_value(serde_json::Value::Object(args)).map_err(|err| {
+        QueryError::new_invalid_request(&format!("Unable to deserialize arguments: {err}")).into()
+    })
+}
+
+/// Wraps the result of a function in the single `__value` row and projects the requested fields
+fn function_result<T: Serialize>(result: T, query: &Query) -> connector::Result<QueryResponse> {
+    let result = serde_json::to_value(result)
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+    let result_row = IndexMap::from([(String::from("__value"), result)]);
+    let projected_row = query
+        .fields
+        .as_ref()
+        .map(|fields| eval_row(fields, &result_row))
+        .transpose()?;
+    Ok(QueryResponse(vec![RowSet {
+        rows: projected_row.map(|row| vec![row]),
+        aggregates: None,
+    }]))
+}
+
+async fn get_marketing_stats(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    kind: MarketingStatsKind,
+    args: schema::MarketingStatsArgs,
+) -> connector::Result<Vec<schema::MarketingStatsRow>> {
+    let params = MarketingStatsParams {
+        start_date: args.start_date,
+        end_date: args.end_date,
+        timezone: args.timezone,
+        aggregated_by: args.aggregated_by,
+        group_by: args.group_by,
+    };
+    let response = sendgrid_api::invoke_get_marketing_stats(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        kind,
+        &args.id,
+        &params,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    Ok(response
+        .results
+        .into_iter()
+        .map(schema::MarketingStatsRow::from)
+        .collect())
+}
+
+async fn get_breakdown_stats(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    breakdown: EmailStatsBreakdown,
+    names: &[String],
+    args: schema::EmailStatsArgs,
+) -> connector::Result<Vec<schema::EmailStatsBreakdownRow>> {
+    if names.is_empty() {
+        return Err(QueryError::new_invalid_request(&format!(
+            "At least one of the {breakdown} must be given"
+        ))
+        .into());
+    }
+    let days = sendgrid_api::invoke_get_breakdown_stats(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        breakdown,
+        names,
+        &email_stats_params(args),
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    Ok(schema::EmailStatsBreakdownRow::from_days(days))
+}
+
+async fn get_breakdown_stats_sums(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    breakdown: EmailStatsBreakdown,
+    args: schema::EmailStatsSumsArgs,
+) -> connector::Result<Vec<schema::EmailStatsBreakdownRow>> {
+    let params = EmailStatsSumsParams {
+        stats: email_stats_params(args.stats),
+        sort_by_metric: args.sort_by_metric,
+        sort_by_direction: args.sort_by_direction,
+        limit: args.limit,
+        offset: args.offset,
+    };
+    let sums = sendgrid_api::invoke_get_breakdown_stats_sums(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        breakdown,
+        &params,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    Ok(schema::EmailStatsBreakdownRow::from_days(vec![sums]))
+}
+
+/// Queries the email_stats collection, fetching the statistics of the requested dimension and
+/// grouping them by the requested columns
+async fn query_email_stats(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    args: schema::EmailStatsCollectionArgs,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    if let (Some(group_by), Some(predicate)) = (&args.group_by, &query.predicate) {
+        let grouped_away = [
+            (schema::EmailStatsGroupColumn::Date, "date"),
+            (schema::EmailStatsGroupColumn::Name, "name"),
+        ]
+        .into_iter()
+        .filter(|(column, _)| !group_by.contains(column))
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>();
+        // The column is null in every grouped row, so the filter would match nothing
+        if let Some(column) = filter::referenced_column(predicate, &grouped_away) {
+            return Err(QueryError::new_invalid_request(&format!(
+                "The {column} column can only be filtered on when the rows are grouped by it. Restrict the rows with the collection's arguments instead"
+            ))
+            .into());
+        }
+    }
+
+    let rows = match args.dimension {
+        None => {
+            let days = sendgrid_api::invoke_get_global_stats(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                &email_stats_params(args.stats),
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            days.into_iter()
+                .map(|day| {
+                    let metrics = day
+                        .stats
+                        .into_iter()
+                        .next()
+                        .map(|entry| entry.metrics)
+                        .unwrap_or_default();
+                    schema::EmailStatsSeriesRow::new(Some(day.date), None, None, metrics)
+                })
+                .collect::<Vec<_>>()
+        }
+        Some(dimension) => {
+            let names = match args.names {
+                Some(names) => names,
+                None => query
+                    .predicate
+                    .as_ref()
+                    .and_then(|predicate| required_string_values(predicate, "name"))
+                    .unwrap_or_default(),
+            };
+            if names.is_empty() && dimension != schema::EmailStatsDimension::MailboxProvider {
+                return Err(QueryError::new_invalid_request(&format!(
+                    "The {}s to retrieve statistics for must be given in the names argument or with an _eq or _in filter on name",
+                    dimension.as_str()
+                ))
+                .into());
+            }
+            let days = sendgrid_api::invoke_get_breakdown_stats(
+                http_client,
+                &configuration.sendgrid_api_base_url,
+                &configuration.sendgrid_api_key,
+                dimension.breakdown(),
+                &names,
+                &email_stats_params(args.stats),
+            )
+            .await
+            .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+            days.into_iter()
+                .flat_map(|day| {
+                    let date = day.date;
+                    day.stats.into_iter().map(move |entry| {
+                        schema::EmailStatsSeriesRow::new(
+                            Some(date.clone()),
+                            Some(dimension),
+                            entry.name,
+                            entry.metrics,
+                        )
+                    })
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    let rows = match &args.group_by {
+        Some(group_by) => group_email_stats(rows, args.dimension, group_by),
+        None => rows,
+    };
+    collection_result(rows, query)
+}
+
+/// Groups rows by the given columns, summing the metrics of each group and recomputing its rates.
+/// Columns that are not grouped by are null in the grouped rows.
+fn group_email_stats(
+    rows: Vec<schema::EmailStatsSeriesRow>,
+    dimension: Option<schema::EmailStatsDimension>,
+    group_by: &[schema::EmailStatsGroupColumn],
+) -> Vec<schema::EmailStatsSeriesRow> {
+    let by_date = group_by.contains(&schema::EmailStatsGroupColumn::Date);
+    let by_name = group_by.contains(&schema::EmailStatsGroupColumn::Name);
+
+    let mut groups: IndexMap<(Option<String>, Option<String>), EmailMetrics> = IndexMap::new();
+    for row in rows {
+        let key = (row.date.filter(|_| by_date), row.name.filter(|_| by_name));
+        *groups.entry(key).or_default() += &row.metrics;
+    }
+
+    groups
+        .into_iter()
+        .map(|((date, name), metrics)| {
+            schema::EmailStatsSeriesRow::new(date, dimension, name, metrics)
+        })
+        .collect()
+}
+
+/// The string values a predicate requires a column to be one of, if there are few enough of them
+/// to pass on to a stats endpoint
+fn required_string_values(predicate: &Expression, column: &str) -> Option<Vec<String>> {
+    filter::required_values(predicate, column)
+        .and_then(|values| {
+            values
+                .into_iter()
+                .map(|value| value.as_str().map(str::to_owned))
+                .collect::<Option<Vec<_>>>()
+        })
+        .filter(|names| !names.is_empty() && names.len() <= MAX_DIMENSION_VALUES)
+}
+
+/// The most values of a dimension that SendGrid accepts in a single stats request
+const MAX_DIMENSION_VALUES: usize = 10;
+
+/// Queries a collection of email statistics broken down by a dimension. Where the endpoint can
+/// restrict the statistics to some values of the dimension, an `_eq` or `_in` comparison on the
+/// `name` column is pushed down to it; the full predicate is still evaluated against the rows.
+async fn query_dimension_stats(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    breakdown: EmailStatsBreakdown,
+    country: Option<String>,
+    args: schema::EmailStatsArgs,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    let names = match breakdown {
+        EmailStatsBreakdown::Geo => country.into_iter().collect(),
+        _ if breakdown.filter_param().is_some() => query
+            .predicate
+            .as_ref()
+            .and_then(|predicate| required_string_values(predicate, "name"))
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    let days = sendgrid_api::invoke_get_breakdown_stats(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        breakdown,
+        &names,
+        &email_stats_params(args),
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    collection_result(schema::EmailStatsBreakdownRow::from_days(days), query)
+}
+
+fn email_stats_params(args: schema::EmailStatsArgs) -> EmailStatsParams {
+    EmailStatsParams {
+        start_date: args.start_date,
+        end_date: args.end_date,
+        aggregated_by: args.aggregated_by,
+    }
+}
+
+const DESIGNS_PAGE_SIZE: u32 = 100;
+
+/// Fetches pages of designs until there are no more pages, or until there are enough designs
+/// to satisfy the query's offset and limit
+async fn fetch_designs(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    query: &Query,
+) -> connector::Result<Vec<sendgrid_api::DesignSummary>> {
+    let row_limit = rows_needed(query);
+
+    let mut designs = vec![];
+    let mut page_token = None;
+    loop {
+        let params = ListDesignsParams {
+            page_size: DESIGNS_PAGE_SIZE,
+            page_token,
+        };
+        let response = sendgrid_api::invoke_list_designs(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &params,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        designs.extend(response.result);
+        page_token = response
+            .metadata
+            .and_then(|metadata| metadata.next_page_token());
+
+        let has_enough_rows = row_limit.is_some_and(|row_limit| designs.len() >= row_limit);
+        if page_token.is_none() || has_enough_rows {
+            break;
+        }
+    }
+    Ok(designs)
+}
+
+const VERIFIED_SENDERS_PAGE_SIZE: u32 = 100;
+const VERIFIED_SENDERS_PAGES_MAX: usize = 100;
+
+/// Fetches every verified sender, a page at a time. Senders whose ID does not advance past the
+/// previous page's last ID are dropped, and paging stops if none remain, as SendGrid would
+/// otherwise keep returning the same page. Fails once VERIFIED_SENDERS_PAGES_MAX pages have been
+/// fetched.
+pub(crate) async fn fetch_verified_senders(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+) -> connector::Result<Vec<sendgrid_api::VerifiedSender>> {
+    let mut verified_senders: Vec<sendgrid_api::VerifiedSender> = vec![];
+    for _ in 0..VERIFIED_SENDERS_PAGES_MAX {
+        let last_seen_id = verified_senders.last().map(|sender| sender.id);
+        let params = ListVerifiedSendersParams {
+            limit: Some(VERIFIED_SENDERS_PAGE_SIZE),
+            last_seen_id,
+        };
+        let mut page = sendgrid_api::invoke_list_verified_senders(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            &params,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?
+        .results;
+
+        let is_last_page = page.len() < VERIFIED_SENDERS_PAGE_SIZE as usize;
+        page.retain(|sender| Some(sender.id) > last_seen_id);
+        let has_advanced = !page.is_empty();
+        verified_senders.extend(page);
+        if is_last_page || !has_advanced {
+            return Ok(verified_senders);
+        }
+    }
+    Err(QueryError::new_invalid_request(&format!(
+        "There are more than {} verified senders",
+        VERIFIED_SENDERS_PAGES_MAX * VERIFIED_SENDERS_PAGE_SIZE as usize
+    ))
+    .into())
+}
+
+/// Looks up an address in every suppression list and unsubscribe group concurrently. When the
+/// suppression cache has been synced, only the group suppressions are looked up with the SendGrid
+/// API.
+async fn get_suppression_status(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    suppression_cache: Option<&SuppressionCache>,
+    email: String,
+) -> connector::Result<schema::SuppressionStatus> {
+    let base_url = configuration.sendgrid_api_base_url.as_str();
+    let api_key = &configuration.sendgrid_api_key;
+
+    if let Some(lists) = suppression_cache.and_then(|cache| cache.lookup(&email)) {
+        let group_suppressions = sendgrid_api::invoke_get_address_group_suppressions(
+            http_client,
+            base_url,
+            api_key,
+            &email,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        return Ok(schema::SuppressionStatus {
+            globally_unsubscribed: lists.contains(&SuppressionList::GlobalUnsubscribes),
+            bounced: lists.contains(&SuppressionList::Bounces),
+            blocked: lists.contains(&SuppressionList::Blocks),
+            spam_reported: lists.contains(&SuppressionList::SpamReports),
+            invalid: lists.contains(&SuppressionList::InvalidEmails),
+            groups: group_suppressions.suppressions,
+            email,
+        });
+    }
+
+    let email_address = email.as_str();
+    let is_on_list = |list| async move {
+        sendgrid_api::invoke_get_suppression(http_client, base_url, api_key, list, email_address)
+            .await
+            .map(|suppressions| !suppressions.is_empty())
+    };
+
+    let (global_unsubscribe, group_suppressions, bounced, blocked, spam_reported, invalid) =
+        tokio::try_join!(
+            sendgrid_api::invoke_get_global_unsubscribe(http_client, base_url, api_key, &email),
+            sendgrid_api::invoke_get_address_group_suppressions(
+                http_client,
+                base_url,
+                api_key,
+                &email
+            ),
+            is_on_list(SuppressionList::Bounces),
+            is_on_list(SuppressionList::Blocks),
+            is_on_list(SuppressionList::SpamReports),
+            is_on_list(SuppressionList::InvalidEmails),
+        )
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    Ok(schema::SuppressionStatus {
+        globally_unsubscribed: global_unsubscribe.recipient_email.is_some(),
+        bounced,
+        blocked,
+        spam_reported,
+        invalid,
+        groups: group_suppressions.suppressions,
+        email,
+    })
+}
+
+fn preference_link_configuration(
+    configuration: &configuration::SendGridConfiguration,
+) -> connector::Result<&configuration::PreferenceLinkConfiguration> {
+    configuration.preference_links.as_ref().ok_or_else(|| {
+        QueryError::new_invalid_request(
+            &"Preference links are not configured. Set SENDGRID_PREFERENCE_LINK_SECRET and SENDGRID_PREFERENCE_LINK_BASE_URL to enable them.",
+        )
+        .into()
+    })
+}
+
+/// The number of validation requests validate_emails has in flight at once
+const EMAIL_VALIDATION_CONCURRENCY: usize = 10;
+
+/// Validates an address with the validation API key, falling back to the main API key
+async fn validate_email(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    email: String,
+    source: Option<String>,
+) -> connector::Result<sendgrid_api::EmailValidation> {
+    sendgrid_api::invoke_validate_email(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        configuration
+            .sendgrid_validation_api_key
+            .as_ref()
+            .unwrap_or(&configuration.sendgrid_api_key),
+        &sendgrid_api::ValidateEmailRequest { email, source },
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))
+}
+
+const SUPPRESSIONS_PAGE_SIZE: u32 = 500;
+
+/// The most entries of a suppression list fetched to answer a single query
+const SUPPRESSIONS_FETCH_MAX: usize = 10_000;
+
+/// The most messages the Email Activity API returns for a single query
+const MESSAGES_LIMIT_MAX: u32 = 1000;
+
+/// Queries the Email Activity feed, evaluating as much of the query's predicate as possible in
+/// SendGrid and the rest against the returned messages
+async fn query_messages(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    let translated = query
+        .predicate
+        .as_ref()
+        .map(activity_query::translate_predicate)
+        .unwrap_or_default();
+    if let Some(column) = translated
+        .residual
+        .as_ref()
+        .and_then(activity_query::unreturned_column)
+    {
+        return Err(QueryError::new_invalid_request(&format!(
+            "'{column}' is not returned when listing messages, so it can only be filtered on with a _contains that SendGrid evaluates: a string category or an object of string or number arguments, not combined by _or or _not with filters SendGrid cannot evaluate"
+        ))
+        .into());
+    }
+    let residual_query = Query {
+        predicate: translated.residual,
+        ..query.clone()
+    };
+    let limit = rows_needed(&residual_query).map_or(MESSAGES_LIMIT_MAX, |rows| {
+        rows.min(MESSAGES_LIMIT_MAX as usize) as u32
+    });
+
+    let response = sendgrid_api::invoke_list_messages(
+        http_client,
+        &configuration.sendgrid_api_base_url,
+        &configuration.sendgrid_api_key,
+        translated.query.as_deref(),
+        limit,
+    )
+    .await
+    .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+    // SendGrid lists no more than MESSAGES_LIMIT_MAX messages, so filtering a full listing in the
+    // connector could silently leave out matching messages that were not listed
+    if residual_query.predicate.is_some() && response.messages.len() >= MESSAGES_LIMIT_MAX as usize
+    {
+        return Err(QueryError::new_invalid_request(&format!(
+            "More than {MESSAGES_LIMIT_MAX} messages match the filters SendGrid can evaluate, so the rest of the filters cannot be applied to all of them. Narrow the query with filters SendGrid evaluates, such as on msg_id, from_email, to_email, subject, status or last_event_time"
+        ))
+        .into());
+    }
+
+    collection_result(response.messages, &residual_query)
+}
+
+/// Queries the events received by the Event Webhook route. As much of the predicate as possible
+/// is evaluated by SQLite, and when all of it is, so are the ordering, offset and limit.
+async fn query_email_events(
+    event_store: Option<&EventStore>,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    let event_store = event_store.ok_or_else(|| {
+        QueryError::new_invalid_request(
+            &"The Event Webhook is not enabled. Set SENDGRID_EVENT_STORE_PATH to enable it.",
+        )
+    })?;
+
+    let translated = query
+        .predicate
+        .as_ref()
+        .map(event_store::translate_predicate)
+        .unwrap_or_default();
+    let mut filter = translated.filter;
+    let mut residual_query = Query {
+        predicate: translated.residual,
+        ..query.clone()
+    };
+    if let Some(order_by) = sortable_event_columns(&residual_query) {
+        filter.order_by = order_by;
+        if residual_query.predicate.is_none() {
+            filter.offset = residual_query.offset.take().map(|offset| offset as usize);
+            filter.limit = residual_query.limit.take().map(|limit| limit as usize);
+        }
+    }
+    let events = event_store.query_events(filter).await?;
+
+    collection_result(events, &residual_query)
+}
+
+/// The columns a query on the email_events collection is sorted by, if SQLite can sort by all of
+/// them
+fn sortable_event_columns(query: &Query) -> Option<Vec<(&'static str, OrderDirection)>> {
+    let Some(order_by) = &query.order_by else {
+        return Some(vec![]);
+    };
+    order_by
+        .elements
+        .iter()
+        .map(|element| match &element.target {
+            OrderByTarget::Column {
+                name,
+                field_path: None,
+                path,
+            } if path.is_empty() => event_store::SORTABLE_COLUMNS
+                .into_iter()
+                .find(|column| *column == name.as_str())
+                .map(|column| (column, element.order_direction)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Queries a suppression list, pushing down equality on email and a range on created to the
+/// SendGrid API where the predicate allows it. The global unsubscribes list cannot be looked up by
+/// email, so it is always fetched by range.
+async fn query_suppressions(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    list: SuppressionList,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    let required_email = query
+        .predicate
+        .as_ref()
+        .filter(|_| list != SuppressionList::GlobalUnsubscribes)
+        .and_then(|predicate| filter::required_equality(predicate, "email"))
+        .and_then(|email| email.as_str());
+
+    match list {
+        SuppressionList::Bounces | SuppressionList::Blocks => {
+            let rows = fetch_suppressions(
+                http_client,
+                configuration,
+                list,
+                required_email,
+                query,
+                schema::ClassifiedSuppression::from,
+            )
+            .await?;
+            collection_result(rows, query)
+        }
+        _ => {
+            let rows = fetch_suppressions(
+                http_client,
+                configuration,
+                list,
+                required_email,
+                query,
+                |suppression| suppression,
+            )
+            .await?;
+            collection_result(rows, query)
+        }
+    }
+}
+
+/// Fetches the entries of a suppression list that match the query's predicate: the entry for an
+/// address if there is one, or otherwise pages of the entries within the range the predicate
+/// places on created, until there are no more pages or enough match to satisfy an unsorted
+/// query's offset and limit. Fails rather than answer from part of the list once
+/// SUPPRESSIONS_FETCH_MAX entries have been fetched.
+async fn fetch_suppressions<T: Serialize>(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    list: SuppressionList,
+    email: Option<&str>,
+    query: &Query,
+    into_row: impl Fn(sendgrid_api::Suppression) -> T,
+) -> connector::Result<Vec<T>> {
+    if let Some(email) = email {
+        return sendgrid_api::invoke_get_suppression(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            list,
+            email,
+        )
+        .await
+        .map(|suppressions| suppressions.into_iter().map(into_row).collect())
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()));
+    }
+
+    let (start_time, end_time) = query.predicate.as_ref().map_or((None, None), |predicate| {
+        filter::required_range(predicate, "created")
+    });
+    // Without an ordering, the first entries that match are the ones the query returns
+    let matches_needed = match (&query.order_by, query.limit) {
+        (None, Some(limit)) => Some(query.offset.unwrap_or(0) as usize + limit as usize),
+        _ => None,
+    };
+
+    let mut rows = vec![];
+    let mut fetched = 0;
+    loop {
+        let params = ListSuppressionsParams {
+            start_time,
+            end_time,
+            limit: SUPPRESSIONS_PAGE_SIZE,
+            offset: fetched as u32,
+        };
+        let page = sendgrid_api::invoke_list_suppressions(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            list,
+            &params,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        let is_last_page = page.len() < SUPPRESSIONS_PAGE_SIZE as usize;
+        fetched += page.len();
+        for suppression in page {
+            let row = into_row(suppression);
+            let matches = match &query.predicate {
+                Some(predicate) => eval_predicate(predicate, &to_row(&row)?)?,
+                None => true,
+            };
+            if matches {
+                rows.push(row);
+            }
+        }
+
+        let has_enough_rows = matches_needed.is_some_and(|needed| rows.len() >= needed);
+        if is_last_page || has_enough_rows {
+            break;
+        }
+        if fetched >= SUPPRESSIONS_FETCH_MAX {
+            return Err(QueryError::new_invalid_request(&format!(
+                "The {list} list has more than {SUPPRESSIONS_FETCH_MAX} entries in the queried range. Narrow the query with a range on created or an _eq on email, or query it without sorting."
+            ))
+            .into());
+        }
+    }
+    Ok(rows)
+}
+
+/// The number of rows that need to be fetched to answer a query, if it can be known before the
+/// rows are filtered and sorted
+fn rows_needed(query: &Query) -> Option<usize> {
+    match (&query.predicate, &query.order_by, query.limit) {
+        (None, None, Some(limit)) => Some(query.offset.unwrap_or(0) as usize + limit as usize),
+        _ => None,
+    }
+}
+
+/// The columns of a row of a collection, as they are filtered and sorted on
+fn to_row<T: Serialize>(row: &T) -> connector::Result<IndexMap<String, serde_json::Value>> {
+    serde_json::to_value(row)
+        .and_then(serde_json::from_value::<IndexMap<String, serde_json::Value>>)
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))
+}
+
+/// Filters, sorts and paginates the rows of a collection according to the query, and projects
+/// the requested fields and evaluates the requested aggregates over the remaining rows
+fn collection_result<T: Serialize>(
+    rows: Vec<T>,
+    query: &Query,
+) -> connector::Result<QueryResponse> {
+    let mut rows = rows
+        .iter()
+        .map(to_row)
+        .collect::<connector::Result<Vec<_>>>()?;
+
+    if let Some(predicate) = &query.predicate {
+        let mut filtered_rows = vec![];
+        for row in rows {
+            if eval_predicate(predicate, &row)? {
+                filtered_rows.push(row);
+            }
+        }
+        rows = filtered_rows;
+    }
+
+    if let Some(order_by) = &query.order_by {
+        let mut sort_keys = vec![];
+        for element in &order_by.elements {
+            match &element.target {
+                OrderByTarget::Column {
+                    name,
+                    field_path: None,
+                    path,
+                } if path.is_empty() => sort_keys.push((name.as_str(), element.order_direction)),
+                _ => {
+                    return Err(QueryError::new_unsupported_operation(
+                        &"Only sorting by columns of the collection is supported",
+                    )
+                    .into())
+                }
+            }
+        }
+        rows.sort_by(|left, right| {
+            sort_keys
+                .iter()
+                .map(|(column, direction)| {
+                    let ordering = compare_values(
+                        left.get(*column).unwrap_or(&serde_json::Value::Null),
+                        right.get(*column).unwrap_or(&serde_json::Value::Null),
+                    )
+                    .unwrap_or(std::cmp::Ordering::Equal);
+                    match direction {
+                        OrderDirection::Asc => ordering,
+                        OrderDirection::Desc => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    let rows = rows
+        .into_iter()
+        .skip(query.offset.unwrap_or(0) as usize)
+        .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+        .collect::<Vec<_>>();
+
+    let projected_rows = query
+        .fields
+        .as_ref()
+        .map(|fields| {
+            rows.iter()
+                .map(|row| eval_row(fields, row))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let aggregates = query
+        .aggregates
+        .as_ref()
+        .map(|aggregates| eval_aggregates(aggregates, &rows))
+        .transpose()?;
+
+    Ok(QueryResponse(vec![RowSet {
+        rows: projected_rows,
+        aggregates,
+    }]))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Query as QueryParams, State},
+        routing::get,
+        Json, Router,
+    };
+    use ndc_sdk::models::{ComparisonOperatorName, ComparisonTarget, ComparisonValue, FieldName};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sendgrid_api::SendGridApiKey;
+
+    /// Serves `sender_count` verified senders, a page at a time, on an unused local port and
+    /// returns the stand-in's base URL. When `honours_last_seen_id` is false, every request gets
+    /// the first page.
+    fn start_stand_in(sender_count: u32, honours_last_seen_id: bool) -> String {
+        let router = Router::new()
+            .route(
+                "/v3/verified_senders",
+                get(
+                    |State((sender_count, honours_last_seen_id)): State<(u32, bool)>,
+                     QueryParams(params): QueryParams<HashMap<String, u32>>| async move {
+                        let limit = params.get("limit").copied().unwrap_or(sender_count);
+                        let first_id = params
+                            .get("lastSeenID")
+                            .filter(|_| honours_last_seen_id)
+                            .map_or(1, |last_seen_id| last_seen_id + 1);
+                        let results = (first_id..=sender_count)
+                            .take(limit as usize)
+                            .map(|id| {
+                                json!({
+                                    "id": id,
+                                    "nickname": format!("Sender {id}"),
+                                    "from_email": format!("sender{id}@example.com"),
+                                    "reply_to": format!("sender{id}@example.com"),
+                                    "verified": true,
+                                    "locked": false,
+                                })
+                            })
+                            .collect::<Vec<_>>();
+                        Json(json!({ "results": results }))
+                    },
+                ),
+            )
+            .with_state((sender_count, honours_last_seen_id));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+        base_url
+    }
+
+    fn test_configuration(base_url: &str) -> configuration::SendGridConfiguration {
+        let Ok(api_key) = SendGridApiKey::new("test-api-key") else {
+            panic!("The API key is not blank");
+        };
+        configuration::SendGridConfiguration {
+            sendgrid_api_key: api_key,
+            sendgrid_validation_api_key: None,
+            sendgrid_api_base_url: base_url.to_owned(),
+            sendgrid_upload_base_url: None,
+            suppression_cache_refresh_seconds: None,
+            suppression_cache_max_age_seconds: None,
+            recipient_safety: configuration::RecipientSafetyConfiguration::default(),
+            preference_links: None,
+            event_webhook: None,
+            event_webhook_verification: None,
+            configuration_dir: PathBuf::from("."),
+        }
+    }
+
+    #[tokio::test]
+    async fn email_stats_reject_filters_on_grouped_away_columns() {
+        let query = Query {
+            predicate: Some(Expression::BinaryComparisonOperator {
+                column: ComparisonTarget::Column {
+                    name: FieldName::from("name"),
+                    field_path: None,
+                    path: vec![],
+                },
+                operator: ComparisonOperatorName::from(filter::EQUAL_OPERATOR),
+                value: ComparisonValue::Scalar {
+                    value: json!("gmail"),
+                },
+            }),
+            ..serde_json::from_value(json!({})).unwrap()
+        };
+        let args = schema::EmailStatsCollectionArgs {
+            dimension: Some(schema::EmailStatsDimension::MailboxProvider),
+            names: None,
+            group_by: Some(vec![schema::EmailStatsGroupColumn::Date]),
+            stats: schema::EmailStatsArgs {
+                start_date: String::from("2024-05-01"),
+                end_date: None,
+                aggregated_by: None,
+            },
+        };
+
+        // Rejected before SendGrid is called
+        let Err(err) = query_email_stats(
+            &reqwest::Client::new(),
+            &test_configuration("http://127.0.0.1:9"),
+            args,
+            &query,
+        )
+        .await
+        else {
+            panic!("A filter on name is rejected when the rows are not grouped by name");
+        };
+        assert!(format!("{err:?}").contains("The name column can only be filtered on"));
+    }
+
+    #[tokio::test]
+    async fn messages_with_a_residual_filter_fail_when_the_listing_is_full() {
+        let router = Router::new().route(
+            "/v3/messages",
+            get(|| async {
+                let messages = (0..MESSAGES_LIMIT_MAX)
+                    .map(|index| {
+                        json!({
+                            "msg_id": format!("msg-{index}"),
+                            "from_email": "news@example.com",
+                            "to_email": format!("recipient{index}@example.com"),
+                            "subject": "News",
+                            "status": "delivered",
+                            "opens_count": index % 2,
+                            "last_event_time": "2024-05-01T12:00:00Z",
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Json(json!({ "messages": messages }))
+            }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+
+        // opens_count cannot be filtered on by SendGrid
+        let query = Query {
+            predicate: Some(Expression::BinaryComparisonOperator {
+                column: ComparisonTarget::Column {
+                    name: FieldName::from("opens_count"),
+                    field_path: None,
+                    path: vec![],
+                },
+                operator: ComparisonOperatorName::from(filter::GREATER_THAN_OPERATOR),
+                value: ComparisonValue::Scalar { value: json!(0) },
+            }),
+            ..serde_json::from_value(json!({})).unwrap()
+        };
+
+        let Err(err) = query_messages(
+            &reqwest::Client::new(),
+            &test_configuration(&base_url),
+            &query,
+        )
+        .await
+        else {
+            panic!("Filtering a full listing in the connector could leave out messages");
+        };
+        assert!(format!("{err:?}").contains("More than 1000 messages match"));
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_senders_reads_every_page() {
+        let base_url = start_stand_in(250, true);
+
+        let verified_senders =
+            fetch_verified_senders(&reqwest::Client::new(), &test_configuration(&base_url))
+                .await
+                .unwrap();
+
+        let ids = verified_senders
+            .iter()
+            .map(|sender| sender.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (1..=250).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn fetch_verified_senders_stops_when_the_last_id_does_not_advance() {
+        let base_url = start_stand_in(250, false);
+
+        let verified_senders =
+            fetch_verified_senders(&reqwest::Client::new(), &test_configuration(&base_url))
+                .await
+                .unwrap();
+
+        assert_eq!(verified_senders.len(), VERIFIED_SENDERS_PAGE_SIZE as usize);
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ndc_sdk::connector::MutationError;
+
+use super::configuration::RecipientSafetyConfiguration;
+use super::schema::{DuplicateRecipientRule, RecipientChange};
+use super::sendgrid_api::{
+    MailAddress, MailPersonalization, MailSetting, MailSettings, SendMailRequest,
+};
+
+const RECIPIENT_FIELDS: [&str; 3] = ["to", "cc", "bcc"];
+
+/// The header that records the recipients a personalization was redirected away from
+const ORIGINAL_RECIPIENTS_HEADER: &str = "X-Original-Recipients";
+
+/// Normalizes the recipients of each personalization and removes addresses that appear more
+/// than once in a personalization, returning every change made
+pub fn normalize_recipients(
+    personalizations: &mut [MailPersonalization],
+    rule: DuplicateRecipientRule,
+) -> Result<Vec<RecipientChange>, MutationError> {
+    let mut changes = vec![];
+    for (index, personalization) in personalizations.iter_mut().enumerate() {
+        normalize_personalization(index, personalization, rule, &mut changes)?;
+    }
+    Ok(changes)
+}
+
+fn normalize_personalization(
+    index: usize,
+    personalization: &mut MailPersonalization,
+    rule: DuplicateRecipientRule,
+    changes: &mut Vec<RecipientChange>,
+) -> Result<(), MutationError> {
+    let mut fields = take_recipient_fields(personalization);
+
+    for (field, addresses) in RECIPIENT_FIELDS.into_iter().zip(fields.iter_mut()) {
+        for address in addresses.iter_mut() {
+            let normalized = normalize_email(&address.email)?;
+            if normalized != address.email {
+                changes.push(RecipientChange {
+                    personalization: index,
+                    field,
+                    original: std::mem::replace(&mut address.email, normalized.clone()),
+                    email: Some(normalized),
+                    change: "normalized",
+                    kept_in: None,
+                });
+            }
+        }
+    }
+
+    remove_duplicates(index, &mut fields, rule, changes);
+    put_recipient_fields(index, personalization, fields)
+}
+
+/// Rewrites every recipient that is not allowlisted to the catch-all address, recording the
+/// original recipients in a header on the personalization, and forces sandbox mode if configured.
+/// Without a catch-all address, the mail is rejected if any recipient is not allowlisted.
+pub fn apply_recipient_safety(
+    request: &mut SendMailRequest,
+    safety: &RecipientSafetyConfiguration,
+) -> Result<Vec<RecipientChange>, MutationError> {
+    let mut changes = vec![];
+    let personalizations = if safety.restricts_recipients() {
+        request.personalizations.iter_mut()
+    } else {
+        [].iter_mut()
+    };
+
+    for (index, personalization) in personalizations.enumerate() {
+        let mut fields = take_recipient_fields(personalization);
+        let mut originals = vec![];
+
+        for (field, addresses) in RECIPIENT_FIELDS.into_iter().zip(fields.iter_mut()) {
+            for address in addresses.iter_mut() {
+                if safety.is_allowed(&address.email) {
+                    continue;
+                }
+                let redirect_to = safety.redirect_to.as_ref().ok_or_else(|| {
+                    MutationError::new_invalid_request(&format!(
+                        "The recipient '{}' is not on the recipient allowlist",
+                        address.email
+                    ))
+                })?;
+                originals.push(format!("{field}: {}", address.email));
+                changes.push(RecipientChange {
+                    personalization: index,
+                    field,
+                    original: std::mem::replace(&mut address.email, redirect_to.clone()),
+                    email: Some(redirect_to.clone()),
+                    change: "redirected",
+                    kept_in: None,
+                });
+            }
+        }
+
+        if !originals.is_empty() {
+            personalization
+                .headers
+                .get_or_insert_with(BTreeMap::new)
+                .insert(ORIGINAL_RECIPIENTS_HEADER.to_owned(), originals.join(", "));
+        }
+
+        // Several redirected recipients end up as the same catch-all address, which SendGrid
+        // only accepts once per personalization
+        remove_duplicates(
+            index,
+            &mut fields,
+            DuplicateRecipientRule::KeepMostVisible,
+            &mut changes,
+        );
+        put_recipient_fields(index, personalization, fields)?;
+    }
+
+    if safety.force_sandbox_mode {
+        request.mail_settings = Some(MailSettings {
+            sandbox_mode: Some(MailSetting { enable: true }),
+        });
+    }
+
+    Ok(changes)
+}
+
+fn take_recipient_fields(personalization: &mut MailPersonalization) -> [Vec<MailAddress>; 3] {
+    [
+        std::mem::take(&mut personalization.to),
+        personalization.cc.take().unwrap_or_default(),
+        personalization.bcc.take().unwrap_or_default(),
+    ]
+}
+
+fn put_recipient_fields(
+    index: usize,
+    personalization: &mut MailPersonalization,
+    fields: [Vec<MailAddress>; 3],
+) -> Result<(), MutationError> {
+    let [to, cc, bcc] = fields;
+    if to.is_empty() {
+        return Err(MutationError::new_invalid_request(&format!(
+            "Personalization {index} has no 'to' recipients"
+        )));
+    }
+    personalization.to = to;
+    personalization.cc = non_empty(cc);
+    personalization.bcc = non_empty(bcc);
+    Ok(())
+}
+
+/// Keeps each address only once across to, cc and bcc, in the field chosen by the rule.
+/// Addresses are compared ignoring case, since SendGrid rejects a personalization with addresses
+/// that differ only in case. An address is always kept in 'to' when it is the only one there, as
+/// SendGrid requires at least one 'to' recipient.
+fn remove_duplicates(
+    index: usize,
+    fields: &mut [Vec<MailAddress>; 3],
+    rule: DuplicateRecipientRule,
+    changes: &mut Vec<RecipientChange>,
+) {
+    // Decide which field keeps each address by visiting the fields in order of precedence
+    let precedence = match rule {
+        DuplicateRecipientRule::KeepMostVisible => [0, 1, 2],
+        DuplicateRecipientRule::KeepMostPrivate => [2, 1, 0],
+    };
+    let mut kept_in: BTreeMap<String, usize> = BTreeMap::new();
+    for field_index in precedence {
+        for address in &fields[field_index] {
+            kept_in
+                .entry(address.email.to_lowercase())
+                .or_insert(field_index);
+        }
+    }
+    if !kept_in.values().any(|field_index| *field_index == 0) {
+        if let Some(first_to) = fields[0].first() {
+            kept_in.insert(first_to.email.to_lowercase(), 0);
+        }
+    }
+
+    let mut kept = BTreeSet::new();
+    for (field_index, field) in RECIPIENT_FIELDS.into_iter().enumerate() {
+        fields[field_index].retain(|address| {
+            let key = address.email.to_lowercase();
+            let kept_field_index = kept_in[&key];
+            if kept_field_index == field_index && kept.insert(key) {
+                return true;
+            }
+            changes.push(RecipientChange {
+                personalization: index,
+                field,
+                original: address.email.clone(),
+                email: None,
+                change: "removed_duplicate",
+                kept_in: Some(RECIPIENT_FIELDS[kept_field_index]),
+            });
+            false
+        });
+    }
+}
+
+fn non_empty(addresses: Vec<MailAddress>) -> Option<Vec<MailAddress>> {
+    (!addresses.is_empty()).then_some(addresses)
+}
+
+/// Trims an address and converts its domain to lowercase ASCII, encoding internationalized
+/// domains as punycode. The local part is left as is, since it is case-sensitive in principle,
+/// though duplicates are still found ignoring its case.
+pub fn normalize_email(email: &str) -> Result<String, MutationError> {
+    let email = email.trim();
+    match email.rsplit_once('@') {
+        Some((local_part, domain)) if !local_part.is_empty() && !domain.is_empty() => {
+            let ascii_domain = normalize_domain(domain).ok_or_else(|| {
+                MutationError::new_invalid_request(&format!(
+                    "The recipient address '{email}' does not have a valid domain"
+                ))
+            })?;
+            Ok(format!("{local_part}@{ascii_domain}"))
+        }
+        _ => Err(MutationError::new_invalid_request(&format!(
+            "The recipient address '{email}' is not a valid email address"
+        ))),
+    }
+}
+
+/// Converts a domain to lowercase ASCII, encoding an internationalized domain as punycode
+pub fn normalize_domain(domain: &str) -> Option<String> {
+    idna::domain_to_ascii(domain).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(emails: &[&str]) -> Vec<MailAddress> {
+        emails
+            .iter()
+            .map(|email| MailAddress {
+                email: String::from(*email),
+                name: None,
+            })
+            .collect()
+    }
+
+    fn personalization(to: &[&str], cc: &[&str], bcc: &[&str]) -> MailPersonalization {
+        MailPersonalization {
+            from: None,
+            to: addresses(to),
+            cc: non_empty(addresses(cc)),
+            bcc: non_empty(addresses(bcc)),
+            subject: None,
+            headers: None,
+            substitutions: None,
+            dynamic_template_data: None,
+            send_at: None,
+        }
+    }
+
+    fn request(personalization: MailPersonalization) -> SendMailRequest {
+        SendMailRequest {
+            personalizations: vec![personalization],
+            from: addresses(&["noreply@corp.example"]).remove(0),
+            reply_to_list: vec![],
+            subject: String::from("Hello"),
+            content: vec![],
+            attachments: None,
+            template_id: None,
+            headers: None,
+            send_at: None,
+            batch_id: None,
+            asm: None,
+            mail_settings: None,
+            custom_args: None,
+        }
+    }
+
+    fn emails(addresses: &[MailAddress]) -> Vec<&str> {
+        addresses
+            .iter()
+            .map(|address| address.email.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn normalize_email_lowercases_and_encodes_the_domain_only() {
+        assert_eq!(
+            normalize_email("  Ada.Lovelace@Example.COM ").unwrap(),
+            "Ada.Lovelace@example.com"
+        );
+        assert_eq!(
+            normalize_email("info@bücher.de").unwrap(),
+            "info@xn--bcher-kva.de"
+        );
+    }
+
+    #[test]
+    fn normalize_email_rejects_addresses_without_a_local_part_or_domain() {
+        assert!(normalize_email("ada.example.com").is_err());
+        assert!(normalize_email("@example.com").is_err());
+        assert!(normalize_email("ada@").is_err());
+    }
+
+    #[test]
+    fn keep_most_visible_keeps_duplicates_in_to_over_cc_over_bcc() {
+        let mut personalizations = [personalization(
+            &["ada@example.com"],
+            &["grace@example.com", "ADA@example.com"],
+            &["grace@example.com"],
+        )];
+
+        let changes = normalize_recipients(
+            &mut personalizations,
+            DuplicateRecipientRule::KeepMostVisible,
+        )
+        .unwrap();
+
+        let [personalization] = &personalizations;
+        assert_eq!(emails(&personalization.to), ["ada@example.com"]);
+        assert_eq!(
+            emails(personalization.cc.as_deref().unwrap_or_default()),
+            ["grace@example.com"]
+        );
+        assert!(personalization.bcc.is_none());
+        let removed = changes
+            .iter()
+            .map(|change| (change.field, change.original.as_str(), change.kept_in))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            removed,
+            [
+                ("cc", "ADA@example.com", Some("to")),
+                ("bcc", "grace@example.com", Some("cc")),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_most_private_moves_duplicates_out_of_to() {
+        let mut personalizations = [personalization(
+            &["ada@example.com", "grace@example.com"],
+            &[],
+            &["grace@example.com"],
+        )];
+
+        normalize_recipients(
+            &mut personalizations,
+            DuplicateRecipientRule::KeepMostPrivate,
+        )
+        .unwrap();
+
+        let [personalization] = &personalizations;
+        assert_eq!(emails(&personalization.to), ["ada@example.com"]);
+        assert_eq!(
+            emails(personalization.bcc.as_deref().unwrap_or_default()),
+            ["grace@example.com"]
+        );
+    }
+
+    #[test]
+    fn keep_most_private_keeps_the_only_to_recipient_in_to() {
+        let mut personalizations = [personalization(
+            &["ada@example.com"],
+            &[],
+            &["ada@example.com", "grace@example.com"],
+        )];
+
+        let changes = normalize_recipients(
+            &mut personalizations,
+            DuplicateRecipientRule::KeepMostPrivate,
+        )
+        .unwrap();
+
+        let [personalization] = &personalizations;
+        assert_eq!(emails(&personalization.to), ["ada@example.com"]);
+        assert_eq!(
+            emails(personalization.bcc.as_deref().unwrap_or_default()),
+            ["grace@example.com"]
+        );
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "bcc");
+        assert_eq!(changes[0].kept_in, Some("to"));
+    }
+
+    #[test]
+    fn redirected_recipients_are_sent_to_the_catch_all_address_once() {
+        let mut request = request(personalization(
+            &["ada@example.com", "grace@corp.example"],
+            &["alan@example.org"],
+            &[],
+        ));
+        let safety = RecipientSafetyConfiguration {
+            allowlist: vec![String::from("corp.example")],
+            redirect_to: Some(String::from("catch-all@corp.example")),
+            force_sandbox_mode: true,
+        };
+
+        apply_recipient_safety(&mut request, &safety).unwrap();
+
+        let personalization = &request.personalizations[0];
+        assert_eq!(
+            emails(&personalization.to),
+            ["catch-all@corp.example", "grace@corp.example"]
+        );
+        assert!(personalization.cc.is_none());
+        assert_eq!(
+            personalization.headers.as_ref().unwrap()[ORIGINAL_RECIPIENTS_HEADER],
+            "to: ada@example.com, cc: alan@example.org"
+        );
+        assert!(request.mail_settings.is_some());
+    }
+
+    #[test]
+    fn recipients_not_on_the_allowlist_are_rejected_without_a_catch_all_address() {
+        let mut request = request(personalization(&["ada@example.com"], &[], &[]));
+        let safety = RecipientSafetyConfiguration {
+            allowlist: vec![String::from("corp.example")],
+            ..RecipientSafetyConfiguration::default()
+        };
+
+        assert!(apply_recipient_safety(&mut request, &safety).is_err());
+    }
+}
@@ -1,20 +1,37 @@
 use std::collections::BTreeMap;
 
 use ndc_sdk::models::{
-    ArgumentInfo, ArgumentName, FieldName, FunctionInfo, FunctionName, ObjectField, ObjectType,
-    ObjectTypeName, ProcedureInfo, ProcedureName, ScalarType, ScalarTypeName, SchemaResponse, Type,
-    TypeName, TypeRepresentation,
+    AggregateFunctionDefinition, AggregateFunctionName, ArgumentInfo, ArgumentName, CollectionInfo,
+    CollectionName, ComparisonOperatorDefinition, ComparisonOperatorName, FieldName, FunctionInfo,
+    FunctionName, ObjectField, ObjectType, ObjectTypeName, ProcedureInfo, ProcedureName,
+    ScalarType, ScalarTypeName, SchemaResponse, Type, TypeName, TypeRepresentation,
+    UniquenessConstraint,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+use super::aggregates;
+use super::bounce_classifier::{self, BounceClassification};
+use super::filter;
 use super::sendgrid_api;
+use super::suppression_cache::{unix_seconds, SyncSummary};
 
 pub fn make_schema_response() -> SchemaResponse {
     SchemaResponse {
         scalar_types: BTreeMap::from([
-            (ScalarTypeName::from("String"), STRING_SCALAR_TYPE),
-            (ScalarTypeName::from("Int"), INT_SCALAR_TYPE),
-            (ScalarTypeName::from("Bool"), BOOL_SCALAR_TYPE),
+            (ScalarTypeName::from("String"), string_scalar_type()),
+            (ScalarTypeName::from("Int"), int_scalar_type()),
+            (ScalarTypeName::from("Float"), float_scalar_type()),
+            (ScalarTypeName::from("Bool"), bool_scalar_type()),
+            (ScalarTypeName::from("Date"), date_scalar_type()),
+            (ScalarTypeName::from("Timestamp"), timestamp_scalar_type()),
+            (
+                ScalarTypeName::from("CategoryList"),
+                contains_scalar_type("String"),
+            ),
+            (
+                ScalarTypeName::from("UniqueArgs"),
+                contains_scalar_type("UniqueArgs"),
+            ),
         ]),
         object_types: BTreeMap::from([
             (
@@ -46,30 +63,390 @@ pub fn make_schema_response() -> SchemaResponse {
                 ObjectTypeName::from("send_mail_response"),
                 send_mail_response(),
             ),
+            (ObjectTypeName::from("recipient_change"), recipient_change()),
+            (
+                ObjectTypeName::from("suppressed_recipient"),
+                suppressed_recipient(),
+            ),
+            (
+                ObjectTypeName::from("contact_import_job"),
+                contact_import_job(),
+            ),
+            (
+                ObjectTypeName::from("contact_import_results"),
+                contact_import_results(),
+            ),
+            (
+                ObjectTypeName::from("export_contacts_response"),
+                export_contacts_response(),
+            ),
+            (
+                ObjectTypeName::from("contact_export_job"),
+                contact_export_job(),
+            ),
+            (ObjectTypeName::from("design_summary"), design_summary()),
+            (ObjectTypeName::from("design"), design()),
+            (ObjectTypeName::from("verified_sender"), verified_sender()),
+            (
+                ObjectTypeName::from("verified_sender_reference"),
+                verified_sender_reference(),
+            ),
+            (
+                ObjectTypeName::from("marketing_stats_row"),
+                marketing_stats_row(),
+            ),
+            (ObjectTypeName::from("email_stats_row"), email_stats_row()),
+            (
+                ObjectTypeName::from("email_stats_breakdown_row"),
+                email_stats_breakdown_row(),
+            ),
+            (
+                ObjectTypeName::from("email_stats_series_row"),
+                email_stats_series_row(),
+            ),
+            (ObjectTypeName::from("message"), message()),
+            (ObjectTypeName::from("email_event"), email_event()),
+            (
+                ObjectTypeName::from("event_webhook_verification"),
+                event_webhook_verification(),
+            ),
+            (ObjectTypeName::from("mail_batch"), mail_batch()),
+            (ObjectTypeName::from("scheduled_send"), scheduled_send()),
+            (
+                ObjectTypeName::from("batch_id_validation"),
+                batch_id_validation(),
+            ),
+            (ObjectTypeName::from("custom_arg"), custom_arg()),
+            (
+                ObjectTypeName::from("delivery_status_response"),
+                delivery_status_response(),
+            ),
+            (
+                ObjectTypeName::from("recipient_delivery_status"),
+                recipient_delivery_status(),
+            ),
+            (ObjectTypeName::from("message_detail"), message_detail()),
+            (ObjectTypeName::from("message_event"), message_event()),
+            (ObjectTypeName::from("bounce"), bounce()),
+            (ObjectTypeName::from("block"), block()),
+            (ObjectTypeName::from("spam_report"), spam_report()),
+            (ObjectTypeName::from("invalid_email"), invalid_email()),
+            (
+                ObjectTypeName::from("delete_suppressions_response"),
+                delete_suppressions_response(),
+            ),
+            (
+                ObjectTypeName::from("global_unsubscribe"),
+                global_unsubscribe(),
+            ),
+            (
+                ObjectTypeName::from("global_unsubscribes_response"),
+                global_unsubscribes_response(),
+            ),
+            (
+                ObjectTypeName::from("suppression_status"),
+                suppression_status(),
+            ),
+            (
+                ObjectTypeName::from("group_suppression"),
+                group_suppression(),
+            ),
+            (
+                ObjectTypeName::from("suppression_cache_sync"),
+                suppression_cache_sync(),
+            ),
+            (
+                ObjectTypeName::from("bounce_classification"),
+                bounce_classification(),
+            ),
+            (ObjectTypeName::from("preference_link"), preference_link()),
+            (
+                ObjectTypeName::from("preference_token_verification"),
+                preference_token_verification(),
+            ),
+            (ObjectTypeName::from("email_validation"), email_validation()),
+            (
+                ObjectTypeName::from("email_validation_checks"),
+                email_validation_checks(),
+            ),
+            (
+                ObjectTypeName::from("email_domain_checks"),
+                email_domain_checks(),
+            ),
+            (
+                ObjectTypeName::from("email_local_part_checks"),
+                email_local_part_checks(),
+            ),
+            (
+                ObjectTypeName::from("email_additional_checks"),
+                email_additional_checks(),
+            ),
+            (
+                ObjectTypeName::from("unsubscribe_group"),
+                unsubscribe_group(),
+            ),
+            (
+                ObjectTypeName::from("unsubscribe_group_reference"),
+                unsubscribe_group_reference(),
+            ),
+            (
+                ObjectTypeName::from("group_suppressions_response"),
+                group_suppressions_response(),
+            ),
         ]),
-        collections: vec![],
-        functions: vec![list_function_templates()],
-        procedures: vec![send_mail()],
+        collections: vec![
+            designs(),
+            verified_senders(),
+            suppression_collection(
+                BOUNCES_COLLECTION_NAME,
+                "The addresses that have bounced",
+                "bounce",
+            ),
+            suppression_collection(
+                BLOCKS_COLLECTION_NAME,
+                "The addresses whose mail has been blocked by the receiving server",
+                "block",
+            ),
+            suppression_collection(
+                SPAM_REPORTS_COLLECTION_NAME,
+                "The addresses that have marked mail as spam",
+                "spam_report",
+            ),
+            suppression_collection(
+                INVALID_EMAILS_COLLECTION_NAME,
+                "The addresses that are invalid or malformed",
+                "invalid_email",
+            ),
+            global_unsubscribes(),
+            unsubscribe_groups(),
+            email_stats_breakdown_collection(
+                MAILBOX_PROVIDER_STATS_COLLECTION_NAME,
+                "Email statistics broken down by the recipients' mailbox provider, such as Gmail or Outlook.com",
+                email_stats_args(),
+            ),
+            email_stats_breakdown_collection(
+                BROWSER_STATS_COLLECTION_NAME,
+                "Email statistics broken down by the browser recipients clicked links in",
+                email_stats_args(),
+            ),
+            email_stats_breakdown_collection(
+                DEVICE_STATS_COLLECTION_NAME,
+                "Email statistics broken down by the type of device recipients opened mail on, such as desktop or phone",
+                email_stats_args(),
+            ),
+            email_stats_breakdown_collection(
+                CLIENT_STATS_COLLECTION_NAME,
+                "Email statistics broken down by the type of client recipients opened mail in, such as webmail or desktop",
+                email_stats_args(),
+            ),
+            email_stats_breakdown_collection(
+                GEO_STATS_COLLECTION_NAME,
+                "Email statistics broken down by the country, or the state or province, recipients opened mail in",
+                geo_stats_args(),
+            ),
+            email_stats(),
+            messages(),
+            email_events(),
+            scheduled_sends(),
+        ],
+        functions: vec![
+            list_function_templates(),
+            get_contact_export(),
+            get_design(),
+            singlesend_stats(),
+            automation_stats(),
+            export_singlesend_stats(),
+            export_automation_stats(),
+            get_suppression_status(),
+            search_group_suppressions(),
+            validate_email(),
+            validate_emails(),
+            make_preference_link(),
+            classify_bounce(),
+            global_stats(),
+            get_message(),
+            get_delivery_status(),
+            category_stats(),
+            category_stats_sums(),
+            subuser_stats(),
+            subuser_stats_sums(),
+            verify_preference_token(),
+            verify_event_webhook(),
+            validate_batch_id(),
+        ],
+        procedures: vec![
+            send_mail(),
+            create_batch_id(),
+            scheduled_send_procedure(
+                PAUSE_SCHEDULED_SEND,
+                "Pauses the scheduled send of a batch, so that its mail is held until it is resumed. Mail still paused 72 hours after its send time is discarded. A cancelled batch cannot be paused; resume it first.",
+            ),
+            scheduled_send_procedure(
+                CANCEL_SCHEDULED_SEND,
+                "Cancels the scheduled send of a batch, so that its mail is discarded at its send time unless it is resumed before then.",
+            ),
+            resume_scheduled_send(),
+            import_contacts(),
+            export_contacts(),
+            duplicate_design(),
+            create_verified_sender(),
+            resend_sender_verification(),
+            delete_verified_sender(),
+            delete_suppressions(DELETE_BOUNCES, "bounces"),
+            delete_suppressions(DELETE_BLOCKS, "blocks"),
+            delete_suppressions(DELETE_SPAM_REPORTS, "spam reports"),
+            delete_suppressions(DELETE_INVALID_EMAILS, "invalid emails"),
+            add_global_unsubscribes(),
+            delete_global_unsubscribe(),
+            refresh_suppression_cache(),
+            create_unsubscribe_group(),
+            update_unsubscribe_group(),
+            delete_unsubscribe_group(),
+            add_group_suppressions(),
+            delete_group_suppression(),
+        ],
     }
 }
 
-const STRING_SCALAR_TYPE: ScalarType = ScalarType {
-    representation: Some(TypeRepresentation::String),
-    aggregate_functions: BTreeMap::new(),
-    comparison_operators: BTreeMap::new(),
-};
+fn string_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::String),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: ordered_comparison_operators("String"),
+    }
+}
 
-const INT_SCALAR_TYPE: ScalarType = ScalarType {
-    representation: Some(TypeRepresentation::Int32),
-    aggregate_functions: BTreeMap::new(),
-    comparison_operators: BTreeMap::new(),
-};
+fn date_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::Date),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: ordered_comparison_operators("Date"),
+    }
+}
 
-const BOOL_SCALAR_TYPE: ScalarType = ScalarType {
-    representation: Some(TypeRepresentation::Boolean),
-    aggregate_functions: BTreeMap::new(),
-    comparison_operators: BTreeMap::new(),
-};
+fn timestamp_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::TimestampTZ),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: ordered_comparison_operators("Timestamp"),
+    }
+}
+
+/// A JSON array or object scalar type that can only be filtered with the `_contains` operator,
+/// which takes an element of the array or a subset of the object's entries
+fn contains_scalar_type(argument_type: &str) -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::JSON),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: BTreeMap::from([(
+            ComparisonOperatorName::from(filter::CONTAINS_OPERATOR),
+            ComparisonOperatorDefinition::Custom {
+                argument_type: named(argument_type),
+            },
+        )]),
+    }
+}
+
+fn int_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::Int32),
+        aggregate_functions: numeric_aggregate_functions("Int"),
+        comparison_operators: ordered_comparison_operators("Int"),
+    }
+}
+
+fn float_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::Float64),
+        aggregate_functions: numeric_aggregate_functions("Float"),
+        comparison_operators: ordered_comparison_operators("Float"),
+    }
+}
+
+fn bool_scalar_type() -> ScalarType {
+    ScalarType {
+        representation: Some(TypeRepresentation::Boolean),
+        aggregate_functions: BTreeMap::new(),
+        comparison_operators: equality_comparison_operators("Bool"),
+    }
+}
+
+fn equality_comparison_operators(
+    scalar_type: &str,
+) -> BTreeMap<ComparisonOperatorName, ComparisonOperatorDefinition> {
+    BTreeMap::from([
+        (
+            ComparisonOperatorName::from(filter::EQUAL_OPERATOR),
+            ComparisonOperatorDefinition::Equal,
+        ),
+        (
+            ComparisonOperatorName::from(filter::IN_OPERATOR),
+            ComparisonOperatorDefinition::In,
+        ),
+        (
+            ComparisonOperatorName::from(filter::NOT_EQUAL_OPERATOR),
+            ComparisonOperatorDefinition::Custom {
+                argument_type: named(scalar_type),
+            },
+        ),
+    ])
+}
+
+/// The aggregate functions of a numeric scalar type. The average of any numeric column is a
+/// Float, and every function but sum is null over no rows.
+fn numeric_aggregate_functions(
+    scalar_type: &str,
+) -> BTreeMap<AggregateFunctionName, AggregateFunctionDefinition> {
+    BTreeMap::from([
+        (
+            AggregateFunctionName::from(aggregates::SUM_FUNCTION),
+            AggregateFunctionDefinition {
+                result_type: named(scalar_type),
+            },
+        ),
+        (
+            AggregateFunctionName::from(aggregates::AVG_FUNCTION),
+            AggregateFunctionDefinition {
+                result_type: nullable(named("Float")),
+            },
+        ),
+        (
+            AggregateFunctionName::from(aggregates::MIN_FUNCTION),
+            AggregateFunctionDefinition {
+                result_type: nullable(named(scalar_type)),
+            },
+        ),
+        (
+            AggregateFunctionName::from(aggregates::MAX_FUNCTION),
+            AggregateFunctionDefinition {
+                result_type: nullable(named(scalar_type)),
+            },
+        ),
+    ])
+}
+
+fn ordered_comparison_operators(
+    scalar_type: &str,
+) -> BTreeMap<ComparisonOperatorName, ComparisonOperatorDefinition> {
+    let mut operators = equality_comparison_operators(scalar_type);
+    operators.extend(
+        [
+            filter::GREATER_THAN_OPERATOR,
+            filter::GREATER_THAN_OR_EQUAL_OPERATOR,
+            filter::LESS_THAN_OPERATOR,
+            filter::LESS_THAN_OR_EQUAL_OPERATOR,
+        ]
+        .map(|operator| {
+            (
+                ComparisonOperatorName::from(operator),
+                ComparisonOperatorDefinition::Custom {
+                    argument_type: named(scalar_type),
+                },
+            )
+        }),
+    );
+    operators
+}
 
 fn list_template_item() -> ObjectType {
     ObjectType {
@@ -227,6 +604,41 @@ pub struct SendMailRequest {
     pub send_at: Option<u32>,
     pub batch_id: Option<String>,
     pub asm: Option<sendgrid_api::UnsubscriptionSettings>,
+    pub custom_args: Option<Vec<CustomArg>>,
+}
+
+/// Arguments to the send_mail procedure that control how the connector handles the send,
+/// rather than being passed on to SendGrid
+#[derive(Deserialize, Clone, Debug)]
+pub struct SendMailOptions {
+    pub verify_from_address: Option<bool>,
+    pub suppression_policy: Option<SuppressionPolicy>,
+    pub duplicate_recipients: Option<DuplicateRecipientRule>,
+    pub list_unsubscribe: Option<bool>,
+    pub auto_batch: Option<bool>,
+    pub tag_batch: Option<bool>,
+}
+
+/// Which field keeps an address that appears in more than one of a personalization's to, cc
+/// and bcc
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateRecipientRule {
+    /// to wins over cc, which wins over bcc
+    #[default]
+    KeepMostVisible,
+    /// bcc wins over cc, which wins over to
+    KeepMostPrivate,
+}
+
+/// What send_mail does with recipients that are on one of the account's suppression lists
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionPolicy {
+    /// Remove the suppressed recipients and send to the rest
+    Drop,
+    /// Reject the whole mail if any recipient is suppressed
+    Reject,
 }
 
 fn send_mail_request_args() -> BTreeMap<ArgumentName, ArgumentInfo> {
@@ -275,6 +687,34 @@ fn send_mail_request_args() -> BTreeMap<ArgumentName, ArgumentInfo> {
             argument_type: nullable(named("unsubscription_settings")),
             description: Some(String::from("An object allowing you to specify how to handle unsubscribes."))
         }),
+        (ArgumentName::from("auto_batch"), ArgumentInfo {
+            argument_type: nullable(named("Bool")),
+            description: Some(String::from("If true, a new batch ID is created and the mail is sent with it, so that a scheduled send can be paused or cancelled later. The batch ID is returned in the response. Cannot be combined with batch_id."))
+        }),
+        (ArgumentName::from("custom_args"), ArgumentInfo {
+            argument_type: nullable(array_of(named("custom_arg"))),
+            description: Some(String::from("Values that are attached to the mail's events and can be used to find it in the Email Activity feed and with get_delivery_status."))
+        }),
+        (ArgumentName::from("tag_batch"), ArgumentInfo {
+            argument_type: nullable(named("Bool")),
+            description: Some(String::from("If true and the mail is sent with a batch ID, from batch_id or auto_batch, a 'batch_id' custom argument holding the batch ID is added to the mail unless custom_args already has one, so that get_delivery_status can find the mail by batch ID."))
+        }),
+        (ArgumentName::from("verify_from_address"), ArgumentInfo {
+            argument_type: nullable(named("Bool")),
+            description: Some(String::from("If true, the mail is rejected before it is sent unless every 'From' address is a verified sender or belongs to an authenticated domain."))
+        }),
+        (ArgumentName::from("duplicate_recipients"), ArgumentInfo {
+            argument_type: nullable(named("String")),
+            description: Some(String::from("Recipient addresses are trimmed and their domains lowercased and punycode-encoded, and an address that appears more than once across a personalization's to, cc and bcc is kept only once. 'keep_most_visible' (the default) keeps it in to over cc over bcc; 'keep_most_private' keeps it in bcc over cc over to, except that the first 'to' recipient stays in to when every 'to' recipient also appears in cc or bcc. Addresses are compared ignoring case. Every change is reported in the response."))
+        }),
+        (ArgumentName::from("list_unsubscribe"), ArgumentInfo {
+            argument_type: nullable(named("Bool")),
+            description: Some(String::from("If true, List-Unsubscribe and List-Unsubscribe-Post (RFC 8058 one-click) headers are added to each personalization, pointing at a link to the configured preference center signed for the personalization's first 'to' recipient and the mail's unsubscribe group. Personalizations whose first 'to' recipient was redirected to SENDGRID_RECIPIENT_REDIRECT_TO get no headers."))
+        }),
+        (ArgumentName::from("suppression_policy"), ArgumentInfo {
+            argument_type: nullable(named("String")),
+            description: Some(String::from("If set, every to, cc and bcc address is checked against the bounces, blocks, spam reports, invalid emails and global unsubscribes lists before the mail is sent. 'drop' removes suppressed recipients and skips personalizations left without a 'to' recipient; 'reject' fails the send if any recipient is suppressed. Removed recipients are reported in the response."))
+        }),
     ])
 }
 
@@ -368,6 +808,28 @@ fn mail_address() -> ObjectType {
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomArg {
+    pub name: String,
+    pub value: String,
+}
+
+fn custom_arg() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A custom argument attached to a mail")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("name"),
+                object_field(named("String"), "The name of the custom argument"),
+            ),
+            (
+                FieldName::from("value"),
+                object_field(named("String"), "The value of the custom argument"),
+            ),
+        ]),
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct Header {
     pub name: String,
@@ -527,19 +989,165 @@ fn unsubscription_settings() -> ObjectType {
     }
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct SendMailResponse {
+    pub batch_id: Option<String>,
+    pub message_id: Option<String>,
+    pub sent: bool,
+    pub recipient_changes: Vec<RecipientChange>,
+    pub suppressed_recipients: Vec<SuppressedRecipient>,
+    pub skipped_personalizations: Vec<usize>,
+}
+
 fn send_mail_response() -> ObjectType {
     ObjectType {
         description: Some(String::from("The response from a mail send request.")),
-        fields: BTreeMap::from([(
-            FieldName::from("batch_id"),
-            ObjectField {
-                r#type: nullable(named("String")),
-                description: Some(String::from(
-                    "The batch ID used with the send mail request.",
-                )),
-                arguments: BTreeMap::new(),
-            },
-        )]),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("batch_id"),
+                object_field(
+                    nullable(named("String")),
+                    "The batch ID used with the send mail request, or the batch ID created for it with auto_batch.",
+                ),
+            ),
+            (
+                FieldName::from("message_id"),
+                object_field(
+                    nullable(named("String")),
+                    "The ID SendGrid assigned to the mail, if it was sent. Pass it to get_delivery_status to follow its delivery.",
+                ),
+            ),
+            (
+                FieldName::from("sent"),
+                object_field(
+                    named("Bool"),
+                    "Whether the mail was handed to SendGrid. This is false when the suppression policy removed every recipient.",
+                ),
+            ),
+            (
+                FieldName::from("recipient_changes"),
+                object_field(
+                    array_of(named("recipient_change")),
+                    "The changes made to the recipients when normalizing and de-duplicating them, and the recipients left out with skipped personalizations.",
+                ),
+            ),
+            (
+                FieldName::from("suppressed_recipients"),
+                object_field(
+                    array_of(named("suppressed_recipient")),
+                    "The recipients removed from the mail by the suppression policy.",
+                ),
+            ),
+            (
+                FieldName::from("skipped_personalizations"),
+                object_field(
+                    array_of(named("Int")),
+                    "The indexes of the personalizations that were not sent because the suppression policy removed all of their 'to' recipients. Their 'cc' and 'bcc' recipients are listed in recipient_changes.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecipientChange {
+    pub personalization: usize,
+    pub field: &'static str,
+    pub original: String,
+    pub email: Option<String>,
+    pub change: &'static str,
+    pub kept_in: Option<&'static str>,
+}
+
+fn recipient_change() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "A change made to a recipient when normalizing and de-duplicating recipients",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("personalization"),
+                object_field(
+                    named("Int"),
+                    "The index of the personalization the recipient is in.",
+                ),
+            ),
+            (
+                FieldName::from("field"),
+                object_field(
+                    named("String"),
+                    "Whether the recipient is a 'to', 'cc' or 'bcc' recipient.",
+                ),
+            ),
+            (
+                FieldName::from("original"),
+                object_field(named("String"), "The address as it was given."),
+            ),
+            (
+                FieldName::from("email"),
+                object_field(
+                    nullable(named("String")),
+                    "The address after normalization, or null if the recipient was removed.",
+                ),
+            ),
+            (
+                FieldName::from("change"),
+                object_field(
+                    named("String"),
+                    "One of 'normalized', 'removed_duplicate', 'redirected' (by the connector's recipient safety mode), or 'skipped' (because the suppression policy removed every 'to' recipient of its personalization).",
+                ),
+            ),
+            (
+                FieldName::from("kept_in"),
+                object_field(
+                    nullable(named("String")),
+                    "For a removed duplicate, the field the address was kept in.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SuppressedRecipient {
+    pub email: String,
+    pub personalization: usize,
+    pub field: &'static str,
+    pub lists: Vec<sendgrid_api::SuppressionList>,
+}
+
+fn suppressed_recipient() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "A recipient that was removed from a mail because it is suppressed",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("email"),
+                object_field(named("String"), "The address of the recipient."),
+            ),
+            (
+                FieldName::from("personalization"),
+                object_field(
+                    named("Int"),
+                    "The index of the personalization the recipient was removed from.",
+                ),
+            ),
+            (
+                FieldName::from("field"),
+                object_field(
+                    named("String"),
+                    "Whether the recipient was a 'to', 'cc' or 'bcc' recipient.",
+                ),
+            ),
+            (
+                FieldName::from("lists"),
+                object_field(
+                    array_of(named("String")),
+                    "The suppression lists the address is on: bounces, blocks, spam_reports, invalid_emails or global_unsubscribes.",
+                ),
+            ),
+        ]),
     }
 }
 
@@ -554,20 +1162,2875 @@ fn send_mail() -> ProcedureInfo {
     }
 }
 
-fn named(name: &str) -> Type {
-    Type::Named {
-        name: TypeName::from(name),
+#[derive(Deserialize, Clone, Debug)]
+pub struct ImportContactsArgs {
+    pub csv_content: Option<String>,
+    pub csv_path: Option<String>,
+    pub field_mappings: Vec<Option<String>>,
+    pub list_ids: Option<Vec<String>>,
+    pub poll_timeout_seconds: Option<u32>,
+}
+
+pub const IMPORT_CONTACTS: &str = "import_contacts";
+
+fn import_contacts() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(IMPORT_CONTACTS),
+        description: Some(String::from("Imports contacts from CSV content into Marketing Campaigns. The CSV is uploaded to SendGrid and the import job is polled until it finishes or the poll timeout elapses.")),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("csv_content"), argument(
+                nullable(named("String")),
+                "The CSV content to import. Either csv_content or csv_path must be provided.",
+            )),
+            (ArgumentName::from("csv_path"), argument(
+                nullable(named("String")),
+                "The path of a CSV file to import, relative to the connector's configuration directory. Either csv_content or csv_path must be provided.",
+            )),
+            (ArgumentName::from("field_mappings"), argument(
+                array_of(nullable(named("String"))),
+                "The IDs of the fields that each CSV column maps to, in column order. Use null to skip a column. Reserved fields use IDs such as _rf0_T (first_name) and _rf2_T (email).",
+            )),
+            (ArgumentName::from("list_ids"), argument(
+                nullable(array_of(named("String"))),
+                "The IDs of the lists the imported contacts will be added to.",
+            )),
+            (ArgumentName::from("poll_timeout_seconds"), argument(
+                nullable(named("Int")),
+                "How long to wait for the import job to finish before returning its current status. Defaults to 60 seconds.",
+            )),
+        ]),
+        result_type: named("contact_import_job"),
     }
 }
 
-fn nullable(underlying: Type) -> Type {
-    Type::Nullable {
-        underlying_type: Box::new(underlying),
+fn contact_import_job() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A contacts import job")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("id"),
+                object_field(named("String"), "The ID of the import job."),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    named("String"),
+                    "The status of the import job. One of pending, completed, errored or failed.",
+                ),
+            ),
+            (
+                FieldName::from("job_type"),
+                object_field(nullable(named("String")), "The type of the job."),
+            ),
+            (
+                FieldName::from("results"),
+                object_field(
+                    nullable(named("contact_import_results")),
+                    "The results of the import job, once it has been processed.",
+                ),
+            ),
+            (
+                FieldName::from("started_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the job was created.",
+                ),
+            ),
+            (
+                FieldName::from("finished_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the job finished.",
+                ),
+            ),
+        ]),
     }
 }
 
-fn array_of(element: Type) -> Type {
-    Type::Array {
-        element_type: Box::new(element),
+fn contact_import_results() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The results of a contacts import job")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("requested_count"),
+                object_field(
+                    nullable(named("Int")),
+                    "The number of contacts requested to be imported.",
+                ),
+            ),
+            (
+                FieldName::from("created_count"),
+                object_field(nullable(named("Int")), "The number of contacts created."),
+            ),
+            (
+                FieldName::from("updated_count"),
+                object_field(
+                    nullable(named("Int")),
+                    "The number of existing contacts updated.",
+                ),
+            ),
+            (
+                FieldName::from("deleted_count"),
+                object_field(nullable(named("Int")), "The number of contacts deleted."),
+            ),
+            (
+                FieldName::from("errored_count"),
+                object_field(
+                    nullable(named("Int")),
+                    "The number of contacts that could not be imported.",
+                ),
+            ),
+            (
+                FieldName::from("errors_url"),
+                object_field(
+                    nullable(named("String")),
+                    "A URL to download a file describing the contacts that errored.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExportContactsArgs {
+    pub list_ids: Option<Vec<String>>,
+    pub segment_ids: Option<Vec<String>>,
+    pub file_type: Option<String>,
+    pub max_file_size: Option<u32>,
+}
+
+pub const EXPORT_CONTACTS: &str = "export_contacts";
+
+fn export_contacts() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(EXPORT_CONTACTS),
+        description: Some(String::from("Starts an export of Marketing Campaigns contacts. Use the get_contact_export function to check on the export and retrieve its download URLs.")),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("list_ids"), argument(
+                nullable(array_of(named("String"))),
+                "The IDs of the lists to export. If neither list_ids nor segment_ids are provided, all contacts are exported.",
+            )),
+            (ArgumentName::from("segment_ids"), argument(
+                nullable(array_of(named("String"))),
+                "The IDs of the segments to export.",
+            )),
+            (ArgumentName::from("file_type"), argument(
+                nullable(named("String")),
+                "The file type of the export. Allowed values: csv, json. Defaults to csv.",
+            )),
+            (ArgumentName::from("max_file_size"), argument(
+                nullable(named("Int")),
+                "The maximum size of each exported file in MB. Larger exports are split into multiple files.",
+            )),
+        ]),
+        result_type: named("export_contacts_response"),
+    }
+}
+
+fn export_contacts_response() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "The response from starting a contacts export.",
+        )),
+        fields: BTreeMap::from([(
+            FieldName::from("id"),
+            object_field(named("String"), "The ID of the export job."),
+        )]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GetContactExportArgs {
+    pub id: String,
+}
+
+pub const GET_CONTACT_EXPORT_FUNCTION_NAME: &str = "get_contact_export";
+
+fn get_contact_export() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GET_CONTACT_EXPORT_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the status of a contacts export and, once it is ready, its download URLs",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("id"),
+            argument(named("String"), "The ID of the export job."),
+        )]),
+        result_type: named("contact_export_job"),
+    }
+}
+
+fn contact_export_job() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A contacts export job")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("id"),
+                object_field(named("String"), "The ID of the export job."),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    named("String"),
+                    "The status of the export job. One of pending, ready or failure.",
+                ),
+            ),
+            (
+                FieldName::from("created_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the export was started.",
+                ),
+            ),
+            (
+                FieldName::from("updated_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the export was last updated.",
+                ),
+            ),
+            (
+                FieldName::from("completed_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the export completed.",
+                ),
+            ),
+            (
+                FieldName::from("expires_at"),
+                object_field(
+                    nullable(named("String")),
+                    "The ISO8601 timestamp when the download URLs expire.",
+                ),
+            ),
+            (
+                FieldName::from("urls"),
+                object_field(
+                    nullable(array_of(named("String"))),
+                    "The URLs to download the exported files from, once the export is ready.",
+                ),
+            ),
+            (
+                FieldName::from("message"),
+                object_field(
+                    nullable(named("String")),
+                    "A message describing why the export failed, if it did.",
+                ),
+            ),
+        ]),
+    }
+}
+
+pub const DESIGNS_COLLECTION_NAME: &str = "designs";
+
+fn designs() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(DESIGNS_COLLECTION_NAME),
+        description: Some(String::from(
+            "The designs in the Marketing Campaigns design library",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("design_summary"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("design_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn design_summary() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A design in the design library")),
+        fields: design_summary_fields(),
+    }
+}
+
+fn design_summary_fields() -> BTreeMap<FieldName, ObjectField> {
+    BTreeMap::from([
+        (
+            FieldName::from("id"),
+            object_field(named("String"), "The ID of the design."),
+        ),
+        (
+            FieldName::from("name"),
+            object_field(named("String"), "The name of the design."),
+        ),
+        (
+            FieldName::from("generate_plain_content"),
+            object_field(
+                nullable(named("Bool")),
+                "If true, plain_content is always generated from html_content.",
+            ),
+        ),
+        (
+            FieldName::from("subject"),
+            object_field(nullable(named("String")), "The subject of the design."),
+        ),
+        (
+            FieldName::from("editor"),
+            object_field(
+                nullable(named("String")),
+                "The editor used to build the design. Allowed values: code, design.",
+            ),
+        ),
+        (
+            FieldName::from("thumbnail_url"),
+            object_field(
+                nullable(named("String")),
+                "A thumbnail preview of the design's html content.",
+            ),
+        ),
+        (
+            FieldName::from("categories"),
+            object_field(
+                nullable(array_of(named("String"))),
+                "The categories the design is tagged with.",
+            ),
+        ),
+        (
+            FieldName::from("created_at"),
+            object_field(
+                nullable(named("String")),
+                "The ISO8601 timestamp when the design was created.",
+            ),
+        ),
+        (
+            FieldName::from("updated_at"),
+            object_field(
+                nullable(named("String")),
+                "The ISO8601 timestamp when the design was last updated.",
+            ),
+        ),
+    ])
+}
+
+fn design() -> ObjectType {
+    let mut fields = design_summary_fields();
+    fields.extend([
+        (
+            FieldName::from("html_content"),
+            object_field(nullable(named("String")), "The HTML content of the design."),
+        ),
+        (
+            FieldName::from("plain_content"),
+            object_field(
+                nullable(named("String")),
+                "The plain text content of the design.",
+            ),
+        ),
+    ]);
+    ObjectType {
+        description: Some(String::from(
+            "A design in the design library, including its content",
+        )),
+        fields,
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GetDesignArgs {
+    pub id: String,
+}
+
+pub const GET_DESIGN_FUNCTION_NAME: &str = "get_design";
+
+fn get_design() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GET_DESIGN_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves a single design from the design library, including its content",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("id"),
+            argument(named("String"), "The ID of the design."),
+        )]),
+        result_type: named("design"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DuplicateDesignArgs {
+    pub id: String,
+    pub name: Option<String>,
+    pub editor: Option<String>,
+}
+
+pub const DUPLICATE_DESIGN: &str = "duplicate_design";
+
+fn duplicate_design() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(DUPLICATE_DESIGN),
+        description: Some(String::from("Duplicates a design in the design library")),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("id"), argument(
+                named("String"),
+                "The ID of the design to duplicate.",
+            )),
+            (ArgumentName::from("name"), argument(
+                nullable(named("String")),
+                "The name of the duplicated design. Defaults to 'Duplicate: ' followed by the original design's name.",
+            )),
+            (ArgumentName::from("editor"), argument(
+                nullable(named("String")),
+                "The editor used to build the duplicated design. Allowed values: code, design. Defaults to the original design's editor.",
+            )),
+        ]),
+        result_type: named("design"),
+    }
+}
+
+pub const VERIFIED_SENDERS_COLLECTION_NAME: &str = "verified_senders";
+
+fn verified_senders() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(VERIFIED_SENDERS_COLLECTION_NAME),
+        description: Some(String::from(
+            "The sender identities that have been set up for sending mail",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("verified_sender"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("verified_sender_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn verified_sender() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A sender identity")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("id"),
+                object_field(named("Int"), "The ID of the sender identity."),
+            ),
+            (
+                FieldName::from("nickname"),
+                object_field(
+                    named("String"),
+                    "A nickname for the sender identity. Not used for sending.",
+                ),
+            ),
+            (
+                FieldName::from("from_email"),
+                object_field(named("String"), "The email address the mail is sent from."),
+            ),
+            (
+                FieldName::from("from_name"),
+                object_field(
+                    nullable(named("String")),
+                    "The name appended to the from email field.",
+                ),
+            ),
+            (
+                FieldName::from("reply_to"),
+                object_field(named("String"), "The email address replies are sent to."),
+            ),
+            (
+                FieldName::from("reply_to_name"),
+                object_field(
+                    nullable(named("String")),
+                    "The name appended to the reply to email field.",
+                ),
+            ),
+            (
+                FieldName::from("address"),
+                object_field(
+                    nullable(named("String")),
+                    "The physical address of the sender identity.",
+                ),
+            ),
+            (
+                FieldName::from("address2"),
+                object_field(
+                    nullable(named("String")),
+                    "Additional sender identity address information.",
+                ),
+            ),
+            (
+                FieldName::from("city"),
+                object_field(
+                    nullable(named("String")),
+                    "The city of the sender identity.",
+                ),
+            ),
+            (
+                FieldName::from("state"),
+                object_field(
+                    nullable(named("String")),
+                    "The state of the sender identity.",
+                ),
+            ),
+            (
+                FieldName::from("zip"),
+                object_field(
+                    nullable(named("String")),
+                    "The zipcode of the sender identity.",
+                ),
+            ),
+            (
+                FieldName::from("country"),
+                object_field(
+                    nullable(named("String")),
+                    "The country of the sender identity.",
+                ),
+            ),
+            (
+                FieldName::from("verified"),
+                object_field(
+                    named("Bool"),
+                    "Whether the sender identity has been verified.",
+                ),
+            ),
+            (
+                FieldName::from("locked"),
+                object_field(
+                    named("Bool"),
+                    "Whether the sender identity is locked, preventing it from being changed.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn verified_sender_reference() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A reference to a sender identity")),
+        fields: BTreeMap::from([(
+            FieldName::from("id"),
+            object_field(named("Int"), "The ID of the sender identity."),
+        )]),
+    }
+}
+
+pub const CREATE_VERIFIED_SENDER: &str = "create_verified_sender";
+
+fn create_verified_sender() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(CREATE_VERIFIED_SENDER),
+        description: Some(String::from("Creates a sender identity. A verification email is sent to the from_email address, and the sender cannot be used until it is verified.")),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("nickname"), argument(named("String"), "A nickname for the sender identity. Not used for sending.")),
+            (ArgumentName::from("from_email"), argument(named("String"), "The email address the mail is sent from.")),
+            (ArgumentName::from("from_name"), argument(nullable(named("String")), "The name appended to the from email field.")),
+            (ArgumentName::from("reply_to"), argument(named("String"), "The email address replies are sent to.")),
+            (ArgumentName::from("reply_to_name"), argument(nullable(named("String")), "The name appended to the reply to email field.")),
+            (ArgumentName::from("address"), argument(named("String"), "The physical address of the sender identity.")),
+            (ArgumentName::from("address2"), argument(nullable(named("String")), "Additional sender identity address information.")),
+            (ArgumentName::from("city"), argument(named("String"), "The city of the sender identity.")),
+            (ArgumentName::from("state"), argument(nullable(named("String")), "The state of the sender identity.")),
+            (ArgumentName::from("zip"), argument(nullable(named("String")), "The zipcode of the sender identity.")),
+            (ArgumentName::from("country"), argument(named("String"), "The country of the sender identity.")),
+        ]),
+        result_type: named("verified_sender"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VerifiedSenderIdArgs {
+    pub id: u32,
+}
+
+pub const RESEND_SENDER_VERIFICATION: &str = "resend_sender_verification";
+
+fn resend_sender_verification() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(RESEND_SENDER_VERIFICATION),
+        description: Some(String::from(
+            "Resends the verification email for a sender identity",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("id"),
+            argument(named("Int"), "The ID of the sender identity."),
+        )]),
+        result_type: named("verified_sender_reference"),
+    }
+}
+
+pub const DELETE_VERIFIED_SENDER: &str = "delete_verified_sender";
+
+fn delete_verified_sender() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(DELETE_VERIFIED_SENDER),
+        description: Some(String::from("Deletes a sender identity")),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("id"),
+            argument(named("Int"), "The ID of the sender identity."),
+        )]),
+        result_type: named("verified_sender_reference"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MarketingStatsArgs {
+    pub id: String,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub timezone: Option<String>,
+    pub aggregated_by: Option<String>,
+    pub group_by: Option<Vec<String>>,
+}
+
+fn marketing_stats_args(
+    id_description: &str,
+    group_by_description: &str,
+) -> BTreeMap<ArgumentName, ArgumentInfo> {
+    BTreeMap::from([
+        (ArgumentName::from("id"), argument(named("String"), id_description)),
+        (ArgumentName::from("start_date"), argument(nullable(named("String")), "The first date to retrieve statistics for, in YYYY-MM-DD format.")),
+        (ArgumentName::from("end_date"), argument(nullable(named("String")), "The last date to retrieve statistics for, in YYYY-MM-DD format. Defaults to today.")),
+        (ArgumentName::from("timezone"), argument(nullable(named("String")), "The IANA timezone the dates are interpreted in, for example America/Chicago. Defaults to UTC.")),
+        (ArgumentName::from("aggregated_by"), argument(nullable(named("String")), "How the statistics are aggregated over the date range. Allowed values: day, total. Defaults to total.")),
+        (ArgumentName::from("group_by"), argument(nullable(array_of(named("String"))), group_by_description)),
+    ])
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MarketingStatsRow {
+    pub id: String,
+    pub ab_variation: Option<String>,
+    pub ab_phase: Option<String>,
+    pub step_id: Option<String>,
+    pub aggregation: String,
+    #[serde(flatten)]
+    pub stats: sendgrid_api::MarketingStats,
+}
+
+impl From<sendgrid_api::MarketingStatsResult> for MarketingStatsRow {
+    fn from(result: sendgrid_api::MarketingStatsResult) -> Self {
+        MarketingStatsRow {
+            id: result.id,
+            ab_variation: result.ab_variation,
+            ab_phase: result.ab_phase,
+            step_id: result.step_id,
+            aggregation: result.aggregation,
+            stats: result.stats,
+        }
+    }
+}
+
+fn marketing_stats_row() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("Statistics for a Single Send or automation")),
+        fields: BTreeMap::from([
+            (FieldName::from("id"), object_field(named("String"), "The ID of the Single Send or automation.")),
+            (FieldName::from("ab_variation"), object_field(nullable(named("String")), "The A/B test variation the statistics are for, when grouped by ab_variation.")),
+            (FieldName::from("ab_phase"), object_field(nullable(named("String")), "The A/B test phase the statistics are for, when grouped by ab_phase. One of send, test or all.")),
+            (FieldName::from("step_id"), object_field(nullable(named("String")), "The automation step the statistics are for, when grouped by step_id.")),
+            (FieldName::from("aggregation"), object_field(named("String"), "The date the statistics are aggregated over, or 'total'.")),
+            (FieldName::from("requests"), object_field(named("Int"), "The number of emails requested to be sent.")),
+            (FieldName::from("delivered"), object_field(named("Int"), "The number of emails delivered.")),
+            (FieldName::from("opens"), object_field(named("Int"), "The total number of times the emails were opened.")),
+            (FieldName::from("unique_opens"), object_field(named("Int"), "The number of unique recipients who opened the emails.")),
+            (FieldName::from("clicks"), object_field(named("Int"), "The total number of links clicked in the emails.")),
+            (FieldName::from("unique_clicks"), object_field(named("Int"), "The number of unique recipients who clicked links in the emails.")),
+            (FieldName::from("bounces"), object_field(named("Int"), "The number of emails that bounced.")),
+            (FieldName::from("bounce_drops"), object_field(named("Int"), "The number of emails dropped because the recipient previously bounced.")),
+            (FieldName::from("invalid_emails"), object_field(named("Int"), "The number of recipients with malformed addresses.")),
+            (FieldName::from("unsubscribes"), object_field(named("Int"), "The number of recipients who unsubscribed.")),
+            (FieldName::from("spam_reports"), object_field(named("Int"), "The number of recipients who marked the emails as spam.")),
+            (FieldName::from("spam_report_drops"), object_field(named("Int"), "The number of emails dropped because the recipient previously reported spam.")),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailStatsArgs {
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub aggregated_by: Option<String>,
+}
+
+fn email_stats_args() -> BTreeMap<ArgumentName, ArgumentInfo> {
+    BTreeMap::from([
+        (ArgumentName::from("start_date"), argument(named("Date"), "The first date to retrieve statistics for, in YYYY-MM-DD format.")),
+        (ArgumentName::from("end_date"), argument(nullable(named("Date")), "The last date to retrieve statistics for, in YYYY-MM-DD format. Defaults to today.")),
+        (ArgumentName::from("aggregated_by"), argument(nullable(named("String")), "How the statistics are grouped over the date range. Allowed values: day, week, month. Defaults to day.")),
+    ])
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EmailStatsRow {
+    pub date: String,
+    #[serde(flatten)]
+    pub metrics: sendgrid_api::EmailMetrics,
+}
+
+impl From<sendgrid_api::EmailStatsDay> for EmailStatsRow {
+    fn from(day: sendgrid_api::EmailStatsDay) -> Self {
+        EmailStatsRow {
+            date: day.date,
+            metrics: day
+                .stats
+                .into_iter()
+                .next()
+                .map(|entry| entry.metrics)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The fields for the metrics SendGrid reports in its email statistics
+fn email_metrics_fields() -> Vec<(FieldName, ObjectField)> {
+    vec![
+        (
+            FieldName::from("requests"),
+            object_field(named("Int"), "The number of emails requested to be sent."),
+        ),
+        (
+            FieldName::from("processed"),
+            object_field(named("Int"), "The number of emails processed by SendGrid."),
+        ),
+        (
+            FieldName::from("delivered"),
+            object_field(
+                named("Int"),
+                "The number of emails accepted by the receiving server.",
+            ),
+        ),
+        (
+            FieldName::from("deferred"),
+            object_field(
+                named("Int"),
+                "The number of emails temporarily rejected by the receiving server.",
+            ),
+        ),
+        (
+            FieldName::from("opens"),
+            object_field(
+                named("Int"),
+                "The total number of times the emails were opened.",
+            ),
+        ),
+        (
+            FieldName::from("unique_opens"),
+            object_field(
+                named("Int"),
+                "The number of unique recipients who opened the emails.",
+            ),
+        ),
+        (
+            FieldName::from("clicks"),
+            object_field(
+                named("Int"),
+                "The total number of links clicked in the emails.",
+            ),
+        ),
+        (
+            FieldName::from("unique_clicks"),
+            object_field(
+                named("Int"),
+                "The number of unique recipients who clicked links in the emails.",
+            ),
+        ),
+        (
+            FieldName::from("bounces"),
+            object_field(named("Int"), "The number of emails that bounced."),
+        ),
+        (
+            FieldName::from("bounce_drops"),
+            object_field(
+                named("Int"),
+                "The number of emails dropped because the recipient previously bounced.",
+            ),
+        ),
+        (
+            FieldName::from("blocks"),
+            object_field(
+                named("Int"),
+                "The number of emails blocked by the receiving server.",
+            ),
+        ),
+        (
+            FieldName::from("invalid_emails"),
+            object_field(
+                named("Int"),
+                "The number of recipients with malformed addresses.",
+            ),
+        ),
+        (
+            FieldName::from("spam_reports"),
+            object_field(
+                named("Int"),
+                "The number of recipients who marked the emails as spam.",
+            ),
+        ),
+        (
+            FieldName::from("spam_report_drops"),
+            object_field(
+                named("Int"),
+                "The number of emails dropped because the recipient previously reported spam.",
+            ),
+        ),
+        (
+            FieldName::from("unsubscribes"),
+            object_field(named("Int"), "The number of recipients who unsubscribed."),
+        ),
+        (
+            FieldName::from("unsubscribe_drops"),
+            object_field(
+                named("Int"),
+                "The number of emails dropped because the recipient previously unsubscribed.",
+            ),
+        ),
+    ]
+}
+
+fn email_stats_row() -> ObjectType {
+    let mut fields = BTreeMap::from_iter(email_metrics_fields());
+    fields.insert(
+        FieldName::from("date"),
+        object_field(
+            named("Date"),
+            "The first date of the period the statistics are for.",
+        ),
+    );
+    ObjectType {
+        description: Some(String::from("Email statistics for a period")),
+        fields,
+    }
+}
+
+pub const GLOBAL_STATS_FUNCTION_NAME: &str = "global_stats";
+
+fn global_stats() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GLOBAL_STATS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the email statistics of the whole account, per day, week or month",
+        )),
+        arguments: email_stats_args(),
+        result_type: array_of(named("email_stats_row")),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CategoryStatsArgs {
+    pub categories: Vec<String>,
+    #[serde(flatten)]
+    pub stats: EmailStatsArgs,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SubuserStatsArgs {
+    pub subusers: Vec<String>,
+    #[serde(flatten)]
+    pub stats: EmailStatsArgs,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailStatsSumsArgs {
+    #[serde(flatten)]
+    pub stats: EmailStatsArgs,
+    pub sort_by_metric: Option<String>,
+    pub sort_by_direction: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+fn email_stats_sums_args() -> BTreeMap<ArgumentName, ArgumentInfo> {
+    let mut arguments = email_stats_args();
+    arguments.extend([
+        (ArgumentName::from("sort_by_metric"), argument(nullable(named("String")), "The metric to sort the results by, such as delivered or opens. Defaults to delivered.")),
+        (ArgumentName::from("sort_by_direction"), argument(nullable(named("String")), "The direction to sort the results in. Allowed values: asc, desc. Defaults to desc.")),
+        (ArgumentName::from("limit"), argument(nullable(named("Int")), "The maximum number of results to return. Defaults to 5.")),
+        (ArgumentName::from("offset"), argument(nullable(named("Int")), "The number of results to skip. Defaults to 0.")),
+    ]);
+    arguments
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EmailStatsBreakdownRow {
+    pub date: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub metrics: sendgrid_api::EmailMetrics,
+}
+
+impl EmailStatsBreakdownRow {
+    /// A row for each value of the breakdown in each period
+    pub fn from_days(days: Vec<sendgrid_api::EmailStatsDay>) -> Vec<EmailStatsBreakdownRow> {
+        days.into_iter()
+            .flat_map(|day| {
+                let date = day.date;
+                day.stats
+                    .into_iter()
+                    .map(move |entry| EmailStatsBreakdownRow {
+                        date: date.clone(),
+                        name: entry.name,
+                        metrics: entry.metrics,
+                    })
+            })
+            .collect()
+    }
+}
+
+fn email_stats_breakdown_row() -> ObjectType {
+    let mut fields = BTreeMap::from_iter(email_metrics_fields());
+    fields.extend([
+        (
+            FieldName::from("date"),
+            object_field(
+                named("Date"),
+                "The first date of the period the statistics are for.",
+            ),
+        ),
+        (
+            FieldName::from("name"),
+            object_field(
+                nullable(named("String")),
+                "The category, subuser, mailbox provider, browser, device type, client type or region the statistics are for.",
+            ),
+        ),
+    ]);
+    ObjectType {
+        description: Some(String::from(
+            "Email statistics for one value of a breakdown, such as a category or mailbox provider, over a period",
+        )),
+        fields,
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GeoStatsArgs {
+    pub country: Option<String>,
+    #[serde(flatten)]
+    pub stats: EmailStatsArgs,
+}
+
+pub const MAILBOX_PROVIDER_STATS_COLLECTION_NAME: &str = "mailbox_provider_stats";
+pub const BROWSER_STATS_COLLECTION_NAME: &str = "browser_stats";
+pub const DEVICE_STATS_COLLECTION_NAME: &str = "device_stats";
+pub const CLIENT_STATS_COLLECTION_NAME: &str = "client_stats";
+pub const GEO_STATS_COLLECTION_NAME: &str = "geo_stats";
+
+/// A collection of email statistics broken down by a dimension, with a row for each value of the
+/// dimension (in the `name` column) in each period
+fn email_stats_breakdown_collection(
+    name: &str,
+    description: &str,
+    arguments: BTreeMap<ArgumentName, ArgumentInfo>,
+) -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(name),
+        description: Some(description.to_owned()),
+        arguments,
+        collection_type: ObjectTypeName::from("email_stats_breakdown_row"),
+        uniqueness_constraints: BTreeMap::from([(
+            format!("{name}_date_name"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("date"), FieldName::from("name")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn geo_stats_args() -> BTreeMap<ArgumentName, ArgumentInfo> {
+    let mut arguments = email_stats_args();
+    arguments.insert(
+        ArgumentName::from("country"),
+        argument(nullable(named("String")), "Break the statistics down by the states or provinces of a country instead of by country. Allowed values: US, CA."),
+    );
+    arguments
+}
+
+/// What the rows of the email_stats collection are broken down by, besides the date
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailStatsDimension {
+    Category,
+    Subuser,
+    MailboxProvider,
+}
+
+impl EmailStatsDimension {
+    pub fn breakdown(self) -> sendgrid_api::EmailStatsBreakdown {
+        match self {
+            EmailStatsDimension::Category => sendgrid_api::EmailStatsBreakdown::Categories,
+            EmailStatsDimension::Subuser => sendgrid_api::EmailStatsBreakdown::Subusers,
+            EmailStatsDimension::MailboxProvider => {
+                sendgrid_api::EmailStatsBreakdown::MailboxProviders
+            }
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EmailStatsDimension::Category => "category",
+            EmailStatsDimension::Subuser => "subuser",
+            EmailStatsDimension::MailboxProvider => "mailbox_provider",
+        }
+    }
+}
+
+/// The columns the rows of the email_stats collection can be grouped by
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailStatsGroupColumn {
+    Date,
+    Name,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailStatsCollectionArgs {
+    pub dimension: Option<EmailStatsDimension>,
+    pub names: Option<Vec<String>>,
+    pub group_by: Option<Vec<EmailStatsGroupColumn>>,
+    #[serde(flatten)]
+    pub stats: EmailStatsArgs,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EmailStatsSeriesRow {
+    pub date: Option<String>,
+    pub dimension: Option<&'static str>,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub metrics: sendgrid_api::EmailMetrics,
+    pub delivery_rate: Option<f64>,
+    pub open_rate: Option<f64>,
+    pub click_rate: Option<f64>,
+    pub bounce_rate: Option<f64>,
+}
+
+impl EmailStatsSeriesRow {
+    pub fn new(
+        date: Option<String>,
+        dimension: Option<EmailStatsDimension>,
+        name: Option<String>,
+        metrics: sendgrid_api::EmailMetrics,
+    ) -> EmailStatsSeriesRow {
+        let rate =
+            |count: u32, total: u32| (total > 0).then(|| f64::from(count) / f64::from(total));
+        EmailStatsSeriesRow {
+            date,
+            dimension: dimension.map(EmailStatsDimension::as_str),
+            name,
+            delivery_rate: rate(metrics.delivered, metrics.requests),
+            open_rate: rate(metrics.unique_opens, metrics.delivered),
+            click_rate: rate(metrics.unique_clicks, metrics.delivered),
+            bounce_rate: rate(metrics.bounces, metrics.requests),
+            metrics,
+        }
+    }
+}
+
+pub const EMAIL_STATS_COLLECTION_NAME: &str = "email_stats";
+
+fn email_stats() -> CollectionInfo {
+    let mut arguments = email_stats_args();
+    arguments.extend([
+        (ArgumentName::from("dimension"), argument(nullable(named("String")), "What to break the statistics down by besides the date. Allowed values: category, subuser, mailbox_provider. Defaults to the statistics of the whole account.")),
+        (ArgumentName::from("names"), argument(nullable(array_of(named("String"))), "The categories, subusers or mailbox providers to retrieve statistics for. Categories and subusers must be given, either here or with an _eq or _in filter on name; up to 10 can be requested.")),
+        (ArgumentName::from("group_by"), argument(nullable(array_of(named("String"))), "The columns to group the rows by, summing the metrics of each group. Allowed values: date, name. Columns that are not grouped by are null and cannot be filtered on. Defaults to no grouping.")),
+    ]);
+    CollectionInfo {
+        name: CollectionName::from(EMAIL_STATS_COLLECTION_NAME),
+        description: Some(String::from(
+            "Email statistics as a time series, optionally broken down by category, subuser or mailbox provider and grouped in the connector. Supports the sum, avg, min and max aggregates and counts.",
+        )),
+        arguments,
+        collection_type: ObjectTypeName::from("email_stats_series_row"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("email_stats_date_dimension_name"),
+            UniquenessConstraint {
+                unique_columns: vec![
+                    FieldName::from("date"),
+                    FieldName::from("dimension"),
+                    FieldName::from("name"),
+                ],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn email_stats_series_row() -> ObjectType {
+    let mut fields = BTreeMap::from_iter(email_metrics_fields());
+    fields.extend([
+        (FieldName::from("date"), object_field(nullable(named("Date")), "The first date of the period the statistics are for. Null when the rows are not grouped by date.")),
+        (FieldName::from("dimension"), object_field(nullable(named("String")), "What the statistics are broken down by: category, subuser or mailbox_provider. Null for the statistics of the whole account.")),
+        (FieldName::from("name"), object_field(nullable(named("String")), "The category, subuser or mailbox provider the statistics are for. Null when the rows are not broken down or not grouped by name.")),
+        (FieldName::from("delivery_rate"), object_field(nullable(named("Float")), "The fraction of requested emails that were delivered.")),
+        (FieldName::from("open_rate"), object_field(nullable(named("Float")), "The fraction of delivered emails that were opened, counting unique opens.")),
+        (FieldName::from("click_rate"), object_field(nullable(named("Float")), "The fraction of delivered emails with a link clicked, counting unique clicks.")),
+        (FieldName::from("bounce_rate"), object_field(nullable(named("Float")), "The fraction of requested emails that bounced.")),
+    ]);
+    ObjectType {
+        description: Some(String::from("Email statistics for a period, category, subuser or mailbox provider, or a group of them")),
+        fields,
+    }
+}
+
+pub const CATEGORY_STATS_FUNCTION_NAME: &str = "category_stats";
+
+fn category_stats() -> FunctionInfo {
+    let mut arguments = email_stats_args();
+    arguments.insert(
+        ArgumentName::from("categories"),
+        argument(
+            array_of(named("String")),
+            "The categories to retrieve statistics for. Up to 10 categories can be requested.",
+        ),
+    );
+    FunctionInfo {
+        name: FunctionName::from(CATEGORY_STATS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the email statistics of the given categories, per day, week or month",
+        )),
+        arguments,
+        result_type: array_of(named("email_stats_breakdown_row")),
+    }
+}
+
+pub const CATEGORY_STATS_SUMS_FUNCTION_NAME: &str = "category_stats_sums";
+
+fn category_stats_sums() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(CATEGORY_STATS_SUMS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the total email statistics of every category over a date range",
+        )),
+        arguments: email_stats_sums_args(),
+        result_type: array_of(named("email_stats_breakdown_row")),
+    }
+}
+
+pub const SUBUSER_STATS_FUNCTION_NAME: &str = "subuser_stats";
+
+fn subuser_stats() -> FunctionInfo {
+    let mut arguments = email_stats_args();
+    arguments.insert(
+        ArgumentName::from("subusers"),
+        argument(array_of(named("String")), "The usernames of the subusers to retrieve statistics for. Up to 10 subusers can be requested."),
+    );
+    FunctionInfo {
+        name: FunctionName::from(SUBUSER_STATS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the email statistics of the given subusers, per day, week or month",
+        )),
+        arguments,
+        result_type: array_of(named("email_stats_breakdown_row")),
+    }
+}
+
+pub const SUBUSER_STATS_SUMS_FUNCTION_NAME: &str = "subuser_stats_sums";
+
+fn subuser_stats_sums() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(SUBUSER_STATS_SUMS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the total email statistics of every subuser over a date range",
+        )),
+        arguments: email_stats_sums_args(),
+        result_type: array_of(named("email_stats_breakdown_row")),
+    }
+}
+
+pub const SINGLESEND_STATS_FUNCTION_NAME: &str = "singlesend_stats";
+
+fn singlesend_stats() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(SINGLESEND_STATS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the statistics of a Single Send",
+        )),
+        arguments: marketing_stats_args(
+            "The ID of the Single Send.",
+            "Splits the statistics into rows per A/B test variation or phase. Allowed values: ab_variation, ab_phase.",
+        ),
+        result_type: array_of(named("marketing_stats_row")),
+    }
+}
+
+pub const AUTOMATION_STATS_FUNCTION_NAME: &str = "automation_stats";
+
+fn automation_stats() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(AUTOMATION_STATS_FUNCTION_NAME),
+        description: Some(String::from("Retrieves the statistics of an automation")),
+        arguments: marketing_stats_args(
+            "The ID of the automation.",
+            "Splits the statistics into rows per automation step. Allowed values: step_id.",
+        ),
+        result_type: array_of(named("marketing_stats_row")),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExportMarketingStatsArgs {
+    pub ids: Vec<String>,
+    pub timezone: Option<String>,
+}
+
+fn export_marketing_stats_args(ids_description: &str) -> BTreeMap<ArgumentName, ArgumentInfo> {
+    BTreeMap::from([
+        (ArgumentName::from("ids"), argument(array_of(named("String")), ids_description)),
+        (ArgumentName::from("timezone"), argument(nullable(named("String")), "The IANA timezone the dates are interpreted in, for example America/Chicago. Defaults to UTC.")),
+    ])
+}
+
+pub const EXPORT_SINGLESEND_STATS_FUNCTION_NAME: &str = "export_singlesend_stats";
+
+fn export_singlesend_stats() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(EXPORT_SINGLESEND_STATS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Exports the statistics of Single Sends as CSV",
+        )),
+        arguments: export_marketing_stats_args(
+            "The IDs of the Single Sends to export statistics for.",
+        ),
+        result_type: named("String"),
+    }
+}
+
+pub const EXPORT_AUTOMATION_STATS_FUNCTION_NAME: &str = "export_automation_stats";
+
+fn export_automation_stats() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(EXPORT_AUTOMATION_STATS_FUNCTION_NAME),
+        description: Some(String::from("Exports the statistics of automations as CSV")),
+        arguments: export_marketing_stats_args(
+            "The IDs of the automations to export statistics for.",
+        ),
+        result_type: named("String"),
+    }
+}
+
+pub const BOUNCES_COLLECTION_NAME: &str = "bounces";
+pub const BLOCKS_COLLECTION_NAME: &str = "blocks";
+pub const SPAM_REPORTS_COLLECTION_NAME: &str = "spam_reports";
+pub const INVALID_EMAILS_COLLECTION_NAME: &str = "invalid_emails";
+
+fn suppression_collection(name: &str, description: &str, collection_type: &str) -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(name),
+        description: Some(format!("{description}. Filtering on created is applied as a date range by SendGrid, and filtering on email with _eq looks up a single address.")),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from(collection_type),
+        uniqueness_constraints: BTreeMap::from([(
+            format!("{name}_email"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("email")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn suppression_email_field() -> (FieldName, ObjectField) {
+    (
+        FieldName::from("email"),
+        object_field(named("String"), "The suppressed email address."),
+    )
+}
+
+fn suppression_created_field() -> (FieldName, ObjectField) {
+    (
+        FieldName::from("created"),
+        object_field(
+            named("Int"),
+            "The unix timestamp when the address was added to the list.",
+        ),
+    )
+}
+
+fn bounce() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An address that has bounced")),
+        fields: BTreeMap::from([
+            suppression_email_field(),
+            suppression_created_field(),
+            (
+                FieldName::from("reason"),
+                object_field(
+                    nullable(named("String")),
+                    "The reason the receiving server gave for the bounce.",
+                ),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    nullable(named("String")),
+                    "The enhanced SMTP status code of the bounce.",
+                ),
+            ),
+            (
+                FieldName::from("classification"),
+                object_field(
+                    named("bounce_classification"),
+                    "The category of the bounce, derived from its reason and status code.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn block() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An address whose mail has been blocked")),
+        fields: BTreeMap::from([
+            suppression_email_field(),
+            suppression_created_field(),
+            (
+                FieldName::from("reason"),
+                object_field(
+                    nullable(named("String")),
+                    "The reason the receiving server gave for the block.",
+                ),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    nullable(named("String")),
+                    "The enhanced SMTP status code of the block.",
+                ),
+            ),
+            (
+                FieldName::from("classification"),
+                object_field(
+                    named("bounce_classification"),
+                    "The category of the block, derived from its reason and status code.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn spam_report() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An address that has marked mail as spam")),
+        fields: BTreeMap::from([
+            suppression_email_field(),
+            suppression_created_field(),
+            (
+                FieldName::from("ip"),
+                object_field(
+                    nullable(named("String")),
+                    "The IP address the reported mail was sent from.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn invalid_email() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An address that is invalid or malformed")),
+        fields: BTreeMap::from([
+            suppression_email_field(),
+            suppression_created_field(),
+            (
+                FieldName::from("reason"),
+                object_field(
+                    nullable(named("String")),
+                    "The reason the address was marked as invalid.",
+                ),
+            ),
+        ]),
+    }
+}
+
+/// A bounce or block, with the classification of its reason
+#[derive(Serialize, Clone, Debug)]
+pub struct ClassifiedSuppression {
+    #[serde(flatten)]
+    pub suppression: sendgrid_api::Suppression,
+    pub classification: BounceClassification,
+}
+
+impl From<sendgrid_api::Suppression> for ClassifiedSuppression {
+    fn from(suppression: sendgrid_api::Suppression) -> Self {
+        let classification = bounce_classifier::classify(
+            suppression.reason.as_deref().unwrap_or_default(),
+            suppression.status.as_deref(),
+        );
+        ClassifiedSuppression {
+            suppression,
+            classification,
+        }
+    }
+}
+
+fn bounce_classification() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The category of a bounce or block reason")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("severity"),
+                object_field(
+                    named("String"),
+                    "'hard' if the address should not be mailed again, 'soft' if the address is fine but this delivery failed, or 'unknown'.",
+                ),
+            ),
+            (
+                FieldName::from("category"),
+                object_field(
+                    named("String"),
+                    "One of invalid_mailbox, invalid_domain, mailbox_disabled, mailbox_full, message_too_large, policy, reputation, spam_content, authentication, rate_limited, temporary_failure or unknown.",
+                ),
+            ),
+            (
+                FieldName::from("enhanced_status_code"),
+                object_field(
+                    nullable(named("String")),
+                    "The enhanced SMTP status code (RFC 3463) found in the status or reason, such as 5.1.1.",
+                ),
+            ),
+            (
+                FieldName::from("smtp_code"),
+                object_field(
+                    nullable(named("Int")),
+                    "The SMTP reply code found in the reason, such as 550.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClassifyBounceArgs {
+    pub reason: String,
+    pub status: Option<String>,
+}
+
+pub const CLASSIFY_BOUNCE_FUNCTION_NAME: &str = "classify_bounce";
+
+fn classify_bounce() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(CLASSIFY_BOUNCE_FUNCTION_NAME),
+        description: Some(String::from(
+            "Classifies the reason a receiving server gave for a bounce or block, using its SMTP status codes and wording",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("reason"),
+                argument(named("String"), "The bounce or block reason."),
+            ),
+            (
+                ArgumentName::from("status"),
+                argument(
+                    nullable(named("String")),
+                    "The enhanced SMTP status code SendGrid recorded with the bounce, if known.",
+                ),
+            ),
+        ]),
+        result_type: named("bounce_classification"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct DeleteSuppressionsArgs {
+    pub emails: Option<Vec<String>>,
+    pub delete_all: Option<bool>,
+}
+
+pub const DELETE_BOUNCES: &str = "delete_bounces";
+pub const DELETE_BLOCKS: &str = "delete_blocks";
+pub const DELETE_SPAM_REPORTS: &str = "delete_spam_reports";
+pub const DELETE_INVALID_EMAILS: &str = "delete_invalid_emails";
+
+fn delete_suppressions(name: &str, list_description: &str) -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(name),
+        description: Some(format!("Removes addresses from the {list_description} suppression list, or clears the whole list")),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("emails"), argument(
+                nullable(array_of(named("String"))),
+                "The addresses to remove from the list.",
+            )),
+            (ArgumentName::from("delete_all"), argument(
+                nullable(named("Bool")),
+                "If true, every address is removed from the list. Cannot be combined with emails.",
+            )),
+        ]),
+        result_type: named("delete_suppressions_response"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeleteSuppressionsResponse {
+    pub deleted_all: bool,
+    pub emails: Option<Vec<String>>,
+}
+
+fn delete_suppressions_response() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "The response from removing addresses from a suppression list.",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("deleted_all"),
+                object_field(named("Bool"), "Whether the whole list was cleared."),
+            ),
+            (
+                FieldName::from("emails"),
+                object_field(
+                    nullable(array_of(named("String"))),
+                    "The addresses that were removed from the list.",
+                ),
+            ),
+        ]),
+    }
+}
+
+pub const GLOBAL_UNSUBSCRIBES_COLLECTION_NAME: &str = "global_unsubscribes";
+
+fn global_unsubscribes() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(GLOBAL_UNSUBSCRIBES_COLLECTION_NAME),
+        description: Some(String::from("The addresses that have unsubscribed from all mail. Filtering on created is applied as a date range by SendGrid.")),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("global_unsubscribe"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("global_unsubscribes_email"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("email")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn global_unsubscribe() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "An address that has unsubscribed from all mail",
+        )),
+        fields: BTreeMap::from([suppression_email_field(), suppression_created_field()]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddGlobalUnsubscribesArgs {
+    pub emails: Vec<String>,
+}
+
+pub const ADD_GLOBAL_UNSUBSCRIBES: &str = "add_global_unsubscribes";
+
+fn add_global_unsubscribes() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(ADD_GLOBAL_UNSUBSCRIBES),
+        description: Some(String::from(
+            "Adds addresses to the global unsubscribe list, so they will receive no further mail",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("emails"),
+            argument(array_of(named("String")), "The addresses to unsubscribe."),
+        )]),
+        result_type: named("global_unsubscribes_response"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailArgs {
+    pub email: String,
+}
+
+pub const DELETE_GLOBAL_UNSUBSCRIBE: &str = "delete_global_unsubscribe";
+
+fn delete_global_unsubscribe() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(DELETE_GLOBAL_UNSUBSCRIBE),
+        description: Some(String::from(
+            "Removes an address from the global unsubscribe list",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("email"),
+            argument(named("String"), "The address to resubscribe."),
+        )]),
+        result_type: named("global_unsubscribes_response"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GlobalUnsubscribesResponse {
+    pub emails: Vec<String>,
+}
+
+fn global_unsubscribes_response() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "The response from changing the global unsubscribe list.",
+        )),
+        fields: BTreeMap::from([(
+            FieldName::from("emails"),
+            object_field(
+                array_of(named("String")),
+                "The addresses that were added to or removed from the list.",
+            ),
+        )]),
+    }
+}
+
+pub const GET_SUPPRESSION_STATUS_FUNCTION_NAME: &str = "get_suppression_status";
+
+fn get_suppression_status() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GET_SUPPRESSION_STATUS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Reports whether an address is globally unsubscribed, suppressed in any unsubscribe group, bounced, blocked, spam-reported or invalid",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("email"),
+            argument(named("String"), "The address to look up."),
+        )]),
+        result_type: named("suppression_status"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SuppressionStatus {
+    pub email: String,
+    pub globally_unsubscribed: bool,
+    pub bounced: bool,
+    pub blocked: bool,
+    pub spam_reported: bool,
+    pub invalid: bool,
+    pub groups: Vec<sendgrid_api::GroupSuppression>,
+}
+
+fn suppression_status() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The suppression status of an address")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("email"),
+                object_field(named("String"), "The address that was looked up."),
+            ),
+            (
+                FieldName::from("globally_unsubscribed"),
+                object_field(
+                    named("Bool"),
+                    "Whether the address is on the global unsubscribe list.",
+                ),
+            ),
+            (
+                FieldName::from("bounced"),
+                object_field(named("Bool"), "Whether the address is on the bounces list."),
+            ),
+            (
+                FieldName::from("blocked"),
+                object_field(named("Bool"), "Whether the address is on the blocks list."),
+            ),
+            (
+                FieldName::from("spam_reported"),
+                object_field(
+                    named("Bool"),
+                    "Whether the address is on the spam reports list.",
+                ),
+            ),
+            (
+                FieldName::from("invalid"),
+                object_field(
+                    named("Bool"),
+                    "Whether the address is on the invalid emails list.",
+                ),
+            ),
+            (
+                FieldName::from("groups"),
+                object_field(
+                    array_of(named("group_suppression")),
+                    "Every unsubscribe group, and whether the address is suppressed in it.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn group_suppression() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "Whether an address is suppressed in an unsubscribe group",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("id"),
+                object_field(named("Int"), "The ID of the unsubscribe group."),
+            ),
+            (
+                FieldName::from("name"),
+                object_field(named("String"), "The name of the unsubscribe group."),
+            ),
+            (
+                FieldName::from("description"),
+                object_field(
+                    nullable(named("String")),
+                    "The description of the unsubscribe group.",
+                ),
+            ),
+            (
+                FieldName::from("is_default"),
+                object_field(
+                    nullable(named("Bool")),
+                    "Whether this is the default unsubscribe group.",
+                ),
+            ),
+            (
+                FieldName::from("suppressed"),
+                object_field(
+                    named("Bool"),
+                    "Whether the address is suppressed in the unsubscribe group.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RefreshSuppressionCacheArgs {
+    pub full: Option<bool>,
+}
+
+pub const REFRESH_SUPPRESSION_CACHE: &str = "refresh_suppression_cache";
+
+fn refresh_suppression_cache() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(REFRESH_SUPPRESSION_CACHE),
+        description: Some(String::from("Syncs the connector's local copy of the suppression lists with SendGrid straight away, rather than waiting for the next scheduled sync")),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("full"),
+            argument(
+                nullable(named("Bool")),
+                "If true (the default), the cache is rebuilt from the full suppression lists, which also drops addresses removed from a list outside the connector. If false, only suppressions created since the last sync are fetched.",
+            ),
+        )]),
+        result_type: named("suppression_cache_sync"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SuppressionCacheSync {
+    pub fetched: usize,
+    pub entries: usize,
+    pub synced_at: i64,
+}
+
+impl From<SyncSummary> for SuppressionCacheSync {
+    fn from(summary: SyncSummary) -> Self {
+        SuppressionCacheSync {
+            fetched: summary.fetched,
+            entries: summary.entries,
+            synced_at: unix_seconds(summary.synced_at) as i64,
+        }
+    }
+}
+
+fn suppression_cache_sync() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "The outcome of a sync of the suppression cache",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("fetched"),
+                object_field(
+                    named("Int"),
+                    "The number of suppressions fetched from SendGrid.",
+                ),
+            ),
+            (
+                FieldName::from("entries"),
+                object_field(
+                    named("Int"),
+                    "The number of suppressed addresses in the cache after the sync.",
+                ),
+            ),
+            (
+                FieldName::from("synced_at"),
+                object_field(named("Int"), "The unix timestamp of the sync."),
+            ),
+        ]),
+    }
+}
+
+pub const UNSUBSCRIBE_GROUPS_COLLECTION_NAME: &str = "unsubscribe_groups";
+
+fn unsubscribe_groups() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(UNSUBSCRIBE_GROUPS_COLLECTION_NAME),
+        description: Some(String::from(
+            "The unsubscribe groups that recipients can opt out of individually",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("unsubscribe_group"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("unsubscribe_group_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn unsubscribe_group() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An unsubscribe group")),
+        fields: BTreeMap::from([
+            (FieldName::from("id"), object_field(named("Int"), "The ID of the unsubscribe group. Use this as the asm group_id when sending mail.")),
+            (FieldName::from("name"), object_field(named("String"), "The name of the unsubscribe group, shown to recipients.")),
+            (FieldName::from("description"), object_field(nullable(named("String")), "The description of the unsubscribe group, shown to recipients.")),
+            (FieldName::from("is_default"), object_field(nullable(named("Bool")), "Whether this is the default unsubscribe group.")),
+            (FieldName::from("last_email_sent_at"), object_field(nullable(named("Int")), "The unix timestamp of the last mail sent with this unsubscribe group.")),
+            (FieldName::from("unsubscribes"), object_field(nullable(named("Int")), "The number of addresses suppressed in this unsubscribe group.")),
+        ]),
+    }
+}
+
+fn unsubscribe_group_reference() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A reference to an unsubscribe group")),
+        fields: BTreeMap::from([(
+            FieldName::from("id"),
+            object_field(named("Int"), "The ID of the unsubscribe group."),
+        )]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CreateUnsubscribeGroupArgs {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: Option<bool>,
+}
+
+pub const CREATE_UNSUBSCRIBE_GROUP: &str = "create_unsubscribe_group";
+
+fn create_unsubscribe_group() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(CREATE_UNSUBSCRIBE_GROUP),
+        description: Some(String::from("Creates an unsubscribe group")),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("name"),
+                argument(
+                    named("String"),
+                    "The name of the unsubscribe group, shown to recipients.",
+                ),
+            ),
+            (
+                ArgumentName::from("description"),
+                argument(
+                    nullable(named("String")),
+                    "The description of the unsubscribe group, shown to recipients.",
+                ),
+            ),
+            (
+                ArgumentName::from("is_default"),
+                argument(
+                    nullable(named("Bool")),
+                    "Whether this should become the default unsubscribe group.",
+                ),
+            ),
+        ]),
+        result_type: named("unsubscribe_group"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UpdateUnsubscribeGroupArgs {
+    pub id: u32,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub is_default: Option<bool>,
+}
+
+pub const UPDATE_UNSUBSCRIBE_GROUP: &str = "update_unsubscribe_group";
+
+fn update_unsubscribe_group() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(UPDATE_UNSUBSCRIBE_GROUP),
+        description: Some(String::from(
+            "Updates an unsubscribe group. Only the arguments that are provided are changed.",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("id"),
+                argument(named("Int"), "The ID of the unsubscribe group."),
+            ),
+            (
+                ArgumentName::from("name"),
+                argument(
+                    nullable(named("String")),
+                    "The name of the unsubscribe group, shown to recipients.",
+                ),
+            ),
+            (
+                ArgumentName::from("description"),
+                argument(
+                    nullable(named("String")),
+                    "The description of the unsubscribe group, shown to recipients.",
+                ),
+            ),
+            (
+                ArgumentName::from("is_default"),
+                argument(
+                    nullable(named("Bool")),
+                    "Whether this should become the default unsubscribe group.",
+                ),
+            ),
+        ]),
+        result_type: named("unsubscribe_group"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UnsubscribeGroupIdArgs {
+    pub id: u32,
+}
+
+pub const DELETE_UNSUBSCRIBE_GROUP: &str = "delete_unsubscribe_group";
+
+fn delete_unsubscribe_group() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(DELETE_UNSUBSCRIBE_GROUP),
+        description: Some(String::from("Deletes an unsubscribe group. Addresses suppressed in the group are added to the global unsubscribe list.")),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("id"),
+            argument(named("Int"), "The ID of the unsubscribe group."),
+        )]),
+        result_type: named("unsubscribe_group_reference"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GroupSuppressionsArgs {
+    pub group_id: u32,
+    pub emails: Vec<String>,
+}
+
+fn group_suppressions_args(emails_description: &str) -> BTreeMap<ArgumentName, ArgumentInfo> {
+    BTreeMap::from([
+        (
+            ArgumentName::from("group_id"),
+            argument(named("Int"), "The ID of the unsubscribe group."),
+        ),
+        (
+            ArgumentName::from("emails"),
+            argument(array_of(named("String")), emails_description),
+        ),
+    ])
+}
+
+pub const ADD_GROUP_SUPPRESSIONS: &str = "add_group_suppressions";
+
+fn add_group_suppressions() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(ADD_GROUP_SUPPRESSIONS),
+        description: Some(String::from("Suppresses addresses in an unsubscribe group, so they will receive no further mail sent with that group")),
+        arguments: group_suppressions_args("The addresses to suppress."),
+        result_type: named("group_suppressions_response"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GroupSuppressionArgs {
+    pub group_id: u32,
+    pub email: String,
+}
+
+pub const DELETE_GROUP_SUPPRESSION: &str = "delete_group_suppression";
+
+fn delete_group_suppression() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(DELETE_GROUP_SUPPRESSION),
+        description: Some(String::from(
+            "Removes the suppression of an address in an unsubscribe group",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("group_id"),
+                argument(named("Int"), "The ID of the unsubscribe group."),
+            ),
+            (
+                ArgumentName::from("email"),
+                argument(named("String"), "The address to resubscribe to the group."),
+            ),
+        ]),
+        result_type: named("group_suppressions_response"),
+    }
+}
+
+pub const SEARCH_GROUP_SUPPRESSIONS_FUNCTION_NAME: &str = "search_group_suppressions";
+
+fn search_group_suppressions() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(SEARCH_GROUP_SUPPRESSIONS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Finds which of the given addresses are suppressed in an unsubscribe group",
+        )),
+        arguments: group_suppressions_args("The addresses to look for."),
+        result_type: named("group_suppressions_response"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct GroupSuppressionsResponse {
+    pub group_id: u32,
+    pub emails: Vec<String>,
+}
+
+fn group_suppressions_response() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("Addresses suppressed in an unsubscribe group")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("group_id"),
+                object_field(named("Int"), "The ID of the unsubscribe group."),
+            ),
+            (
+                FieldName::from("emails"),
+                object_field(
+                    array_of(named("String")),
+                    "The addresses that were suppressed, resubscribed or found.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn named(name: &str) -> Type {
+    Type::Named {
+        name: TypeName::from(name),
+    }
+}
+
+fn nullable(underlying: Type) -> Type {
+    Type::Nullable {
+        underlying_type: Box::new(underlying),
+    }
+}
+
+fn array_of(element: Type) -> Type {
+    Type::Array {
+        element_type: Box::new(element),
+    }
+}
+
+fn object_field(r#type: Type, description: &str) -> ObjectField {
+    ObjectField {
+        r#type,
+        description: Some(String::from(description)),
+        arguments: BTreeMap::new(),
+    }
+}
+
+fn argument(argument_type: Type, description: &str) -> ArgumentInfo {
+    ArgumentInfo {
+        argument_type,
+        description: Some(String::from(description)),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ValidateEmailArgs {
+    pub email: String,
+    pub source: Option<String>,
+}
+
+pub const VALIDATE_EMAIL_FUNCTION_NAME: &str = "validate_email";
+
+fn validate_email() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(VALIDATE_EMAIL_FUNCTION_NAME),
+        description: Some(String::from(
+            "Checks whether an address is likely to receive mail, using the Email Address Validation API",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("email"),
+                argument(named("String"), "The address to validate."),
+            ),
+            (
+                ArgumentName::from("source"),
+                argument(
+                    nullable(named("String")),
+                    "A label for where the address came from, such as 'signup', recorded with the validation.",
+                ),
+            ),
+        ]),
+        result_type: named("email_validation"),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ValidateEmailsArgs {
+    pub emails: Vec<String>,
+    pub source: Option<String>,
+}
+
+pub const VALIDATE_EMAILS_FUNCTION_NAME: &str = "validate_emails";
+
+fn validate_emails() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(VALIDATE_EMAILS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Validates a list of addresses concurrently, returning the validations in the same order as the addresses",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("emails"),
+                argument(array_of(named("String")), "The addresses to validate."),
+            ),
+            (
+                ArgumentName::from("source"),
+                argument(
+                    nullable(named("String")),
+                    "A label for where the addresses came from, such as 'signup', recorded with each validation.",
+                ),
+            ),
+        ]),
+        result_type: array_of(named("email_validation")),
+    }
+}
+
+fn email_validation() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The result of validating an address")),
+        fields: BTreeMap::from([
+            (FieldName::from("email"), object_field(named("String"), "The address that was validated.")),
+            (FieldName::from("verdict"), object_field(named("String"), "Valid, Risky or Invalid.")),
+            (FieldName::from("score"), object_field(named("Float"), "The likelihood, from 0 to 1, that the address is valid.")),
+            (FieldName::from("local"), object_field(named("String"), "The part of the address before the '@'.")),
+            (FieldName::from("host"), object_field(named("String"), "The domain of the address.")),
+            (FieldName::from("suggestion"), object_field(nullable(named("String")), "A corrected domain, if the domain looks like a typo of a common one (for example gmail.com for gmial.com).")),
+            (FieldName::from("checks"), object_field(named("email_validation_checks"), "The individual checks that make up the verdict.")),
+            (FieldName::from("source"), object_field(nullable(named("String")), "The source label passed with the validation.")),
+            (FieldName::from("ip_address"), object_field(nullable(named("String")), "The IP address the validation was requested from.")),
+        ]),
+    }
+}
+
+fn email_validation_checks() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The checks made when validating an address")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("domain"),
+                object_field(
+                    named("email_domain_checks"),
+                    "Checks on the domain of the address.",
+                ),
+            ),
+            (
+                FieldName::from("local_part"),
+                object_field(
+                    named("email_local_part_checks"),
+                    "Checks on the part of the address before the '@'.",
+                ),
+            ),
+            (
+                FieldName::from("additional"),
+                object_field(
+                    named("email_additional_checks"),
+                    "Checks based on previous sends to the address.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn email_domain_checks() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("Checks on the domain of an address")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("has_valid_address_syntax"),
+                object_field(named("Bool"), "Whether the address is syntactically valid."),
+            ),
+            (
+                FieldName::from("has_mx_or_a_record"),
+                object_field(
+                    named("Bool"),
+                    "Whether the domain has an MX or A record to deliver to.",
+                ),
+            ),
+            (
+                FieldName::from("is_suspected_disposable_address"),
+                object_field(
+                    named("Bool"),
+                    "Whether the domain is a known disposable email provider.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn email_local_part_checks() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("Checks on the part of an address before the '@'")),
+        fields: BTreeMap::from([(
+            FieldName::from("is_suspected_role_address"),
+            object_field(named("Bool"), "Whether the address belongs to a role, such as admin@ or support@, rather than a person."),
+        )]),
+    }
+}
+
+fn email_additional_checks() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("Checks on an address based on previous sends")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("has_known_bounces"),
+                object_field(
+                    named("Bool"),
+                    "Whether mail to the address has bounced before.",
+                ),
+            ),
+            (
+                FieldName::from("has_suspected_bounces"),
+                object_field(
+                    named("Bool"),
+                    "Whether mail to the address is likely to bounce.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MakePreferenceLinkArgs {
+    pub email: String,
+    pub group_ids: Option<Vec<u32>>,
+    pub expires_in: Option<u32>,
+}
+
+pub const MAKE_PREFERENCE_LINK_FUNCTION_NAME: &str = "make_preference_link";
+
+fn make_preference_link() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(MAKE_PREFERENCE_LINK_FUNCTION_NAME),
+        description: Some(String::from(
+            "Creates a link to the preference center carrying a signed token that identifies the recipient and the unsubscribe groups they may manage",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("email"),
+                argument(named("String"), "The recipient the link is for."),
+            ),
+            (
+                ArgumentName::from("group_ids"),
+                argument(
+                    nullable(array_of(named("Int"))),
+                    "The unsubscribe groups the recipient may manage through the link.",
+                ),
+            ),
+            (
+                ArgumentName::from("expires_in"),
+                argument(
+                    nullable(named("Int")),
+                    "The number of seconds the link is valid for. If omitted, the link does not expire.",
+                ),
+            ),
+        ]),
+        result_type: named("preference_link"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PreferenceLink {
+    pub url: String,
+    pub token: String,
+    pub expires_at: Option<i64>,
+}
+
+fn preference_link() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A signed link to the preference center")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("url"),
+                object_field(
+                    named("String"),
+                    "The preference center URL, including the token.",
+                ),
+            ),
+            (
+                FieldName::from("token"),
+                object_field(named("String"), "The signed token."),
+            ),
+            (
+                FieldName::from("expires_at"),
+                object_field(
+                    nullable(named("Int")),
+                    "The unix timestamp the link expires at, or null if it does not expire.",
+                ),
+            ),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VerifyPreferenceTokenArgs {
+    pub token: String,
+}
+
+pub const VERIFY_PREFERENCE_TOKEN_FUNCTION_NAME: &str = "verify_preference_token";
+
+fn verify_preference_token() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(VERIFY_PREFERENCE_TOKEN_FUNCTION_NAME),
+        description: Some(String::from(
+            "Checks the signature and expiry of a token from a preference center link and returns who it was issued for",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("token"),
+            argument(named("String"), "The token from the link."),
+        )]),
+        result_type: named("preference_token_verification"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PreferenceTokenVerification {
+    pub valid: bool,
+    pub error: Option<String>,
+    pub email: Option<String>,
+    pub group_ids: Vec<u32>,
+    pub expires_at: Option<i64>,
+}
+
+fn preference_token_verification() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The result of verifying a preference token")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("valid"),
+                object_field(
+                    named("Bool"),
+                    "Whether the token is correctly signed and has not expired.",
+                ),
+            ),
+            (
+                FieldName::from("error"),
+                object_field(
+                    nullable(named("String")),
+                    "Why the token is not valid, if it is not.",
+                ),
+            ),
+            (
+                FieldName::from("email"),
+                object_field(
+                    nullable(named("String")),
+                    "The recipient the token was issued for, if it is valid.",
+                ),
+            ),
+            (
+                FieldName::from("group_ids"),
+                object_field(
+                    array_of(named("Int")),
+                    "The unsubscribe groups the recipient may manage, if the token is valid.",
+                ),
+            ),
+            (
+                FieldName::from("expires_at"),
+                object_field(
+                    nullable(named("Int")),
+                    "The unix timestamp the token expires at, if it is valid and expires.",
+                ),
+            ),
+        ]),
+    }
+}
+
+pub const MESSAGES_COLLECTION_NAME: &str = "messages";
+
+fn messages() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(MESSAGES_COLLECTION_NAME),
+        description: Some(String::from(
+            "The messages in the Email Activity feed. Filters on msg_id, from_email, to_email, subject, status, last_event_time, categories and unique_args are evaluated by SendGrid. Requires the Email Activity add-on.",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("message"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("message_msg_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("msg_id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn message() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("A message in the Email Activity feed")),
+        fields: BTreeMap::from([
+            (FieldName::from("msg_id"), object_field(named("String"), "The ID of the message.")),
+            (FieldName::from("from_email"), object_field(named("String"), "The address the message was sent from.")),
+            (FieldName::from("to_email"), object_field(named("String"), "The address the message was sent to.")),
+            (FieldName::from("subject"), object_field(named("String"), "The subject of the message.")),
+            (FieldName::from("status"), object_field(named("String"), "The status of the message: processed, delivered or not_delivered.")),
+            (FieldName::from("opens_count"), object_field(named("Int"), "The number of times the message was opened.")),
+            (FieldName::from("clicks_count"), object_field(named("Int"), "The number of times links in the message were clicked.")),
+            (FieldName::from("last_event_time"), object_field(named("Timestamp"), "When the most recent event for the message happened.")),
+            (FieldName::from("categories"), object_field(nullable(named("CategoryList")), "The categories of the message. Not returned when listing messages, but can be filtered on with _contains and a category.")),
+            (FieldName::from("unique_args"), object_field(nullable(named("UniqueArgs")), "The custom arguments of the message. Not returned when listing messages, but can be filtered on with _contains and an object of the string or number arguments to match.")),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GetMessageArgs {
+    pub msg_id: String,
+}
+
+pub const GET_MESSAGE_FUNCTION_NAME: &str = "get_message";
+
+fn get_message() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GET_MESSAGE_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves a message from the Email Activity feed with the timeline of its events. Requires the Email Activity add-on.",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("msg_id"),
+            argument(named("String"), "The ID of the message, as returned by the messages collection."),
+        )]),
+        result_type: named("message_detail"),
+    }
+}
+
+fn message_detail() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "A message in the Email Activity feed, with its events",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("msg_id"),
+                object_field(named("String"), "The ID of the message."),
+            ),
+            (
+                FieldName::from("from_email"),
+                object_field(named("String"), "The address the message was sent from."),
+            ),
+            (
+                FieldName::from("to_email"),
+                object_field(named("String"), "The address the message was sent to."),
+            ),
+            (
+                FieldName::from("subject"),
+                object_field(named("String"), "The subject of the message."),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    named("String"),
+                    "The status of the message: processed, delivered or not_delivered.",
+                ),
+            ),
+            (
+                FieldName::from("template_id"),
+                object_field(
+                    nullable(named("String")),
+                    "The ID of the template the message was sent with, if any.",
+                ),
+            ),
+            (
+                FieldName::from("asm_group_id"),
+                object_field(
+                    nullable(named("Int")),
+                    "The unsubscribe group the message was sent with, if any.",
+                ),
+            ),
+            (
+                FieldName::from("teammate"),
+                object_field(
+                    nullable(named("String")),
+                    "The teammate who sent the message, if any.",
+                ),
+            ),
+            (
+                FieldName::from("api_key_id"),
+                object_field(
+                    nullable(named("String")),
+                    "The ID of the API key the message was sent with.",
+                ),
+            ),
+            (
+                FieldName::from("originating_ip"),
+                object_field(
+                    nullable(named("String")),
+                    "The IP address the message was submitted to SendGrid from.",
+                ),
+            ),
+            (
+                FieldName::from("outbound_ip"),
+                object_field(
+                    nullable(named("String")),
+                    "The IP address SendGrid delivered the message from.",
+                ),
+            ),
+            (
+                FieldName::from("outbound_ip_type"),
+                object_field(
+                    nullable(named("String")),
+                    "Whether the outbound IP address is dedicated or shared.",
+                ),
+            ),
+            (
+                FieldName::from("categories"),
+                object_field(array_of(named("String")), "The categories of the message."),
+            ),
+            (
+                FieldName::from("unique_args"),
+                object_field(
+                    nullable(named("UniqueArgs")),
+                    "The custom arguments of the message.",
+                ),
+            ),
+            (
+                FieldName::from("events"),
+                object_field(
+                    array_of(named("message_event")),
+                    "The events of the message, oldest first.",
+                ),
+            ),
+        ]),
+    }
+}
+
+fn message_event() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An event in the timeline of a message")),
+        fields: BTreeMap::from([
+            (FieldName::from("event_name"), object_field(named("String"), "The kind of event, such as processed, delivered, open, click, bounce, deferred or dropped.")),
+            (FieldName::from("processed"), object_field(named("Timestamp"), "When the event happened.")),
+            (FieldName::from("reason"), object_field(nullable(named("String")), "Why the message bounced, was deferred or was dropped.")),
+            (FieldName::from("attempt_num"), object_field(nullable(named("Int")), "The delivery attempt the event is for, for deferrals.")),
+            (FieldName::from("url"), object_field(nullable(named("String")), "The URL that was clicked, for clicks.")),
+            (FieldName::from("bounce_type"), object_field(nullable(named("String")), "Whether a bounce was a bounce or a block.")),
+            (FieldName::from("http_user_agent"), object_field(nullable(named("String")), "The user agent of the recipient, for opens and clicks.")),
+            (FieldName::from("mx_server"), object_field(nullable(named("String")), "The mail server of the recipient, for deliveries.")),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GetDeliveryStatusArgs {
+    pub message_id: Option<String>,
+    pub batch_id: Option<String>,
+    pub custom_arg: Option<CustomArg>,
+}
+
+pub const GET_DELIVERY_STATUS_FUNCTION_NAME: &str = "get_delivery_status";
+
+fn get_delivery_status() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(GET_DELIVERY_STATUS_FUNCTION_NAME),
+        description: Some(String::from(
+            "Retrieves the delivery status of each recipient of a send, found by exactly one of its message ID, batch ID or a custom argument. Requires the Email Activity add-on.",
+        )),
+        arguments: BTreeMap::from([
+            (ArgumentName::from("message_id"), argument(nullable(named("String")), "The message ID returned by send_mail.")),
+            (ArgumentName::from("batch_id"), argument(nullable(named("String")), "The batch ID the mail was sent with. The mail is found by the batch_id custom argument that send_mail adds when tag_batch is true, so looking up a batch with no tagged mail fails unless the batch was paused or cancelled.")),
+            (ArgumentName::from("custom_arg"), argument(nullable(named("custom_arg")), "A custom argument the mail was sent with.")),
+        ]),
+        result_type: named("delivery_status_response"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeliveryStatusResponse {
+    pub batch_status: Option<&'static str>,
+    pub recipients: Vec<RecipientDeliveryStatus>,
+    pub complete: bool,
+}
+
+fn delivery_status_response() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The delivery status of the recipients of a send")),
+        fields: BTreeMap::from([
+            (FieldName::from("batch_status"), object_field(nullable(named("String")), "Whether the scheduled send of the batch is paused or cancelled, when looking up a batch. Null if the batch is neither.")),
+            (FieldName::from("recipients"), object_field(array_of(named("recipient_delivery_status")), "The status of each recipient of the send.")),
+            (FieldName::from("complete"), object_field(named("Bool"), "False if the send has more messages in the Email Activity feed than were listed (up to 10,000), or more recipients whose status needed the message's events than were looked up (up to 100). Those recipients' statuses come from the message summary instead, which reports any undelivered message as bounced.")),
+        ]),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RecipientDeliveryStatus {
+    pub email: String,
+    pub status: DeliveryStatus,
+    pub msg_id: String,
+    pub last_event_time: String,
+    pub reason: Option<String>,
+}
+
+/// How far a message to a recipient got, from queued to clicked
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Queued,
+    Deferred,
+    Dropped,
+    Bounced,
+    Delivered,
+    Opened,
+    Clicked,
+}
+
+fn recipient_delivery_status() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The delivery status of a recipient")),
+        fields: BTreeMap::from([
+            (FieldName::from("email"), object_field(named("String"), "The address of the recipient.")),
+            (FieldName::from("status"), object_field(named("String"), "The furthest the message to the recipient got: queued, deferred, dropped, bounced, delivered, opened or clicked.")),
+            (FieldName::from("msg_id"), object_field(named("String"), "The ID of the message to the recipient in the Email Activity feed.")),
+            (FieldName::from("last_event_time"), object_field(named("Timestamp"), "When the most recent event for the message happened.")),
+            (FieldName::from("reason"), object_field(nullable(named("String")), "Why the message was deferred, dropped or bounced.")),
+        ]),
+    }
+}
+
+pub const EMAIL_EVENTS_COLLECTION_NAME: &str = "email_events";
+
+fn email_events() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(EMAIL_EVENTS_COLLECTION_NAME),
+        description: Some(String::from(
+            "The events SendGrid has posted to the connector's Event Webhook route. Requires SENDGRID_EVENT_STORE_PATH to be set.",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("email_event"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("email_event_sg_event_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("sg_event_id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn email_event() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("An event received from SendGrid's Event Webhook")),
+        fields: BTreeMap::from([
+            (FieldName::from("sg_event_id"), object_field(named("String"), "The ID of the event.")),
+            (FieldName::from("sg_message_id"), object_field(nullable(named("String")), "The ID of the message the event is about, as in the msg_id of the messages collection.")),
+            (FieldName::from("email"), object_field(named("String"), "The lowercased address of the recipient. Addresses it is compared to are lowercased too.")),
+            (FieldName::from("event"), object_field(named("String"), "The type of event: processed, dropped, deferred, delivered, bounce, open, click, spamreport, unsubscribe, group_unsubscribe or group_resubscribe.")),
+            (FieldName::from("timestamp"), object_field(named("Timestamp"), "When the event happened.")),
+            (FieldName::from("categories"), object_field(nullable(named("CategoryList")), "The categories of the message.")),
+            (FieldName::from("reason"), object_field(nullable(named("String")), "Why the message was dropped, deferred or bounced.")),
+            (FieldName::from("status"), object_field(nullable(named("String")), "The SMTP status code of a bounce.")),
+            (FieldName::from("response"), object_field(nullable(named("String")), "The response of the receiving server to a delivery or deferral.")),
+            (FieldName::from("attempt"), object_field(nullable(named("Int")), "The number of delivery attempts made, on deferred events.")),
+            (FieldName::from("bounce_type"), object_field(nullable(named("String")), "Whether a bounce event was a bounce or a block.")),
+            (FieldName::from("url"), object_field(nullable(named("String")), "The URL that was clicked, on click events.")),
+            (FieldName::from("useragent"), object_field(nullable(named("String")), "The user agent that opened the message or clicked the link.")),
+            (FieldName::from("ip"), object_field(nullable(named("String")), "The IP address the message was sent from, or that opened it or clicked the link.")),
+            (FieldName::from("unique_args"), object_field(nullable(named("UniqueArgs")), "The custom arguments of the message.")),
+        ]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct VerifyEventWebhookArgs {
+    pub payload: String,
+    pub signature: String,
+    pub timestamp: String,
+}
+
+pub const VERIFY_EVENT_WEBHOOK_FUNCTION_NAME: &str = "verify_event_webhook";
+
+fn verify_event_webhook() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(VERIFY_EVENT_WEBHOOK_FUNCTION_NAME),
+        description: Some(String::from(
+            "Checks that a Signed Event Webhook request was signed by SendGrid with the configured verification key and that its timestamp is recent, for apps that receive the webhook themselves",
+        )),
+        arguments: BTreeMap::from([
+            (
+                ArgumentName::from("payload"),
+                argument(named("String"), "The body of the request, exactly as it was received."),
+            ),
+            (
+                ArgumentName::from("signature"),
+                argument(named("String"), "The X-Twilio-Email-Event-Webhook-Signature header of the request."),
+            ),
+            (
+                ArgumentName::from("timestamp"),
+                argument(named("String"), "The X-Twilio-Email-Event-Webhook-Timestamp header of the request."),
+            ),
+        ]),
+        result_type: named("event_webhook_verification"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EventWebhookVerification {
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+fn event_webhook_verification() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The result of verifying a Signed Event Webhook request")),
+        fields: BTreeMap::from([
+            (FieldName::from("valid"), object_field(named("Bool"), "Whether the request is correctly signed and its timestamp is within the tolerance.")),
+            (FieldName::from("error"), object_field(nullable(named("String")), "Why the request failed verification, if it did.")),
+        ]),
+    }
+}
+
+pub const CREATE_BATCH_ID: &str = "create_batch_id";
+
+fn create_batch_id() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(CREATE_BATCH_ID),
+        description: Some(String::from(
+            "Creates a batch ID, which can be passed to send_mail so that the scheduled send can be paused or cancelled",
+        )),
+        arguments: BTreeMap::new(),
+        result_type: named("mail_batch"),
+    }
+}
+
+fn mail_batch() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "A batch of mail that can be paused or cancelled together",
+        )),
+        fields: BTreeMap::from([(
+            FieldName::from("batch_id"),
+            object_field(named("String"), "The ID of the batch."),
+        )]),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BatchIdArgs {
+    pub batch_id: String,
+}
+
+pub const VALIDATE_BATCH_ID_FUNCTION_NAME: &str = "validate_batch_id";
+
+fn validate_batch_id() -> FunctionInfo {
+    FunctionInfo {
+        name: FunctionName::from(VALIDATE_BATCH_ID_FUNCTION_NAME),
+        description: Some(String::from(
+            "Checks whether a batch ID was created for the account and can be used to send mail",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("batch_id"),
+            argument(named("String"), "The batch ID to check."),
+        )]),
+        result_type: named("batch_id_validation"),
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchIdValidation {
+    pub batch_id: String,
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+fn batch_id_validation() -> ObjectType {
+    ObjectType {
+        description: Some(String::from("The result of validating a batch ID")),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("batch_id"),
+                object_field(named("String"), "The batch ID that was checked."),
+            ),
+            (
+                FieldName::from("valid"),
+                object_field(named("Bool"), "Whether the batch ID is valid."),
+            ),
+            (
+                FieldName::from("error"),
+                object_field(
+                    nullable(named("String")),
+                    "Why the batch ID is not valid, if it is not.",
+                ),
+            ),
+        ]),
+    }
+}
+
+pub const SCHEDULED_SENDS_COLLECTION_NAME: &str = "scheduled_sends";
+
+fn scheduled_sends() -> CollectionInfo {
+    CollectionInfo {
+        name: CollectionName::from(SCHEDULED_SENDS_COLLECTION_NAME),
+        description: Some(String::from(
+            "The batches whose scheduled send has been paused or cancelled",
+        )),
+        arguments: BTreeMap::new(),
+        collection_type: ObjectTypeName::from("scheduled_send"),
+        uniqueness_constraints: BTreeMap::from([(
+            String::from("scheduled_send_batch_id"),
+            UniquenessConstraint {
+                unique_columns: vec![FieldName::from("batch_id")],
+            },
+        )]),
+        foreign_keys: BTreeMap::new(),
+    }
+}
+
+fn scheduled_send() -> ObjectType {
+    ObjectType {
+        description: Some(String::from(
+            "A batch whose scheduled send has been paused or cancelled",
+        )),
+        fields: BTreeMap::from([
+            (
+                FieldName::from("batch_id"),
+                object_field(named("String"), "The ID of the batch."),
+            ),
+            (
+                FieldName::from("status"),
+                object_field(
+                    named("String"),
+                    "Whether the send is paused (pause) or cancelled (cancel).",
+                ),
+            ),
+        ]),
+    }
+}
+
+pub const PAUSE_SCHEDULED_SEND: &str = "pause_scheduled_send";
+pub const CANCEL_SCHEDULED_SEND: &str = "cancel_scheduled_send";
+
+fn scheduled_send_procedure(name: &str, description: &str) -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(name),
+        description: Some(String::from(description)),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("batch_id"),
+            argument(named("String"), "The batch ID the mail was sent with."),
+        )]),
+        result_type: named("scheduled_send"),
+    }
+}
+
+pub const RESUME_SCHEDULED_SEND: &str = "resume_scheduled_send";
+
+fn resume_scheduled_send() -> ProcedureInfo {
+    ProcedureInfo {
+        name: ProcedureName::from(RESUME_SCHEDULED_SEND),
+        description: Some(String::from(
+            "Resumes the scheduled send of a paused or cancelled batch, so that its mail is sent at its send time, or straight away if that has passed",
+        )),
+        arguments: BTreeMap::from([(
+            ArgumentName::from("batch_id"),
+            argument(named("String"), "The batch ID the mail was sent with."),
+        )]),
+        result_type: named("mail_batch"),
     }
 }
@@ -2,7 +2,7 @@ use std::{collections::BTreeMap, fmt::Display};
 
 use reqwest::StatusCode;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -108,7 +108,7 @@ impl Display for ErrorItem {
     }
 }
 
-const SENDGRID_BASE_URL: &str = "https://api.sendgrid.com";
+pub const SENDGRID_BASE_URL: &str = "https://api.sendgrid.com";
 
 #[derive(Clone, Debug, Error)]
 pub enum RequestError<Err> {
@@ -147,6 +147,21 @@ pub struct SendMailRequest {
     pub send_at: Option<u32>,
     pub batch_id: Option<String>,
     pub asm: Option<UnsubscriptionSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mail_settings: Option<MailSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_args: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MailSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox_mode: Option<MailSetting>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MailSetting {
+    pub enable: bool,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -189,71 +204,1492 @@ pub struct UnsubscriptionSettings {
     pub groups_to_display: Option<Vec<u32>>,
 }
 
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportContactsRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_ids: Option<Vec<String>>,
+    pub file_type: String,
+    pub field_mappings: Vec<Option<String>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ImportContactsResponse {
+    pub job_id: String,
+    pub upload_uri: String,
+    pub upload_headers: Vec<UploadHeader>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct UploadHeader {
+    pub header: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContactImportJob {
+    pub id: String,
+    pub status: String,
+    pub job_type: Option<String>,
+    pub results: Option<ContactImportResults>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContactImportResults {
+    pub requested_count: Option<u32>,
+    pub created_count: Option<u32>,
+    pub updated_count: Option<u32>,
+    pub deleted_count: Option<u32>,
+    pub errored_count: Option<u32>,
+    pub errors_url: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportContactsRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_ids: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExportContactsResponse {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ContactExportJob {
+    pub id: String,
+    pub status: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub completed_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub urls: Option<Vec<String>>,
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PaginationMetadata {
+    pub next: Option<String>,
+}
+
+impl PaginationMetadata {
+    /// The page token of the next page of results, extracted from the `next` link
+    pub fn next_page_token(&self) -> Option<String> {
+        let next = reqwest::Url::parse(self.next.as_ref()?).ok()?;
+        next.query_pairs()
+            .find(|(name, _)| name == "page_token")
+            .map(|(_, value)| value.into_owned())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListDesignsParams {
+    pub page_size: u32,
+    pub page_token: Option<String>,
+}
+
+impl ListDesignsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = vec![
+            (String::from("page_size"), self.page_size.to_string()),
+            (String::from("summary"), String::from("true")),
+        ];
+        if let Some(page_token) = &self.page_token {
+            params.push((String::from("page_token"), page_token.clone()))
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ListDesignsResponse {
+    pub result: Vec<DesignSummary>,
+    #[serde(rename = "_metadata")]
+    pub metadata: Option<PaginationMetadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DesignSummary {
+    pub id: String,
+    pub name: String,
+    pub generate_plain_content: Option<bool>,
+    pub subject: Option<String>,
+    pub editor: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub categories: Option<Vec<String>>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Design {
+    pub id: String,
+    pub name: String,
+    pub html_content: Option<String>,
+    pub plain_content: Option<String>,
+    pub generate_plain_content: Option<bool>,
+    pub subject: Option<String>,
+    pub editor: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub categories: Option<Vec<String>>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DuplicateDesignRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListVerifiedSendersParams {
+    pub limit: Option<u32>,
+    pub last_seen_id: Option<u32>,
+}
+
+impl ListVerifiedSendersParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = Vec::<(String, String)>::new();
+        if let Some(limit) = self.limit {
+            params.push((String::from("limit"), limit.to_string()))
+        }
+        if let Some(last_seen_id) = self.last_seen_id {
+            params.push((String::from("lastSeenID"), last_seen_id.to_string()))
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ListVerifiedSendersResponse {
+    pub results: Vec<VerifiedSender>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VerifiedSender {
+    pub id: u32,
+    pub nickname: String,
+    pub from_email: String,
+    pub from_name: Option<String>,
+    pub reply_to: String,
+    pub reply_to_name: Option<String>,
+    pub address: Option<String>,
+    pub address2: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub zip: Option<String>,
+    pub country: Option<String>,
+    pub verified: bool,
+    pub locked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CreateVerifiedSenderRequest {
+    pub nickname: String,
+    pub from_email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_name: Option<String>,
+    pub reply_to: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to_name: Option<String>,
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address2: Option<String>,
+    pub city: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zip: Option<String>,
+    pub country: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListAuthenticatedDomainsParams {
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl ListAuthenticatedDomainsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        vec![
+            (String::from("limit"), self.limit.to_string()),
+            (String::from("offset"), self.offset.to_string()),
+        ]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuthenticatedDomain {
+    pub id: u32,
+    pub domain: String,
+    pub subdomain: Option<String>,
+    pub valid: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MarketingStatsParams {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub timezone: Option<String>,
+    pub aggregated_by: Option<String>,
+    pub group_by: Option<Vec<String>>,
+}
+
+impl MarketingStatsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = Vec::<(String, String)>::new();
+        if let Some(start_date) = &self.start_date {
+            params.push((String::from("start_date"), start_date.clone()))
+        }
+        if let Some(end_date) = &self.end_date {
+            params.push((String::from("end_date"), end_date.clone()))
+        }
+        if let Some(timezone) = &self.timezone {
+            params.push((String::from("timezone"), timezone.clone()))
+        }
+        if let Some(aggregated_by) = &self.aggregated_by {
+            params.push((String::from("aggregated_by"), aggregated_by.clone()))
+        }
+        if let Some(group_by) = &self.group_by {
+            params.push((String::from("group_by"), group_by.join(",")))
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MarketingStatsResponse {
+    pub results: Vec<MarketingStatsResult>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MarketingStatsResult {
+    pub id: String,
+    pub ab_variation: Option<String>,
+    pub ab_phase: Option<String>,
+    pub step_id: Option<String>,
+    pub aggregation: String,
+    pub stats: MarketingStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MarketingStats {
+    #[serde(default)]
+    pub requests: u32,
+    #[serde(default)]
+    pub delivered: u32,
+    #[serde(default)]
+    pub opens: u32,
+    #[serde(default)]
+    pub unique_opens: u32,
+    #[serde(default)]
+    pub clicks: u32,
+    #[serde(default)]
+    pub unique_clicks: u32,
+    #[serde(default)]
+    pub bounces: u32,
+    #[serde(default)]
+    pub bounce_drops: u32,
+    #[serde(default)]
+    pub invalid_emails: u32,
+    #[serde(default)]
+    pub unsubscribes: u32,
+    #[serde(default)]
+    pub spam_reports: u32,
+    #[serde(default)]
+    pub spam_report_drops: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailStatsParams {
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub aggregated_by: Option<String>,
+}
+
+impl EmailStatsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = vec![(String::from("start_date"), self.start_date.clone())];
+        if let Some(end_date) = &self.end_date {
+            params.push((String::from("end_date"), end_date.clone()))
+        }
+        if let Some(aggregated_by) = &self.aggregated_by {
+            params.push((String::from("aggregated_by"), aggregated_by.clone()))
+        }
+        params
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailStatsSumsParams {
+    pub stats: EmailStatsParams,
+    pub sort_by_metric: Option<String>,
+    pub sort_by_direction: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl EmailStatsSumsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = self.stats.to_query_params();
+        if let Some(sort_by_metric) = &self.sort_by_metric {
+            params.push((String::from("sort_by_metric"), sort_by_metric.clone()))
+        }
+        if let Some(sort_by_direction) = &self.sort_by_direction {
+            params.push((String::from("sort_by_direction"), sort_by_direction.clone()))
+        }
+        if let Some(limit) = self.limit {
+            params.push((String::from("limit"), limit.to_string()))
+        }
+        if let Some(offset) = self.offset {
+            params.push((String::from("offset"), offset.to_string()))
+        }
+        params
+    }
+}
+
+/// What email statistics are broken down by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailStatsBreakdown {
+    Categories,
+    Subusers,
+    MailboxProviders,
+    Browsers,
+    Devices,
+    Clients,
+    Geo,
+}
+
+impl EmailStatsBreakdown {
+    /// The query parameter that restricts the statistics to some values of the breakdown, if
+    /// the endpoint has one
+    pub fn filter_param(self) -> Option<&'static str> {
+        match self {
+            EmailStatsBreakdown::Categories => Some("categories"),
+            EmailStatsBreakdown::Subusers => Some("subusers"),
+            EmailStatsBreakdown::MailboxProviders => Some("mailbox_providers"),
+            EmailStatsBreakdown::Browsers => Some("browsers"),
+            EmailStatsBreakdown::Geo => Some("country"),
+            EmailStatsBreakdown::Devices | EmailStatsBreakdown::Clients => None,
+        }
+    }
+}
+
+impl Display for EmailStatsBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EmailStatsBreakdown::Categories => write!(f, "categories"),
+            EmailStatsBreakdown::Subusers => write!(f, "subusers"),
+            EmailStatsBreakdown::MailboxProviders => write!(f, "mailbox_providers"),
+            EmailStatsBreakdown::Browsers => write!(f, "browsers"),
+            EmailStatsBreakdown::Devices => write!(f, "devices"),
+            EmailStatsBreakdown::Clients => write!(f, "clients"),
+            EmailStatsBreakdown::Geo => write!(f, "geo"),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailStatsDay {
+    pub date: String,
+    pub stats: Vec<EmailStatsEntry>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmailStatsEntry {
+    /// The category, subuser, mailbox provider, browser, device, client or region the metrics are
+    /// for, when the statistics are broken down
+    pub name: Option<String>,
+    pub metrics: EmailMetrics,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EmailMetrics {
+    #[serde(default)]
+    pub requests: u32,
+    #[serde(default)]
+    pub processed: u32,
+    #[serde(default)]
+    pub delivered: u32,
+    #[serde(default)]
+    pub deferred: u32,
+    #[serde(default)]
+    pub opens: u32,
+    #[serde(default)]
+    pub unique_opens: u32,
+    #[serde(default)]
+    pub clicks: u32,
+    #[serde(default)]
+    pub unique_clicks: u32,
+    #[serde(default)]
+    pub bounces: u32,
+    #[serde(default)]
+    pub bounce_drops: u32,
+    #[serde(default)]
+    pub blocks: u32,
+    #[serde(default)]
+    pub invalid_emails: u32,
+    #[serde(default)]
+    pub spam_reports: u32,
+    #[serde(default)]
+    pub spam_report_drops: u32,
+    #[serde(default)]
+    pub unsubscribes: u32,
+    #[serde(default)]
+    pub unsubscribe_drops: u32,
+}
+
+/// Sums the metrics of several days or groups. Large accounts can exceed the counters' range, so
+/// the sums saturate rather than overflow.
+impl std::ops::AddAssign<&EmailMetrics> for EmailMetrics {
+    fn add_assign(&mut self, other: &EmailMetrics) {
+        self.requests = self.requests.saturating_add(other.requests);
+        self.processed = self.processed.saturating_add(other.processed);
+        self.delivered = self.delivered.saturating_add(other.delivered);
+        self.deferred = self.deferred.saturating_add(other.deferred);
+        self.opens = self.opens.saturating_add(other.opens);
+        self.unique_opens = self.unique_opens.saturating_add(other.unique_opens);
+        self.clicks = self.clicks.saturating_add(other.clicks);
+        self.unique_clicks = self.unique_clicks.saturating_add(other.unique_clicks);
+        self.bounces = self.bounces.saturating_add(other.bounces);
+        self.bounce_drops = self.bounce_drops.saturating_add(other.bounce_drops);
+        self.blocks = self.blocks.saturating_add(other.blocks);
+        self.invalid_emails = self.invalid_emails.saturating_add(other.invalid_emails);
+        self.spam_reports = self.spam_reports.saturating_add(other.spam_reports);
+        self.spam_report_drops = self
+            .spam_report_drops
+            .saturating_add(other.spam_report_drops);
+        self.unsubscribes = self.unsubscribes.saturating_add(other.unsubscribes);
+        self.unsubscribe_drops = self
+            .unsubscribe_drops
+            .saturating_add(other.unsubscribe_drops);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MarketingStatsKind {
+    Automations,
+    Singlesends,
+}
+
+impl Display for MarketingStatsKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarketingStatsKind::Automations => write!(f, "automations"),
+            MarketingStatsKind::Singlesends => write!(f, "singlesends"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionList {
+    Bounces,
+    Blocks,
+    SpamReports,
+    InvalidEmails,
+    GlobalUnsubscribes,
+}
+
+impl Display for SuppressionList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuppressionList::Bounces => write!(f, "bounces"),
+            SuppressionList::Blocks => write!(f, "blocks"),
+            SuppressionList::SpamReports => write!(f, "spam_reports"),
+            SuppressionList::InvalidEmails => write!(f, "invalid_emails"),
+            SuppressionList::GlobalUnsubscribes => write!(f, "unsubscribes"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ListSuppressionsParams {
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+impl ListSuppressionsParams {
+    pub fn to_query_params(&self) -> Vec<(String, String)> {
+        let mut params = vec![
+            (String::from("limit"), self.limit.to_string()),
+            (String::from("offset"), self.offset.to_string()),
+        ];
+        if let Some(start_time) = self.start_time {
+            params.push((String::from("start_time"), start_time.to_string()))
+        }
+        if let Some(end_time) = self.end_time {
+            params.push((String::from("end_time"), end_time.to_string()))
+        }
+        params
+    }
+}
+
+/// An entry in one of the suppression lists. Not every list records every field: spam reports
+/// and global unsubscribes have no reason, and only bounces and blocks have a status.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Suppression {
+    pub email: String,
+    pub created: i64,
+    pub reason: Option<String>,
+    pub status: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DeleteSuppressionsRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delete_all: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emails: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecipientEmails {
+    pub recipient_emails: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GlobalUnsubscribeLookup {
+    pub recipient_email: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AddressGroupSuppressions {
+    pub suppressions: Vec<GroupSuppression>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupSuppression {
+    pub id: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: Option<bool>,
+    pub suppressed: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ListMessagesResponse {
+    pub messages: Vec<Message>,
+}
+
+/// A message in the Email Activity feed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Message {
+    pub msg_id: String,
+    pub from_email: String,
+    pub to_email: String,
+    pub subject: String,
+    /// One of processed, delivered or not_delivered
+    pub status: String,
+    #[serde(default)]
+    pub opens_count: u32,
+    #[serde(default)]
+    pub clicks_count: u32,
+    pub last_event_time: String,
+    /// Only returned for a single message, but can be filtered on when listing messages
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
+    /// Only returned for a single message, but can be filtered on when listing messages
+    #[serde(default)]
+    pub unique_args: Option<serde_json::Value>,
+}
+
+/// A message in the Email Activity feed, with its events
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MessageDetail {
+    pub msg_id: String,
+    pub from_email: String,
+    pub to_email: String,
+    pub subject: String,
+    pub status: String,
+    pub template_id: Option<String>,
+    pub asm_group_id: Option<u32>,
+    pub teammate: Option<String>,
+    pub api_key_id: Option<String>,
+    pub originating_ip: Option<String>,
+    pub outbound_ip: Option<String>,
+    pub outbound_ip_type: Option<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub unique_args: Option<serde_json::Value>,
+    #[serde(default)]
+    pub events: Vec<MessageEvent>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MessageEvent {
+    /// The kind of event, such as processed, delivered, open, click or bounce
+    pub event_name: String,
+    /// When the event happened
+    pub processed: String,
+    pub reason: Option<String>,
+    pub attempt_num: Option<u32>,
+    pub url: Option<String>,
+    pub bounce_type: Option<String>,
+    pub http_user_agent: Option<String>,
+    pub mx_server: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MailBatch {
+    pub batch_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScheduledSend {
+    pub batch_id: String,
+    pub status: ScheduledSendStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduledSendStatus {
+    Pause,
+    Cancel,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScheduledSendStatusRequest {
+    pub status: ScheduledSendStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnsubscribeGroup {
+    pub id: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_default: Option<bool>,
+    pub last_email_sent_at: Option<i64>,
+    pub unsubscribes: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UnsubscribeGroupRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_default: Option<bool>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ValidateEmailRequest {
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct ValidateEmailResponse {
+    result: EmailValidation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailValidation {
+    pub email: String,
+    pub verdict: EmailVerdict,
+    pub score: f64,
+    pub local: String,
+    pub host: String,
+    pub suggestion: Option<String>,
+    pub checks: EmailValidationChecks,
+    pub source: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmailVerdict {
+    Valid,
+    Risky,
+    Invalid,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EmailValidationChecks {
+    pub domain: DomainChecks,
+    pub local_part: LocalPartChecks,
+    pub additional: AdditionalChecks,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DomainChecks {
+    pub has_valid_address_syntax: bool,
+    pub has_mx_or_a_record: bool,
+    pub is_suspected_disposable_address: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocalPartChecks {
+    pub is_suspected_role_address: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AdditionalChecks {
+    pub has_known_bounces: bool,
+    pub has_suspected_bounces: bool,
+}
+
 pub async fn invoke_list_function_templates(
     http_client: &reqwest::Client,
-    SendGridApiKey(api_key): &SendGridApiKey,
+    base_url: &str,
+    api_key: &SendGridApiKey,
     params: &ListTransactionalTemplatesParams,
 ) -> Result<ListTransactionalTemplatesResponse, RequestError<ErrorResponse>> {
-    let response = http_client
-        .get(format!("{SENDGRID_BASE_URL}/v3/templates"))
-        .query(&(params.to_query_params()))
-        .header("Authorization", format!("Bearer {api_key}"))
+    let request = http_client
+        .get(format!("{base_url}/v3/templates"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+/// Sends mail, returning the message ID SendGrid assigned to it, which prefixes the IDs of the
+/// messages in the Email Activity feed
+pub async fn invoke_send_mail(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &SendMailRequest,
+) -> Result<Option<String>, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/mail/send"))
+        .json(request);
+    let response = authorize_and_send(request, api_key).await?;
+
+    if response.status() == StatusCode::ACCEPTED {
+        Ok(response
+            .headers()
+            .get("X-Message-Id")
+            .and_then(|message_id| message_id.to_str().ok())
+            .map(str::to_owned))
+    } else {
+        Err(error_from_response(response).await)
+    }
+}
+
+pub async fn invoke_import_contacts(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &ImportContactsRequest,
+) -> Result<ImportContactsResponse, RequestError<ErrorResponse>> {
+    let request = http_client
+        .put(format!("{base_url}/v3/marketing/contacts/imports"))
+        .json(request);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+/// Uploads the contacts file to the URL returned by [`invoke_import_contacts`]. The upload URL is
+/// pre-signed, so the SendGrid API key must not be sent with it.
+pub async fn invoke_upload_contacts_file(
+    http_client: &reqwest::Client,
+    upload_uri: &str,
+    upload_headers: &[UploadHeader],
+    content: Vec<u8>,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = upload_headers
+        .iter()
+        .fold(http_client.put(upload_uri), |request, upload_header| {
+            request.header(upload_header.header.as_str(), upload_header.value.as_str())
+        })
+        .body(content);
+
+    let response = request
         .send()
         .await
         .map_err(|err| RequestError::OtherError {
             error: err.to_string(),
         })?;
 
-    match response.status() {
-        StatusCode::OK => response
-            .json::<ListTransactionalTemplatesResponse>()
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(RequestError::UnexpectedStatusCode {
+            status_code: response.status(),
+        })
+    }
+}
+
+pub async fn invoke_get_contact_import(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    job_id: &str,
+) -> Result<ContactImportJob, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/marketing/contacts/imports"), job_id)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_export_contacts(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &ExportContactsRequest,
+) -> Result<ExportContactsResponse, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/marketing/contacts/exports"))
+        .json(request);
+    send_request(request, api_key, StatusCode::ACCEPTED).await
+}
+
+pub async fn invoke_get_contact_export(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    export_id: &str,
+) -> Result<ContactExportJob, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(
+        &format!("{base_url}/v3/marketing/contacts/exports"),
+        export_id,
+    )?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_list_designs(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    params: &ListDesignsParams,
+) -> Result<ListDesignsResponse, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/designs"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_design(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    design_id: &str,
+) -> Result<Design, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/designs"), design_id)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_duplicate_design(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    design_id: &str,
+    request: &DuplicateDesignRequest,
+) -> Result<Design, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/designs"), design_id)?;
+    let request = http_client.post(url).json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_list_verified_senders(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    params: &ListVerifiedSendersParams,
+) -> Result<ListVerifiedSendersResponse, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/verified_senders"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_create_verified_sender(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &CreateVerifiedSenderRequest,
+) -> Result<VerifiedSender, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/verified_senders"))
+        .json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_resend_sender_verification(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    sender_id: u32,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client.post(format!("{base_url}/v3/verified_senders/resend/{sender_id}"));
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_delete_verified_sender(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    sender_id: u32,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client.delete(format!("{base_url}/v3/verified_senders/{sender_id}"));
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_list_authenticated_domains(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    params: &ListAuthenticatedDomainsParams,
+) -> Result<Vec<AuthenticatedDomain>, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/whitelabel/domains"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_marketing_stats(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    kind: MarketingStatsKind,
+    id: &str,
+    params: &MarketingStatsParams,
+) -> Result<MarketingStatsResponse, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/marketing/stats/{kind}"), id)?;
+    let request = http_client.get(url).query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_global_stats(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    params: &EmailStatsParams,
+) -> Result<Vec<EmailStatsDay>, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/stats"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_breakdown_stats(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    breakdown: EmailStatsBreakdown,
+    names: &[String],
+    params: &EmailStatsParams,
+) -> Result<Vec<EmailStatsDay>, RequestError<ErrorResponse>> {
+    let mut query_params = params.to_query_params();
+    if let Some(filter_param) = breakdown.filter_param() {
+        query_params.extend(
+            names
+                .iter()
+                .map(|name| (filter_param.to_owned(), name.clone())),
+        );
+    }
+    let request = http_client
+        .get(format!("{base_url}/v3/{breakdown}/stats"))
+        .query(&query_params);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_breakdown_stats_sums(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    breakdown: EmailStatsBreakdown,
+    params: &EmailStatsSumsParams,
+) -> Result<EmailStatsDay, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/{breakdown}/stats/sums"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_export_marketing_stats(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    kind: MarketingStatsKind,
+    ids: &[String],
+    timezone: Option<&str>,
+) -> Result<String, RequestError<ErrorResponse>> {
+    let mut query_params = vec![(String::from("ids"), ids.join(","))];
+    if let Some(timezone) = timezone {
+        query_params.push((String::from("timezone"), timezone.to_owned()))
+    }
+    let request = http_client
+        .get(format!("{base_url}/v3/marketing/stats/{kind}/export"))
+        .query(&query_params);
+    send_request_for_text(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_list_suppressions(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    list: SuppressionList,
+    params: &ListSuppressionsParams,
+) -> Result<Vec<Suppression>, RequestError<ErrorResponse>> {
+    let request = http_client
+        .get(format!("{base_url}/v3/suppression/{list}"))
+        .query(&(params.to_query_params()));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_suppression(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    list: SuppressionList,
+    email: &str,
+) -> Result<Vec<Suppression>, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/suppression/{list}"), email)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_delete_suppressions(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    list: SuppressionList,
+    request: &DeleteSuppressionsRequest,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client
+        .delete(format!("{base_url}/v3/suppression/{list}"))
+        .json(request);
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_add_global_unsubscribes(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &RecipientEmails,
+) -> Result<RecipientEmails, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/asm/suppressions/global"))
+        .json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_delete_global_unsubscribe(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    email: &str,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/asm/suppressions/global"), email)?;
+    let request = http_client.delete(url);
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_get_global_unsubscribe(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    email: &str,
+) -> Result<GlobalUnsubscribeLookup, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/asm/suppressions/global"), email)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_address_group_suppressions(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    email: &str,
+) -> Result<AddressGroupSuppressions, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/asm/suppressions"), email)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_list_messages(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    query: Option<&str>,
+    limit: u32,
+) -> Result<ListMessagesResponse, RequestError<ErrorResponse>> {
+    let mut query_params = vec![(String::from("limit"), limit.to_string())];
+    if let Some(query) = query {
+        query_params.push((String::from("query"), query.to_owned()))
+    }
+    let request = http_client
+        .get(format!("{base_url}/v3/messages"))
+        .query(&query_params);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_get_message(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    msg_id: &str,
+) -> Result<MessageDetail, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/messages"), msg_id)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_create_batch_id(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+) -> Result<MailBatch, RequestError<ErrorResponse>> {
+    let request = http_client.post(format!("{base_url}/v3/mail/batch"));
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_validate_batch_id(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    batch_id: &str,
+) -> Result<MailBatch, RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(&format!("{base_url}/v3/mail/batch"), batch_id)?;
+    let request = http_client.get(url);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_list_scheduled_sends(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+) -> Result<Vec<ScheduledSend>, RequestError<ErrorResponse>> {
+    let request = http_client.get(format!("{base_url}/v3/user/scheduled_sends"));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+/// Gets the status of a batch's scheduled send, or nothing if the batch has not been paused or
+/// cancelled, for which SendGrid answers not found
+pub async fn invoke_get_scheduled_send(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    batch_id: &str,
+) -> Result<Option<ScheduledSend>, RequestError<ErrorResponse>> {
+    let request = http_client.get(scheduled_send_url(base_url, batch_id)?);
+    match send_request::<Vec<ScheduledSend>>(request, api_key, StatusCode::OK).await {
+        Ok(scheduled_sends) => Ok(scheduled_sends.into_iter().next()),
+        Err(RequestError::UnexpectedStatusCode { status_code })
+            if status_code == StatusCode::NOT_FOUND =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+pub async fn invoke_create_scheduled_send(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    scheduled_send: &ScheduledSend,
+) -> Result<ScheduledSend, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/user/scheduled_sends"))
+        .json(scheduled_send);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_update_scheduled_send(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    batch_id: &str,
+    request: &ScheduledSendStatusRequest,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client
+        .patch(scheduled_send_url(base_url, batch_id)?)
+        .json(request);
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_delete_scheduled_send(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    batch_id: &str,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client.delete(scheduled_send_url(base_url, batch_id)?);
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_list_unsubscribe_groups(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+) -> Result<Vec<UnsubscribeGroup>, RequestError<ErrorResponse>> {
+    let request = http_client.get(format!("{base_url}/v3/asm/groups"));
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_create_unsubscribe_group(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &UnsubscribeGroupRequest,
+) -> Result<UnsubscribeGroup, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/asm/groups"))
+        .json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_update_unsubscribe_group(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    group_id: u32,
+    request: &UnsubscribeGroupRequest,
+) -> Result<UnsubscribeGroup, RequestError<ErrorResponse>> {
+    let request = http_client
+        .patch(format!("{base_url}/v3/asm/groups/{group_id}"))
+        .json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_delete_unsubscribe_group(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    group_id: u32,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let request = http_client.delete(format!("{base_url}/v3/asm/groups/{group_id}"));
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_add_group_suppressions(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    group_id: u32,
+    request: &RecipientEmails,
+) -> Result<RecipientEmails, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/asm/groups/{group_id}/suppressions"))
+        .json(request);
+    send_request(request, api_key, StatusCode::CREATED).await
+}
+
+pub async fn invoke_delete_group_suppression(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    group_id: u32,
+    email: &str,
+) -> Result<(), RequestError<ErrorResponse>> {
+    let url = url_with_path_segment(
+        &format!("{base_url}/v3/asm/groups/{group_id}/suppressions"),
+        email,
+    )?;
+    let request = http_client.delete(url);
+    send_request_without_response_body(request, api_key, StatusCode::NO_CONTENT).await
+}
+
+pub async fn invoke_search_group_suppressions(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    group_id: u32,
+    request: &RecipientEmails,
+) -> Result<Vec<String>, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!(
+            "{base_url}/v3/asm/groups/{group_id}/suppressions/search"
+        ))
+        .json(request);
+    send_request(request, api_key, StatusCode::OK).await
+}
+
+pub async fn invoke_validate_email(
+    http_client: &reqwest::Client,
+    base_url: &str,
+    api_key: &SendGridApiKey,
+    request: &ValidateEmailRequest,
+) -> Result<EmailValidation, RequestError<ErrorResponse>> {
+    let request = http_client
+        .post(format!("{base_url}/v3/validations/email"))
+        .json(request);
+    send_request::<ValidateEmailResponse>(request, api_key, StatusCode::OK)
+        .await
+        .map(|response| response.result)
+}
+
+/// The URL of a batch's scheduled send
+fn scheduled_send_url(
+    base_url: &str,
+    batch_id: &str,
+) -> Result<reqwest::Url, RequestError<ErrorResponse>> {
+    url_with_path_segment(&format!("{base_url}/v3/user/scheduled_sends"), batch_id)
+}
+
+/// Appends a percent-encoded path segment, such as an email address, to a URL. An empty, "." or
+/// ".." segment is rejected, as the URL would address the collection the segment should be an item
+/// of, or its parent.
+fn url_with_path_segment(
+    url: &str,
+    segment: &str,
+) -> Result<reqwest::Url, RequestError<ErrorResponse>> {
+    if matches!(segment, "" | "." | "..") {
+        return Err(RequestError::OtherError {
+            error: format!("'{segment}' is not a valid ID"),
+        });
+    }
+    let mut url = reqwest::Url::parse(url).map_err(|err| RequestError::OtherError {
+        error: err.to_string(),
+    })?;
+    url.path_segments_mut()
+        .map_err(|_| RequestError::OtherError {
+            error: String::from("Cannot append a path segment to the base URL"),
+        })?
+        .push(segment);
+    Ok(url)
+}
+
+async fn send_request<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    api_key: &SendGridApiKey,
+    success_status_code: StatusCode,
+) -> Result<T, RequestError<ErrorResponse>> {
+    let response = authorize_and_send(request, api_key).await?;
+
+    if response.status() == success_status_code {
+        response
+            .json::<T>()
             .await
             .map_err(|err| RequestError::OtherError {
                 error: err.to_string(),
-            }),
-        StatusCode::BAD_REQUEST => {
-            let result = response.json::<ErrorResponse>().await;
-            match result {
-                Ok(err) => Err(RequestError::ApiError { error: err }),
-                Err(other) => Err(RequestError::OtherError {
-                    error: other.to_string(),
-                }),
-            }
-        }
-        other_code => Err(RequestError::UnexpectedStatusCode {
-            status_code: other_code,
-        }),
+            })
+    } else {
+        Err(error_from_response(response).await)
     }
 }
 
-pub async fn invoke_send_mail(
-    http_client: &reqwest::Client,
-    SendGridApiKey(api_key): &SendGridApiKey,
-    request: &SendMailRequest,
+async fn send_request_for_text(
+    request: reqwest::RequestBuilder,
+    api_key: &SendGridApiKey,
+    success_status_code: StatusCode,
+) -> Result<String, RequestError<ErrorResponse>> {
+    let response = authorize_and_send(request, api_key).await?;
+
+    if response.status() == success_status_code {
+        response
+            .text()
+            .await
+            .map_err(|err| RequestError::OtherError {
+                error: err.to_string(),
+            })
+    } else {
+        Err(error_from_response(response).await)
+    }
+}
+
+async fn send_request_without_response_body(
+    request: reqwest::RequestBuilder,
+    api_key: &SendGridApiKey,
+    success_status_code: StatusCode,
 ) -> Result<(), RequestError<ErrorResponse>> {
-    let response = http_client
-        .post(format!("{SENDGRID_BASE_URL}/v3/mail/send"))
-        .json(request)
+    let response = authorize_and_send(request, api_key).await?;
+
+    if response.status() == success_status_code {
+        Ok(())
+    } else {
+        Err(error_from_response(response).await)
+    }
+}
+
+async fn authorize_and_send(
+    request: reqwest::RequestBuilder,
+    SendGridApiKey(api_key): &SendGridApiKey,
+) -> Result<reqwest::Response, RequestError<ErrorResponse>> {
+    request
         .header("Authorization", format!("Bearer {api_key}"))
         .send()
         .await
         .map_err(|err| RequestError::OtherError {
             error: err.to_string(),
-        })?;
+        })
+}
 
+async fn error_from_response(response: reqwest::Response) -> RequestError<ErrorResponse> {
     match response.status() {
-        StatusCode::ACCEPTED => Ok(()),
-        StatusCode::BAD_REQUEST => {
-            let result = response.json::<ErrorResponse>().await;
-            match result {
-                Ok(err) => Err(RequestError::ApiError { error: err }),
-                Err(other) => Err(RequestError::OtherError {
-                    error: other.to_string(),
-                }),
-            }
-        }
-        other_code => Err(RequestError::UnexpectedStatusCode {
+        StatusCode::BAD_REQUEST => match response.json::<ErrorResponse>().await {
+            Ok(err) => RequestError::ApiError { error: err },
+            Err(other) => RequestError::OtherError {
+                error: other.to_string(),
+            },
+        },
+        other_code => RequestError::UnexpectedStatusCode {
             status_code: other_code,
-        }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_segments_cannot_escape_their_resource() {
+        let url = url_with_path_segment(
+            "https://api.sendgrid.com/v3/messages",
+            "../../api_keys/abc?x=1#y",
+        )
+        .unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://api.sendgrid.com/v3/messages/..%2F..%2Fapi_keys%2Fabc%3Fx=1%23y"
+        );
+        for segment in ["", ".", ".."] {
+            assert!(
+                url_with_path_segment("https://api.sendgrid.com/v3/messages", segment).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn scheduled_send_urls_reject_batch_ids_that_address_the_collection() {
+        assert_eq!(
+            scheduled_send_url("https://api.sendgrid.com", "batch-1")
+                .unwrap()
+                .as_str(),
+            "https://api.sendgrid.com/v3/user/scheduled_sends/batch-1"
+        );
+        assert_eq!(
+            scheduled_send_url("https://api.sendgrid.com", "../batch/1")
+                .unwrap()
+                .as_str(),
+            "https://api.sendgrid.com/v3/user/scheduled_sends/..%2Fbatch%2F1"
+        );
+        for batch_id in ["", ".", ".."] {
+            assert!(scheduled_send_url("https://api.sendgrid.com", batch_id).is_err());
+        }
+    }
+
+    #[test]
+    fn summed_email_metrics_saturate() {
+        let mut total = EmailMetrics {
+            requests: u32::MAX - 1,
+            delivered: 10,
+            ..EmailMetrics::default()
+        };
+        total += &EmailMetrics {
+            requests: 5,
+            delivered: 20,
+            ..EmailMetrics::default()
+        };
+        assert_eq!(total.requests, u32::MAX);
+        assert_eq!(total.delivered, 30);
     }
 }
@@ -0,0 +1,515 @@
+use ndc_sdk::connector;
+use prometheus::{Gauge, IntCounter, IntGauge, Registry};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::configuration;
+use super::sendgrid_api::{self, ListSuppressionsParams, SuppressionList};
+
+const SUPPRESSION_LISTS: [SuppressionList; 5] = [
+    SuppressionList::Bounces,
+    SuppressionList::Blocks,
+    SuppressionList::SpamReports,
+    SuppressionList::InvalidEmails,
+    SuppressionList::GlobalUnsubscribes,
+];
+
+const SYNC_PAGE_SIZE: u32 = 500;
+
+/// Every this many syncs, the background task does a full sync instead of an incremental one, to
+/// drop addresses that were removed from a list outside the connector
+const FULL_SYNC_EVERY: u32 = 24;
+
+/// An in-memory index of the addresses on the account's suppression lists, kept up to date by a
+/// background task so that suppression lookups do not need to call the SendGrid API
+#[derive(Clone, Debug)]
+pub struct SuppressionCache {
+    index: Arc<RwLock<SuppressionIndex>>,
+    /// How long after the last successful sync the cache is still used for lookups
+    max_age: Duration,
+    metrics: SuppressionCacheMetrics,
+}
+
+#[derive(Debug, Default)]
+struct SuppressionIndex {
+    /// The lists each suppressed address is on, keyed by the lowercased address
+    entries: HashMap<String, Vec<SuppressionList>>,
+    /// The most recent creation time seen on each list, from which the next incremental sync
+    /// continues
+    latest_created: HashMap<SuppressionList, i64>,
+    last_synced_at: Option<SystemTime>,
+    /// The number of syncs that are fetching suppressions from SendGrid
+    syncs_in_progress: usize,
+    /// The changes the connector made while a sync was fetching, which are re-applied on top of
+    /// the fetched suppressions so that they are not lost
+    changes_during_sync: Vec<IndexChange>,
+}
+
+/// A change the connector made to a suppression list
+#[derive(Clone, Debug)]
+enum IndexChange {
+    Insert(String, SuppressionList),
+    Remove(String, SuppressionList),
+    Clear(SuppressionList),
+}
+
+#[derive(Clone, Debug)]
+struct SuppressionCacheMetrics {
+    entries: IntGauge,
+    last_sync_timestamp: Gauge,
+    age: Gauge,
+    sync_failures: IntCounter,
+}
+
+/// The outcome of a sync of the suppression cache
+#[derive(Clone, Copy, Debug)]
+pub struct SyncSummary {
+    /// The number of suppressions fetched from SendGrid during the sync
+    pub fetched: usize,
+    /// The number of suppressed addresses in the cache after the sync
+    pub entries: usize,
+    pub synced_at: SystemTime,
+}
+
+impl SuppressionCache {
+    pub fn new(
+        metrics_registry: &mut Registry,
+        max_age: Duration,
+    ) -> connector::Result<SuppressionCache> {
+        let metrics = SuppressionCacheMetrics {
+            entries: IntGauge::new(
+                "sendgrid_suppression_cache_entries",
+                "The number of suppressed addresses in the suppression cache",
+            )
+            .map_err(metrics_error)?,
+            last_sync_timestamp: Gauge::new(
+                "sendgrid_suppression_cache_last_sync_timestamp_seconds",
+                "The unix time the suppression cache was last synced with SendGrid",
+            )
+            .map_err(metrics_error)?,
+            age: Gauge::new(
+                "sendgrid_suppression_cache_age_seconds",
+                "The number of seconds since the suppression cache was last synced with SendGrid",
+            )
+            .map_err(metrics_error)?,
+            sync_failures: IntCounter::new(
+                "sendgrid_suppression_cache_sync_failures_total",
+                "The number of suppression cache syncs that failed",
+            )
+            .map_err(metrics_error)?,
+        };
+
+        metrics_registry
+            .register(Box::new(metrics.entries.clone()))
+            .map_err(metrics_error)?;
+        metrics_registry
+            .register(Box::new(metrics.last_sync_timestamp.clone()))
+            .map_err(metrics_error)?;
+        metrics_registry
+            .register(Box::new(metrics.age.clone()))
+            .map_err(metrics_error)?;
+        metrics_registry
+            .register(Box::new(metrics.sync_failures.clone()))
+            .map_err(metrics_error)?;
+
+        Ok(SuppressionCache {
+            index: Arc::new(RwLock::new(SuppressionIndex::default())),
+            max_age,
+            metrics,
+        })
+    }
+
+    /// Starts a background task that fully syncs the cache and then keeps it up to date with
+    /// syncs at the given interval, most of which are incremental
+    pub fn start_sync_task(
+        &self,
+        http_client: reqwest::Client,
+        configuration: configuration::SendGridConfiguration,
+        interval: Duration,
+    ) {
+        let cache = self.clone();
+        tokio::spawn(async move {
+            let mut syncs_since_full = FULL_SYNC_EVERY;
+            loop {
+                let full = syncs_since_full >= FULL_SYNC_EVERY;
+                match cache.sync(&http_client, &configuration, full).await {
+                    Ok(_) if full => syncs_since_full = 1,
+                    Ok(_) => syncs_since_full += 1,
+                    Err(err) => {
+                        cache.metrics.sync_failures.inc();
+                        tracing::warn!(error = ?err, "Failed to sync the suppression cache");
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Pulls suppressions from SendGrid into the cache. A full sync rebuilds the cache from every
+    /// list, which also drops addresses that have since been removed from a list; an incremental
+    /// sync only fetches suppressions created since the last sync. Changes the connector makes
+    /// while the suppressions are being fetched are re-applied afterwards.
+    pub async fn sync(
+        &self,
+        http_client: &reqwest::Client,
+        configuration: &configuration::SendGridConfiguration,
+        full: bool,
+    ) -> connector::Result<SyncSummary> {
+        let (sync_in_progress, latest_created) = {
+            let mut index = self.write_index();
+            let latest_created = if full {
+                HashMap::new()
+            } else {
+                index.latest_created.clone()
+            };
+            (
+                SyncInProgress::start(&self.index, &mut index),
+                latest_created,
+            )
+        };
+
+        let fetched =
+            fetch_all_suppressions_since(http_client, configuration, &latest_created).await?;
+
+        let mut index = self.write_index();
+        let changes = sync_in_progress.finish(&mut index);
+        let fetched_count = fetched
+            .iter()
+            .map(|(_, suppressions)| suppressions.len())
+            .sum();
+
+        let synced_at = SystemTime::now();
+        if full {
+            index.entries.clear();
+            index.latest_created.clear();
+        }
+        for (list, suppressions) in fetched {
+            for suppression in suppressions {
+                index.insert(&suppression.email, list);
+                let latest = index
+                    .latest_created
+                    .entry(list)
+                    .or_insert(suppression.created);
+                *latest = (*latest).max(suppression.created);
+            }
+        }
+        for change in changes {
+            index.apply(change);
+        }
+        index.last_synced_at = Some(synced_at);
+
+        let entries = index.entries.len();
+        self.metrics.entries.set(entries as i64);
+        self.metrics
+            .last_sync_timestamp
+            .set(unix_seconds(synced_at));
+        self.metrics.age.set(0.0);
+
+        Ok(SyncSummary {
+            fetched: fetched_count,
+            entries,
+            synced_at,
+        })
+    }
+
+    /// The suppression lists an address is on, or `None` if the cache has not been synced yet or
+    /// was last synced longer ago than its maximum age, in which case the lookup has to be made
+    /// with the SendGrid API
+    pub fn lookup(&self, email: &str) -> Option<Vec<SuppressionList>> {
+        let index = self.read_index();
+        let age = index.last_synced_at?.elapsed().unwrap_or_default();
+        if age > self.max_age {
+            return None;
+        }
+        Some(
+            index
+                .entries
+                .get(&email.to_lowercase())
+                .cloned()
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Records an address being added to a list by the connector, so that the cache reflects it
+    /// before the next sync
+    pub fn insert(&self, email: &str, list: SuppressionList) {
+        self.record(IndexChange::Insert(email.to_lowercase(), list));
+    }
+
+    /// Records an address being removed from a list by the connector. Removals made outside the
+    /// connector are only picked up by a full sync.
+    pub fn remove(&self, email: &str, list: SuppressionList) {
+        self.record(IndexChange::Remove(email.to_lowercase(), list));
+    }
+
+    /// Records every address being removed from a list by the connector
+    pub fn clear(&self, list: SuppressionList) {
+        self.record(IndexChange::Clear(list));
+    }
+
+    fn record(&self, change: IndexChange) {
+        let mut index = self.write_index();
+        if index.syncs_in_progress > 0 {
+            index.changes_during_sync.push(change.clone());
+        }
+        index.apply(change);
+        self.metrics.entries.set(index.entries.len() as i64);
+    }
+
+    /// Updates the metrics that depend on the current time
+    pub fn update_metrics(&self) {
+        if let Some(last_synced_at) = self.read_index().last_synced_at {
+            let age = last_synced_at.elapsed().unwrap_or_default();
+            self.metrics.age.set(age.as_secs_f64());
+        }
+    }
+
+    fn read_index(&self) -> std::sync::RwLockReadGuard<'_, SuppressionIndex> {
+        self.index
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write_index(&self) -> std::sync::RwLockWriteGuard<'_, SuppressionIndex> {
+        self.index
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SuppressionIndex {
+    fn insert(&mut self, email: &str, list: SuppressionList) {
+        let lists = self.entries.entry(email.to_lowercase()).or_default();
+        if !lists.contains(&list) {
+            lists.push(list);
+        }
+    }
+
+    fn end_sync(&mut self) {
+        self.syncs_in_progress -= 1;
+        if self.syncs_in_progress == 0 {
+            self.changes_during_sync.clear();
+        }
+    }
+
+    fn apply(&mut self, change: IndexChange) {
+        match change {
+            IndexChange::Insert(email, list) => self.insert(&email, list),
+            IndexChange::Remove(email, list) => {
+                if let Some(lists) = self.entries.get_mut(&email) {
+                    lists.retain(|existing| *existing != list);
+                    if lists.is_empty() {
+                        self.entries.remove(&email);
+                    }
+                }
+            }
+            IndexChange::Clear(list) => self.entries.retain(|_, lists| {
+                lists.retain(|existing| *existing != list);
+                !lists.is_empty()
+            }),
+        }
+    }
+}
+
+/// Marks a sync as fetching until it finishes or is dropped, so that a sync that fails or is
+/// cancelled does not leave the cache recording changes
+struct SyncInProgress<'a> {
+    index: &'a RwLock<SuppressionIndex>,
+    first_change: usize,
+    finished: bool,
+}
+
+impl<'a> SyncInProgress<'a> {
+    fn start(lock: &'a RwLock<SuppressionIndex>, index: &mut SuppressionIndex) -> Self {
+        index.syncs_in_progress += 1;
+        SyncInProgress {
+            index: lock,
+            first_change: index.changes_during_sync.len(),
+            finished: false,
+        }
+    }
+
+    /// Ends the sync, returning the changes made while it was fetching
+    fn finish(mut self, index: &mut SuppressionIndex) -> Vec<IndexChange> {
+        let changes = index.changes_during_sync[self.first_change..].to_vec();
+        index.end_sync();
+        self.finished = true;
+        changes
+    }
+}
+
+impl Drop for SyncInProgress<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.index
+                .write()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .end_sync();
+        }
+    }
+}
+
+async fn fetch_all_suppressions_since(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    latest_created: &HashMap<SuppressionList, i64>,
+) -> connector::Result<Vec<(SuppressionList, Vec<sendgrid_api::Suppression>)>> {
+    let mut fetched = vec![];
+    for list in SUPPRESSION_LISTS {
+        let suppressions = fetch_suppressions_since(
+            http_client,
+            configuration,
+            list,
+            latest_created.get(&list).copied(),
+        )
+        .await?;
+        fetched.push((list, suppressions));
+    }
+    Ok(fetched)
+}
+
+async fn fetch_suppressions_since(
+    http_client: &reqwest::Client,
+    configuration: &configuration::SendGridConfiguration,
+    list: SuppressionList,
+    start_time: Option<i64>,
+) -> connector::Result<Vec<sendgrid_api::Suppression>> {
+    let mut suppressions = vec![];
+    loop {
+        let params = ListSuppressionsParams {
+            start_time,
+            end_time: None,
+            limit: SYNC_PAGE_SIZE,
+            offset: suppressions.len() as u32,
+        };
+        let page = sendgrid_api::invoke_list_suppressions(
+            http_client,
+            &configuration.sendgrid_api_base_url,
+            &configuration.sendgrid_api_key,
+            list,
+            &params,
+        )
+        .await
+        .map_err(|err| connector::ErrorResponse::from(err.to_string()))?;
+
+        let is_last_page = page.len() < SYNC_PAGE_SIZE as usize;
+        suppressions.extend(page);
+        if is_last_page {
+            return Ok(suppressions);
+        }
+    }
+}
+
+pub fn unix_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn metrics_error(err: prometheus::Error) -> connector::ErrorResponse {
+    connector::ErrorResponse::from(format!(
+        "Failed to register the suppression cache metrics: {err}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        extract::{Path, State},
+        routing::get,
+        Json, Router,
+    };
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::sendgrid_api::SendGridApiKey;
+
+    /// Serves the suppression lists on an unused local port, with `ada@example.com` on the
+    /// bounces list, and returns the stand-in's base URL. While the bounces list is being
+    /// served, the connector removes Ada from it and adds Grace to the global unsubscribes.
+    fn start_stand_in(cache: SuppressionCache) -> String {
+        let router = Router::new()
+            .route(
+                "/v3/suppression/:list",
+                get(
+                    |State(cache): State<SuppressionCache>, Path(list): Path<String>| async move {
+                        if list != "bounces" {
+                            return Json(json!([]));
+                        }
+                        cache.remove("ada@example.com", SuppressionList::Bounces);
+                        cache.insert("grace@example.com", SuppressionList::GlobalUnsubscribes);
+                        Json(json!([{ "email": "ada@example.com", "created": 1700000000 }]))
+                    },
+                ),
+            )
+            .with_state(cache);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+        base_url
+    }
+
+    fn test_configuration(base_url: &str) -> configuration::SendGridConfiguration {
+        let Ok(api_key) = SendGridApiKey::new("test-api-key") else {
+            panic!("The API key is not blank");
+        };
+        configuration::SendGridConfiguration {
+            sendgrid_api_key: api_key,
+            sendgrid_validation_api_key: None,
+            sendgrid_api_base_url: base_url.to_owned(),
+            sendgrid_upload_base_url: None,
+            suppression_cache_refresh_seconds: Some(60),
+            suppression_cache_max_age_seconds: Some(180),
+            recipient_safety: configuration::RecipientSafetyConfiguration::default(),
+            preference_links: None,
+            event_webhook: None,
+            event_webhook_verification: None,
+            configuration_dir: PathBuf::from("."),
+        }
+    }
+
+    #[tokio::test]
+    async fn changes_made_during_a_full_sync_are_kept() {
+        let cache = SuppressionCache::new(&mut Registry::new(), Duration::from_secs(180)).unwrap();
+        let base_url = start_stand_in(cache.clone());
+
+        let summary = cache
+            .sync(
+                &reqwest::Client::new(),
+                &test_configuration(&base_url),
+                true,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(summary.fetched, 1);
+        assert_eq!(cache.lookup("ada@example.com"), Some(vec![]));
+        assert_eq!(
+            cache.lookup("grace@example.com"),
+            Some(vec![SuppressionList::GlobalUnsubscribes])
+        );
+        assert!(cache.read_index().changes_during_sync.is_empty());
+    }
+
+    #[test]
+    fn a_cache_older_than_its_maximum_age_is_not_used() {
+        let cache = SuppressionCache::new(&mut Registry::new(), Duration::from_secs(180)).unwrap();
+        cache.insert("ada@example.com", SuppressionList::Bounces);
+        assert_eq!(cache.lookup("ada@example.com"), None);
+
+        cache.write_index().last_synced_at = Some(SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(
+            cache.lookup("ada@example.com"),
+            Some(vec![SuppressionList::Bounces])
+        );
+
+        cache.write_index().last_synced_at = Some(SystemTime::now() - Duration::from_secs(240));
+        assert_eq!(cache.lookup("ada@example.com"), None);
+    }
+}
@@ -0,0 +1,175 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+
+use super::preference_links::unix_now;
+
+pub const SIGNATURE_HEADER: &str = "X-Twilio-Email-Event-Webhook-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Twilio-Email-Event-Webhook-Timestamp";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    InvalidPublicKey,
+    MissingHeader(&'static str),
+    MalformedSignature,
+    MalformedTimestamp,
+    OutsideTolerance { tolerance_seconds: u64 },
+    BadSignature,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::InvalidPublicKey => write!(
+                f,
+                "The verification key is not a base64 encoded ECDSA public key"
+            ),
+            SignatureError::MissingHeader(header) => write!(f, "The {header} header is missing"),
+            SignatureError::MalformedSignature => {
+                write!(f, "The signature is not a base64 encoded ECDSA signature")
+            }
+            SignatureError::MalformedTimestamp => {
+                write!(f, "The timestamp is not a unix time in seconds")
+            }
+            SignatureError::OutsideTolerance { tolerance_seconds } => write!(
+                f,
+                "The timestamp is more than {tolerance_seconds} seconds from the current time, so the events may be replayed"
+            ),
+            SignatureError::BadSignature => {
+                write!(f, "The signature does not match the payload and timestamp")
+            }
+        }
+    }
+}
+
+/// Parses the verification key SendGrid shows for a Signed Event Webhook, which is a base64
+/// encoded DER public key
+pub fn parse_public_key(public_key: &str) -> Result<VerifyingKey, SignatureError> {
+    STANDARD
+        .decode(public_key.trim())
+        .ok()
+        .and_then(|der| VerifyingKey::from_public_key_der(&der).ok())
+        .ok_or(SignatureError::InvalidPublicKey)
+}
+
+/// Checks that the timestamp is within the tolerance of the current time, so that old requests
+/// cannot be replayed, and that the signature is SendGrid's ECDSA signature of the timestamp
+/// followed by the payload, exactly as they were received
+pub fn verify_signature(
+    public_key: &str,
+    payload: &[u8],
+    signature: &str,
+    timestamp: &str,
+    tolerance_seconds: u64,
+) -> Result<(), SignatureError> {
+    let public_key = parse_public_key(public_key)?;
+
+    let signed_at = timestamp
+        .trim()
+        .parse::<i64>()
+        .map_err(|_| SignatureError::MalformedTimestamp)?;
+    // The timestamp is chosen by the sender, so the difference must not overflow
+    if unix_now().abs_diff(signed_at) > tolerance_seconds {
+        return Err(SignatureError::OutsideTolerance { tolerance_seconds });
+    }
+
+    let signature = STANDARD
+        .decode(signature.trim())
+        .ok()
+        .and_then(|der| Signature::from_der(&der).ok())
+        .ok_or(SignatureError::MalformedSignature)?;
+    let signed_content = [timestamp.as_bytes(), payload].concat();
+    public_key
+        .verify(&signed_content, &signature)
+        .map_err(|_| SignatureError::BadSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::{signature::Signer, SigningKey};
+    use p256::pkcs8::EncodePublicKey;
+
+    use super::*;
+
+    const PAYLOAD: &[u8] = br#"[{"email":"ada@example.com","event":"delivered"}]"#;
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32].into()).unwrap()
+    }
+
+    fn public_key() -> String {
+        let der = signing_key().verifying_key().to_public_key_der().unwrap();
+        STANDARD.encode(der.as_bytes())
+    }
+
+    fn sign(timestamp: &str, payload: &[u8]) -> String {
+        let signature: Signature = signing_key().sign(&[timestamp.as_bytes(), payload].concat());
+        STANDARD.encode(signature.to_der().as_bytes())
+    }
+
+    #[test]
+    fn signed_requests_verify() {
+        let timestamp = unix_now().to_string();
+        let signature = sign(&timestamp, PAYLOAD);
+        assert_eq!(
+            verify_signature(&public_key(), PAYLOAD, &signature, &timestamp, 300),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn changed_payloads_and_timestamps_are_rejected() {
+        let timestamp = unix_now().to_string();
+        let signature = sign(&timestamp, PAYLOAD);
+        assert_eq!(
+            verify_signature(&public_key(), b"[]", &signature, &timestamp, 300),
+            Err(SignatureError::BadSignature)
+        );
+        let other_timestamp = (unix_now() - 1).to_string();
+        assert_eq!(
+            verify_signature(&public_key(), PAYLOAD, &signature, &other_timestamp, 300),
+            Err(SignatureError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn timestamps_outside_the_tolerance_are_rejected() {
+        for timestamp in [
+            (unix_now() - 301).to_string(),
+            (unix_now() + 301).to_string(),
+            i64::MIN.to_string(),
+            i64::MAX.to_string(),
+        ] {
+            let signature = sign(&timestamp, PAYLOAD);
+            assert_eq!(
+                verify_signature(&public_key(), PAYLOAD, &signature, &timestamp, 300),
+                Err(SignatureError::OutsideTolerance {
+                    tolerance_seconds: 300
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        let timestamp = unix_now().to_string();
+        assert_eq!(
+            verify_signature("not a key", PAYLOAD, "", &timestamp, 300),
+            Err(SignatureError::InvalidPublicKey)
+        );
+        assert_eq!(
+            verify_signature(&public_key(), PAYLOAD, "", "yesterday", 300),
+            Err(SignatureError::MalformedTimestamp)
+        );
+        assert_eq!(
+            verify_signature(
+                &public_key(),
+                PAYLOAD,
+                "bm90IGEgc2lnbmF0dXJl",
+                &timestamp,
+                300
+            ),
+            Err(SignatureError::MalformedSignature)
+        );
+    }
+}